@@ -2,12 +2,26 @@ use std::time::Duration;
 use std::thread;
 use std::sync::mpsc::{
     channel,
+    Sender,
     TryRecvError,
 };
 
 use failure::Error;
 use futures::sync::mpsc::unbounded;
 
+use eternalreckoning_core::net::capture::Capture;
+use eternalreckoning_core::net::transport::{
+    NetworkConditions,
+    ServerAddress,
+};
+use eternalreckoning_core::util::config::{
+    ConfigLoader,
+    Validate,
+    Validator,
+};
+use eternalreckoning_core::util::configwatcher::ConfigWatcher;
+use eternalreckoning_core::util::shutdown::Shutdown;
+
 use crate::{
     eventloop,
     iohandler,
@@ -16,15 +30,30 @@ use crate::{
         Renderer,
         window::Window,
     },
-    simulation::build_simulation,
+    simulation::{
+        build_simulation,
+        event::Event,
+        SimulationConfig,
+    },
     util::config,
 };
 
+/// How often the configuration file is checked for changes
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct ClientConfig {
+    /// `host:port` or `udp://host:port` for UDP, `ws://host:port/path` for WebSocket
     pub server_address: String,
     pub tick_rate: u64,
+    /// late ticks simulated back to back before the rest are skipped
+    pub max_catch_up_ticks: u32,
+    pub player_name: String,
+    /// records every packet sent and received to this file
+    pub capture_file: Option<String>,
+    /// simulated latency, loss, duplication and reordering
+    pub network_conditions: NetworkConditions,
 }
 
 impl Default for ClientConfig {
@@ -32,14 +61,41 @@ impl Default for ClientConfig {
         ClientConfig {
             server_address: "127.0.0.1:6142".to_string(),
             tick_rate: 60,
+            max_catch_up_ticks: 5,
+            player_name: "Player".to_string(),
+            capture_file: None,
+            network_conditions: NetworkConditions::default(),
         }
     }
 }
 
-pub fn main(config: config::Config) -> Result<(), Error> {
+impl Validate for ClientConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check(
+            "server-address",
+            self.server_address.parse::<ServerAddress>().is_ok(),
+            "must be host:port, udp://host:port or ws://host:port/path"
+        );
+        validator.check(
+            "tick-rate",
+            self.tick_rate > 0 && self.tick_rate <= 1000,
+            "must be between 1 and 1000"
+        );
+        validator.check(
+            "player-name",
+            !self.player_name.trim().is_empty(),
+            "must not be empty"
+        );
+        validator.nested("network-conditions", &self.network_conditions);
+    }
+}
+
+pub fn main(config: config::Config, loader: ConfigLoader) -> Result<(), Error> {
     let (event_tx, event_rx) = channel();
     let (net_update_tx, net_update_rx) = unbounded();
     let (main_update_tx, main_update_rx) = channel();
+    let (network_closed_tx, network_closed_rx) = channel();
+    let shutdown = Shutdown::new();
 
     log::info!("Creating window...");
 
@@ -49,13 +105,23 @@ pub fn main(config: config::Config) -> Result<(), Error> {
     
     let net_event_tx = event_tx.clone();
     let addr = config.client.server_address.clone();
+    let player_name = config.client.player_name.clone();
+    let capture = match config.client.capture_file {
+        Some(ref path) => Some(Capture::create(path)?),
+        None => None,
+    };
+    let conditions = config.client.network_conditions.clone();
     thread::spawn(move || {
         networking::connect(
             &addr,
+            player_name,
+            capture,
+            conditions,
             net_update_rx,
             net_event_tx
         );
         log::info!("Networking closed");
+        network_closed_tx.send(()).unwrap_or(());
     });
 
     log::info!("Initializing IO");
@@ -66,6 +132,15 @@ pub fn main(config: config::Config) -> Result<(), Error> {
         log::info!("IO closed");
     });
     
+    log::info!("Watching {} for simulation changes", loader.path());
+
+    let watcher_event_tx = event_tx.clone();
+    let watcher_config = config.simulation.clone();
+    let watcher_shutdown = shutdown.clone();
+    thread::spawn(move || {
+        watch_config(loader, watcher_config, watcher_event_tx, watcher_shutdown);
+    });
+
     log::info!("Initializing simulation");
     
     let tick_length = Duration::from_millis(
@@ -73,13 +148,17 @@ pub fn main(config: config::Config) -> Result<(), Error> {
     );
 
     let sim_config = config.simulation.clone();
+    let max_catch_up = config.client.max_catch_up_ticks;
+    let sim_shutdown = shutdown.clone();
     thread::spawn(move || {
         let mut game = build_simulation(
             sim_config,
             main_update_tx,
             net_update_tx,
             tick_length
-        );
+        )
+            .with_max_catch_up(max_catch_up)
+            .with_shutdown(sim_shutdown);
         game.run(
             move || {
                 match event_rx.try_recv() {
@@ -95,6 +174,8 @@ pub fn main(config: config::Config) -> Result<(), Error> {
             .unwrap_or_else(|_| {
                 log::error!("Network thread disconnected")
             });
+        // hanging up lets networking say goodbye to the server and finish
+        game.dispose();
         log::info!("Simulation closed");
     });
 
@@ -104,5 +185,60 @@ pub fn main(config: config::Config) -> Result<(), Error> {
 
     log::info!("Entering main loop");
     
-    eventloop::run(window, renderer, config, event_tx, main_update_rx, io_channel)
+    eventloop::run(
+        window,
+        renderer,
+        config,
+        event_tx,
+        main_update_rx,
+        io_channel,
+        (shutdown, network_closed_rx)
+    )
+}
+
+/**
+ * Sends the simulation settings to the simulation whenever the movement or
+ * physics settings change in the configuration file. Edits that fail to load
+ * or validate are ignored, and the remaining settings need a restart.
+ */
+fn watch_config(
+    loader: ConfigLoader,
+    mut current: SimulationConfig,
+    event_tx: Sender<Event>,
+    shutdown: Shutdown,
+) {
+    let mut watcher = ConfigWatcher::new(loader);
+
+    while !shutdown.is_requested() {
+        thread::sleep(CONFIG_POLL_INTERVAL);
+
+        match watcher.poll::<config::Config>() {
+            Some(Ok(config)) => {
+                let changed = config.data.simulation;
+                if changed.terrain != current.terrain {
+                    log::warn!("Changes to simulation.terrain take effect after a restart");
+                }
+                if changed.profiler != current.profiler {
+                    log::warn!("Changes to simulation.profiler take effect after a restart");
+                }
+
+                let reloadable = changed.movement_speed != current.movement_speed
+                    || changed.jump_force != current.jump_force
+                    || changed.physics != current.physics;
+                current = changed;
+                if !reloadable {
+                    continue;
+                }
+
+                log::info!("Simulation configuration changed, reloading");
+                if event_tx.send(Event::ConfigChanged(current.clone())).is_err() {
+                    break;
+                }
+            },
+            Some(Err(err)) => {
+                log::error!("Ignoring configuration change: {}", err);
+            },
+            None => (),
+        }
+    }
 }
\ No newline at end of file
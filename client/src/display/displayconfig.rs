@@ -1,3 +1,8 @@
+use eternalreckoning_core::util::config::{
+    Validate,
+    Validator,
+};
+
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct DisplayConfig {
@@ -22,4 +27,19 @@ impl Default for DisplayConfig {
             forward_interpolate: 0.0,
         }
     }
+}
+
+impl Validate for DisplayConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check(
+            "field-of-view",
+            self.field_of_view > 0.0 && self.field_of_view < 180.0,
+            "must be between 0 and 180 degrees"
+        );
+        validator.check(
+            "forward-interpolate",
+            self.forward_interpolate.is_finite(),
+            "must be a number"
+        );
+    }
 }
\ No newline at end of file
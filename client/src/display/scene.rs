@@ -192,6 +192,16 @@ where
         }
     }
 
+    pub fn remove_object(&mut self, id: specs::Entity) -> bool {
+        match self.object_by_id(id) {
+            Some(index) => {
+                self.objects.remove(index);
+                true
+            },
+            _ => false,
+        }
+    }
+
     pub fn get_model<'a>(
         &'a self,
         path: &str,
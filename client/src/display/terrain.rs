@@ -1,46 +1,33 @@
-pub struct HeightMap {
-    pub size: usize,
-    scale: f32,
-    data: Vec<f32>,
+pub use eternalreckoning_core::simulation::HeightMap;
+
+/// Mesh data for drawing a height map as terrain
+pub trait TerrainMesh {
+    fn vertices(&self) -> Vec<rendy::mesh::Position>;
+    fn colors(&self) -> Vec<rendy::mesh::Color>;
+    fn uvs(&self) -> Vec<rendy::mesh::TexCoord>;
+    fn indices(&self) -> Vec<u32>;
 }
 
-impl HeightMap {
-    pub fn new(data: Vec<f32>, size: usize, scale: f32) -> HeightMap {
-        assert_eq!(data.len(), size*size);
-        HeightMap { size, scale, data }
-    }
-
-    pub fn len(&self) -> usize {
-        self.size * self.size
-    }
-
-    pub fn get(&self, x: usize, y: usize) -> Option<f32> {
-        if x >= self.size || y >= self.size {
-            return None;
-        }
-
-        Some(*self.data.get(x + y * self.size).unwrap() * self.scale)
-    }
-
-    pub fn vertices(&self) -> Vec<rendy::mesh::Position> {
-        let mut res = Vec::with_capacity(self.data.len());
+impl TerrainMesh for HeightMap {
+    fn vertices(&self) -> Vec<rendy::mesh::Position> {
+        let mut res = Vec::with_capacity(self.len());
 
         for y in 0..self.size {
             for x in 0..self.size {
-                let value = -(*self.data.get(y*self.size + x).unwrap());
-                res.push([x as f32, value * self.scale, y as f32].into());
+                let value = -self.data()[y*self.size + x];
+                res.push([x as f32, value * self.scale(), y as f32].into());
             }
         }
 
         res
     }
 
-    pub fn colors(&self) -> Vec<rendy::mesh::Color> {
-        let mut res = Vec::with_capacity(self.data.len());
+    fn colors(&self) -> Vec<rendy::mesh::Color> {
+        let mut res = Vec::with_capacity(self.len());
 
         for y in 0..self.size {
             for x in 0..self.size {
-                let value = *self.data.get(y*self.size + x).unwrap();
+                let value = self.data()[y*self.size + x];
                 res.push([value, value, value, 1.0].into());
             }
         }
@@ -48,8 +35,8 @@ impl HeightMap {
         res
     }
 
-    pub fn uvs(&self) -> Vec<rendy::mesh::TexCoord> {
-        let mut res = Vec::with_capacity(self.data.len());
+    fn uvs(&self) -> Vec<rendy::mesh::TexCoord> {
+        let mut res = Vec::with_capacity(self.len());
 
         for y in 0..self.size {
             for x in 0..self.size {
@@ -60,7 +47,7 @@ impl HeightMap {
         res
     }
 
-    pub fn indices(&self) -> Vec<u32> {
+    fn indices(&self) -> Vec<u32> {
         let mut indices = Vec::with_capacity(
             (self.size - 1) * (self.size - 1) * 6
         );
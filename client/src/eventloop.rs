@@ -2,9 +2,12 @@ use std::sync::mpsc::{
     Sender,
     Receiver,
 };
+use std::time::Duration;
 
 use failure::Error;
 
+use eternalreckoning_core::util::shutdown::Shutdown;
+
 use crate::{
     input,
     input::InputTypes,
@@ -22,6 +25,9 @@ use crate::{
     util::config,
 };
 
+/// How long closing the window waits for networking to say goodbye
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
 pub fn run(
     window: Window,
     renderer: Renderer,
@@ -29,7 +35,9 @@ pub fn run(
     event_tx: Sender<event::Event>,
     update_rx: Receiver<event::Update>,
     io_channel: (Sender<iohandler::Request>, Receiver<iohandler::Response>),
+    shutdown_channel: (Shutdown, Receiver<()>),
 ) -> Result<(), Error> {
+    let (shutdown, network_closed) = shutdown_channel;
     let mut key_map = std::collections::HashMap::<u32, InputTypes>::new();
     key_map.insert(config.key_map.move_forward, InputTypes::MoveForward);
     key_map.insert(config.key_map.move_left, InputTypes::MoveLeft);
@@ -106,6 +114,16 @@ pub fn run(
                 },
                 _ => {},
             },
+            winit::event::Event::LoopDestroyed => {
+                // the simulation stops, then networking says goodbye
+                shutdown.request();
+                network_closed.recv_timeout(SHUTDOWN_TIMEOUT)
+                    .unwrap_or_else(|_| {
+                        log::warn!("Networking did not close in time");
+                    });
+                io_tx.send(iohandler::Request::Shutdown).unwrap_or(());
+                log::logger().flush();
+            },
             winit::event::Event::EventsCleared => {
                 if let Some(renderer) = &mut renderer {
                     let scene = renderer.get_scene();
@@ -144,6 +162,9 @@ pub fn run(
                                             true
                                         );
                                     },
+                                    event::Update::EntityRemoved(entity) => {
+                                        scene.remove_object(entity);
+                                    },
                                     event::Update::ModelUpdate(event::ModelUpdate { entity, ref path, offset }) => {
                                         if scene.get_model(&path[..]).is_none() {
                                             loading += 1;
@@ -188,6 +209,9 @@ pub fn run(
                                                 });
                                         }
                                     },
+                                    event::Update::SnapshotAck(_) => (),
+                                    event::Update::ClockSync => (),
+                                    event::Update::InputCommand(_) => (),
                                     event::Update::SimulationTick(time) => {
                                         scene.ticks[0] = scene.ticks[1];
                                         scene.ticks[1] = time;
@@ -259,6 +283,7 @@ pub fn run(
 
         if *control_flow == winit::event_loop::ControlFlow::Exit && renderer.is_some() {
             log::info!("Exiting...");
+            shutdown.request();
             renderer.take();
         }
     });
@@ -1,5 +1,10 @@
 use std::f64::consts::PI;
 
+use eternalreckoning_core::util::config::{
+    Validate,
+    Validator,
+};
+
 #[derive(Clone, Debug)]
 pub struct MouseEuler {
     pub pitch: f64,
@@ -26,6 +31,16 @@ impl Default for MouseConfig {
     }
 }
 
+impl Validate for MouseConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check(
+            "sensitivity",
+            self.sensitivity.is_finite() && self.sensitivity > 0.0,
+            "must be greater than 0"
+        );
+    }
+}
+
 const HALF_PI: f64 = PI / 2.0;
 
 impl Default for MouseEuler {
@@ -22,6 +22,7 @@ pub enum Request {
     LoadFile(String),
     LoadModel(String),
     LoadTerrain(LoadTerrainRequest),
+    Shutdown,
 }
 
 pub enum Response {
@@ -101,6 +102,7 @@ impl IOHandler {
                                 Response::Error
                             }
                         },
+                        Request::Shutdown => break,
                     }
                 },
                 Err(_) => break,
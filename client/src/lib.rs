@@ -12,7 +12,7 @@ mod client;
 use failure::Error;
 use failure::format_err;
 
-use eternalreckoning_core::util::config::Config;
+use eternalreckoning_core::util::config::ConfigLoader;
 use eternalreckoning_core::util::logging;
 
 pub struct Bootstrap {
@@ -21,35 +21,30 @@ pub struct Bootstrap {
 }
 
 pub fn run(bootstrap: Bootstrap) -> Result<(), Error> {
-    let config = initialize(bootstrap)?;
+    let (config, loader) = initialize(bootstrap)?;
 
-    client::main(config)?;
+    let result = client::main(config, loader);
+    log::logger().flush();
 
-    Ok(())
+    result
 }
 
 fn initialize(bootstrap: Bootstrap)
-    -> Result<util::config::Config, Error>
+    -> Result<(util::config::Config, ConfigLoader), Error>
 {
-    let config = get_configuration(bootstrap)?;
-    let config = config.data;
+    let loader = get_config_loader(bootstrap)?;
+    let config = loader.load::<util::config::Config>()?;
 
-    logging::configure(&config.logging, "eternalreckoning_client")?;
+    logging::configure(&config.data.logging, "eternalreckoning_client")?;
+    config.log_sources();
 
-    Ok(config)
+    Ok((config.data, loader))
 }
 
-fn get_configuration(bootstrap: Bootstrap)
-    -> Result<Config<util::config::Config>, Error>
-{
-    match bootstrap.config {
-        Some(path) => {
-            Config::<util::config::Config>::from_file(&path)
-                .or_else(|_| {
-                    Config::<util::config::Config>::write_default(&path)
-                })
-                .map_err(|e| { e.into() })
-        },
-        None => Err(format_err!("no configuration file path provided")),
-    }
+fn get_config_loader(bootstrap: Bootstrap) -> Result<ConfigLoader, Error> {
+    // the first argument is the program
+    let args = bootstrap.args.into_iter().skip(1).collect();
+
+    ConfigLoader::new(bootstrap.config, args)
+        .ok_or_else(|| format_err!("no configuration file path provided"))
 }
\ No newline at end of file
@@ -2,7 +2,4 @@ mod erm;
 mod heightmap;
 
 pub use erm::meshes_from_erm;
-pub use heightmap::{
-    mesh_from_bmp,
-    heightmap_from_bmp,
-};
\ No newline at end of file
+pub use heightmap::mesh_from_bmp;
\ No newline at end of file
@@ -15,6 +15,7 @@ fn main() {
         eprintln!("Application error: {}", e);
         eprintln!("Backtrace: {:?}", e.backtrace());
 
+        log::logger().flush();
         process::exit(1);
     }
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+use std::net::SocketAddr;
 use std::sync::mpsc::Sender;
 
 use failure::{
@@ -10,6 +11,7 @@ use futures::stream::{
     SplitStream,
     SplitSink,
 };
+use tokio::codec::BytesCodec;
 use tokio::net::{
     UdpSocket,
     UdpFramed,
@@ -17,11 +19,25 @@ use tokio::net::{
 use tokio::prelude::*;
 
 use eternalreckoning_core::net::{
-    codec::EternalReckoningCodec,
+    capture::Capture,
+    clock::{
+        timestamp_now,
+        ClockSample,
+    },
     operation::{
         self,
         Operation,
     },
+    quantization::quantize_yaw,
+    transport::{
+        connect_websocket,
+        ConditionedSocket,
+        DatagramTransport,
+        NetworkConditions,
+        ServerAddress,
+        Transport,
+    },
+    PROTOCOL_VERSION,
 };
 use crate::simulation::{
     self,
@@ -34,102 +50,184 @@ use crate::simulation::{
 
 pub fn connect(
     address: &String,
+    player_name: String,
+    capture: Option<Capture>,
+    conditions: NetworkConditions,
     update_rx: mpsc::UnboundedReceiver<Update>,
     event_tx: Sender<Event>,
 )
 {
-    let client = tokio_dns::resolve_sock_addr(&address[..])
-        .from_err()
-        .and_then(move |addr_vec| {
-            let socket = UdpSocket::bind(&([127, 0, 0, 1], 0).into())
-                .map_err(|err| {
-                    format_err!("Failed to bind udp socket: {}", err)
-                })
-                .unwrap();
-
-            let mut addr = None;
-            for try_addr in addr_vec {
-                if socket.connect(&try_addr).is_ok() {
-                    addr = Some(try_addr);
-                    break;
-                }
-            }
-            
-            let addr = match addr {
-                Some(addr) => addr,
-                None => {
-                    panic!("Failed to connect to server");
-                }
-            };
-
-            log::info!("Connected to server: {}", addr);
-
-            UdpFramed::new(socket, EternalReckoningCodec)
-                .send((Operation::ClConnectMessage(operation::ClConnectMessage), addr))
-                .and_then(|framed| {
-                    framed.into_future().map_err(|(err, _stream)| err)
-                })
-                .map(move |(op, stream)| {
-                    if op.is_none() {
-                        return futures::future::err(
-                            failure::format_err!("connection closed")
-                        );
-                    }
-                    let op = op.unwrap().0;
-
-                    if let Operation::SvConnectResponse(operation::SvConnectResponse { uuid }) = op {
-                        event_tx.send(Event::ConnectionEvent(
-                            ConnectionEvent::Connected(uuid)
-                        )).unwrap();
-
-                        let (writer, reader) = stream.split();
-
-                        tokio::spawn(
-                            ReadConnection::new(reader, event_tx.clone())
-                                .map_err(move |err| {
-                                    log::error!("Receive failed: {:?}", err);
-                                    event_tx.send(Event::ConnectionEvent(
-                                        ConnectionEvent::Disconnected(uuid)
-                                    )).unwrap();
-                                })
-                        );
-
-                        tokio::spawn(
-                            WriteConnection::new(writer, addr, update_rx)
-                                .map_err(|err| {
-                                    log::error!("Write failed: {:?}", err);
-                                })
-                        );
-
-                        return futures::future::ok(());
+    let address = match address.parse::<ServerAddress>() {
+        Ok(address) => address,
+        Err(err) => {
+            log::error!("Invalid server address {}: {}", address, err);
+            return;
+        },
+    };
+
+    let client: Box<dyn Future<Item = (), Error = Error> + Send> = match address {
+        ServerAddress::Udp(address) => Box::new(
+            tokio_dns::resolve_sock_addr(&address[..])
+                .from_err()
+                .and_then(move |addr_vec| {
+                    let socket = UdpSocket::bind(&([127, 0, 0, 1], 0).into())
+                        .map_err(|err| {
+                            format_err!("Failed to bind udp socket: {}", err)
+                        })
+                        .unwrap();
+
+                    let mut addr = None;
+                    for try_addr in addr_vec {
+                        if socket.connect(&try_addr).is_ok() {
+                            addr = Some(try_addr);
+                            break;
+                        }
                     }
+                    
+                    let addr = match addr {
+                        Some(addr) => addr,
+                        None => {
+                            panic!("Failed to connect to server");
+                        }
+                    };
+
+                    log::info!("Connected to server: {}", addr);
 
-                    futures::future::err(
-                        failure::format_err!("unexpected response from server")
-                    )
+                    let socket = ConditionedSocket::new(
+                        UdpFramed::new(socket, BytesCodec::new()),
+                        &conditions
+                    );
+                    let transport = DatagramTransport::new(socket)
+                        .with_capture(capture);
+
+                    handshake(transport, addr, player_name, update_rx, event_tx)
+                })
+        ),
+        ServerAddress::WebSocket { host, path } => Box::new(
+            tokio_dns::resolve_sock_addr(&host[..])
+                .from_err()
+                .and_then(|addr_vec| {
+                    addr_vec.first()
+                        .cloned()
+                        .ok_or_else(|| format_err!("Failed to resolve server address"))
+                })
+                .and_then(move |addr| {
+                    connect_websocket(addr, host, path)
+                        .map(move |connection| (connection, addr))
                 })
-                .map_err(|err| {
-                    failure::format_err!("handshake failed: {:?}", err)
+                .and_then(move |(connection, addr)| {
+                    log::info!("Connected to server over WebSocket: {}", addr);
+
+                    let socket = ConditionedSocket::new(connection, &conditions);
+                    let transport = DatagramTransport::new(socket)
+                        .with_capture(capture);
+
+                    handshake(transport, addr, player_name, update_rx, event_tx)
                 })
+        ),
+    };
+
+    tokio::run(client.map_err(|err| {
+        log::error!("Failed to connect to server: {:?}", err);
+    }));
+}
+
+/**
+ * Sends the connect message over `transport` and, once the server accepts
+ * it, spawns the tasks relaying operations between the transport and the
+ * simulation. Both tasks finish once the simulation hangs up.
+ */
+pub fn handshake<T>(
+    transport: T,
+    addr: SocketAddr,
+    player_name: String,
+    update_rx: mpsc::UnboundedReceiver<Update>,
+    event_tx: Sender<Event>,
+) -> impl Future<Item = (), Error = Error>
+where
+    T: Transport + Send + 'static,
+{
+    transport
+        .send((Operation::ClConnectMessage(operation::ClConnectMessage {
+            protocol_version: PROTOCOL_VERSION,
+            client_build: concat!(
+                env!("CARGO_PKG_NAME"), " ", env!("CARGO_PKG_VERSION")
+            ).to_string(),
+            player_name,
+        }), addr))
+        .and_then(|transport| {
+            transport.into_future().map_err(|(err, _stream)| err)
         })
-        .map(|_| ())
-        .map_err(|err| {
-            log::error!("Failed to connect to server: {:?}", err);
-        });
+        .and_then(move |(op, stream)| {
+            if op.is_none() {
+                return futures::future::err(
+                    failure::format_err!("connection closed")
+                );
+            }
+            let op = op.unwrap().0;
+
+            if let Operation::SvConnectResponse(operation::SvConnectResponse { uuid, zone_origin, params }) = op {
+                event_tx.send(Event::ConnectionEvent(
+                    ConnectionEvent::Connected(uuid, zone_origin, params)
+                )).unwrap();
+
+                let (writer, reader) = stream.split();
+                let (closed_tx, closed_rx) = futures::sync::oneshot::channel();
+
+                tokio::spawn(
+                    ReadConnection::new(reader, event_tx.clone())
+                        .map_err(move |err| {
+                            log::error!("Receive failed: {:?}", err);
+                            event_tx.send(Event::ConnectionEvent(
+                                ConnectionEvent::Disconnected(uuid)
+                            )).unwrap();
+                        })
+                        // stops reading once the writer has said goodbye
+                        .select(closed_rx.or_else(|_| futures::future::empty()))
+                        .map(|_| ())
+                        .map_err(|_| ())
+                );
+
+                tokio::spawn(
+                    WriteConnection::new(writer, addr, update_rx)
+                        .map(move |_| {
+                            closed_tx.send(()).unwrap_or(());
+                        })
+                        .map_err(|err| {
+                            log::error!("Write failed: {:?}", err);
+                        })
+                );
 
-    tokio::run(client);
+                return futures::future::ok(());
+            }
+
+            if let Operation::SvConnectRejected(data) = op {
+                return futures::future::err(failure::format_err!(
+                    "connection rejected ({}): {}",
+                    data.reason,
+                    data.message
+                ));
+            }
+
+            futures::future::err(
+                failure::format_err!("unexpected response from server")
+            )
+        })
+        .map_err(|err| {
+            failure::format_err!("handshake failed: {:?}", err)
+        })
 }
 
-struct ReadConnection {
-    frames: SplitStream<UdpFramed<EternalReckoningCodec>>,
+struct ReadConnection<T: Transport> {
+    frames: SplitStream<T>,
     event_tx: Sender<Event>,
 }
 
-impl ReadConnection {
+impl<T: Transport> ReadConnection<T> {
     pub fn new(
-        frames: SplitStream<UdpFramed<EternalReckoningCodec>>,
+        frames: SplitStream<T>,
         event_tx: Sender<Event>,
-    ) -> ReadConnection
+    ) -> ReadConnection<T>
     {
         ReadConnection {
             frames,
@@ -140,9 +238,24 @@ impl ReadConnection {
     fn process_data(&mut self, packet: &Operation)
         -> Result<(), Error> {
         match packet {
-            Operation::SvUpdateWorld(_) => {
+            Operation::SvUpdateWorld(_)
+            | Operation::SvSpawnEntity(_)
+            | Operation::SvDespawnEntity(_) => {
                 self.event_tx.send(Event::NetworkEvent(packet.clone()))?;
             },
+            Operation::DisconnectMessage => {
+                return Err(format_err!("Server closed the connection"));
+            },
+            Operation::SvSync(data) => {
+                self.event_tx.send(Event::ConnectionEvent(
+                    ConnectionEvent::ClockSample(ClockSample {
+                        sent: data.client_time,
+                        server_time: data.server_time,
+                        server_tick: data.tick,
+                        received: timestamp_now(),
+                    })
+                ))?;
+            },
             _ => {
                 log::warn!("Unexpected server message received, ignoring");
             }
@@ -151,19 +264,18 @@ impl ReadConnection {
     }
 }
 
-impl Future for ReadConnection {
+impl<T: Transport> Future for ReadConnection<T> {
     type Item = ();
     type Error = Error;
 
     fn poll(&mut self) -> Poll<(), Error> {
         while let Async::Ready(frame) = self.frames.poll()? {
-            if let Some(packet) = frame {
-                log::trace!("Packet: {}", &packet.0);
-                self.process_data(&packet.0)?;
-            } else {
-                // EOF
-                log::warn!("Disconnected from server");
-                return Ok(Async::Ready(()));
+            match frame {
+                Some(packet) => {
+                    log::trace!("Packet: {}", &packet.0);
+                    self.process_data(&packet.0)?;
+                },
+                None => return Err(format_err!("Connection closed")),
             }
         }
 
@@ -174,21 +286,23 @@ impl Future for ReadConnection {
 enum WriteConnectionState {
     Sending,
     Connected,
+    /// the simulation hung up, so only the goodbye is left to flush
+    Closing,
 }
 
-struct WriteConnection {
-    frames: SplitSink<UdpFramed<EternalReckoningCodec>>,
-    addr: std::net::SocketAddr,
+struct WriteConnection<T: Transport> {
+    frames: SplitSink<T>,
+    addr: SocketAddr,
     update_rx: mpsc::UnboundedReceiver<Update>,
     state: WriteConnectionState,
 }
 
-impl WriteConnection {
+impl<T: Transport> WriteConnection<T> {
     pub fn new(
-        frames: SplitSink<UdpFramed<EternalReckoningCodec>>,
-        addr: std::net::SocketAddr,
+        frames: SplitSink<T>,
+        addr: SocketAddr,
         update_rx: mpsc::UnboundedReceiver<Update>,
-    ) -> WriteConnection
+    ) -> WriteConnection<T>
     {
         WriteConnection {
             frames,
@@ -205,7 +319,7 @@ impl WriteConnection {
     }
 }
 
-impl Future for WriteConnection {
+impl<T: Transport> Future for WriteConnection<T> {
     type Item = ();
     type Error = Error;
 
@@ -216,25 +330,48 @@ impl Future for WriteConnection {
                     futures::try_ready!(self.frames.poll_complete());
                     self.state = WriteConnectionState::Connected;
                 },
+                WriteConnectionState::Closing => {
+                    futures::try_ready!(self.frames.poll_complete());
+                    log::info!("Disconnected from server");
+                    return Ok(Async::Ready(()));
+                },
                 WriteConnectionState::Connected => {
                     match self.update_rx.poll() {
                         Ok(Async::Ready(Some(update))) => {
                             match update {
-                                simulation::event::Update::PositionUpdate(data) => {
-                                    self.send(Operation::ClMoveSetPosition(
-                                        operation::ClMoveSetPosition {
-                                            pos: data.position.clone(),
+                                simulation::event::Update::InputCommand(data) => {
+                                    self.send(Operation::ClInput(
+                                        operation::ClInput {
+                                            tick: data.tick,
+                                            keys: data.keys.0,
+                                            jump: data.jump,
+                                            yaw: quantize_yaw(data.yaw),
                                         }
                                     ))?;
                                 },
+                                simulation::event::Update::ClockSync => {
+                                    // stamped here so queueing delay is not counted
+                                    self.send(Operation::ClSync(
+                                        operation::ClSync { client_time: timestamp_now() }
+                                    ))?;
+                                },
+                                simulation::event::Update::SnapshotAck(snapshot) => {
+                                    self.send(Operation::ClSnapshotAck(
+                                        operation::ClSnapshotAck { snapshot }
+                                    ))?;
+                                },
                                 _ => (),
                             }
                         },
+                        Ok(Async::Ready(None)) => {
+                            self.frames.start_send((Operation::DisconnectMessage, self.addr))?;
+                            self.state = WriteConnectionState::Closing;
+                        },
                         Err(err) => {
                             log::warn!("Update channel closed: {:?}", err);
                             return Ok(Async::Ready(()));
                         }
-                        _ => {
+                        Ok(Async::NotReady) => {
                             return Ok(Async::NotReady);
                         },
                     };
@@ -1,23 +1,21 @@
-pub mod collider;
-mod health;
-mod jump;
 mod model;
-mod movement;
-mod name;
-mod position;
 mod serverid;
 mod terrain;
 mod texture;
-mod velocity;
 
-pub use collider::Collider;
-pub use health::Health;
-pub use jump::Jump;
 pub use model::Model;
-pub use movement::Movement;
-pub use name::Name;
-pub use position::Position;
 pub use serverid::ServerID;
 pub use terrain::Terrain;
 pub use texture::Texture;
-pub use velocity::Velocity;
\ No newline at end of file
+
+pub use eternalreckoning_core::simulation::component::{
+    collider,
+    Collider,
+    Health,
+    Jump,
+    Movement,
+    Name,
+    PlayerInput,
+    Position,
+    Velocity,
+};
\ No newline at end of file
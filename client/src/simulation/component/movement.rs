@@ -1,10 +0,0 @@
-use specs::prelude::*;
-
-pub struct Movement {
-    pub speed: f64,
-    pub on_ground: bool,
-}
-
-impl Component for Movement {
-    type Storage = VecStorage<Self>;
-}
\ No newline at end of file
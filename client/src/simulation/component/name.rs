@@ -1,7 +0,0 @@
-use specs::prelude::*;
-
-pub struct Name(pub String);
-
-impl Component for Name {
-    type Storage = VecStorage<Self>;
-}
\ No newline at end of file
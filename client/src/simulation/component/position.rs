@@ -1,7 +0,0 @@
-use specs::prelude::*;
-
-pub struct Position(pub nalgebra::Point3<f64>);
-
-impl Component for Position {
-    type Storage = VecStorage<Self>;
-}
\ No newline at end of file
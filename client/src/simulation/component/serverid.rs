@@ -1,8 +1,32 @@
 use specs::prelude::*;
 use uuid::Uuid;
 
+use eternalreckoning_core::simulation::worldsnapshot::{
+    EntityMap,
+    SnapshotComponent,
+    SnapshotError,
+};
+
 pub struct ServerID(pub Uuid);
 
 impl Component for ServerID {
     type Storage = VecStorage<Self>;
+}
+
+impl SnapshotComponent for ServerID {
+    const KEY: &'static str = "server-id";
+    type Data = String;
+
+    fn save(&self, _: &EntityMap) -> String {
+        self.0.to_string()
+    }
+
+    fn restore(data: String, _: &EntityMap) -> Result<ServerID, SnapshotError> {
+        data.parse()
+            .map(ServerID)
+            .map_err(|err| SnapshotError::InvalidComponent {
+                key: Self::KEY.to_string(),
+                message: format!("{}", err),
+            })
+    }
 }
\ No newline at end of file
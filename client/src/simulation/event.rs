@@ -1,7 +1,17 @@
 use uuid::Uuid;
-use eternalreckoning_core::net::operation::Operation;
+use eternalreckoning_core::net::{
+    clock::ClockSample,
+    operation::{
+        Operation,
+        SimulationParams,
+    },
+};
+use eternalreckoning_core::simulation::movement::MoveKeys;
+
+use super::SimulationConfig;
 
 pub enum Event {
+    ConfigChanged(SimulationConfig),
     ConnectionEvent(ConnectionEvent),
     InputEvent(InputEvent),
     NetworkEvent(Operation),
@@ -9,8 +19,10 @@ pub enum Event {
 
 #[derive(Debug)]
 pub enum ConnectionEvent {
-    Connected(Uuid),
+    /// player id, zone origin and the server's movement settings
+    Connected(Uuid, nalgebra::Point3<f64>, SimulationParams),
     Disconnected(Uuid),
+    ClockSample(ClockSample),
 }
 
 #[derive(Debug)]
@@ -24,8 +36,12 @@ pub enum InputEvent {
 pub enum Update {
     SimulationTick(std::time::Instant),
     CameraUpdate(CameraUpdate),
+    InputCommand(InputCommand),
+    ClockSync,
+    EntityRemoved(specs::Entity),
     ModelUpdate(ModelUpdate),
     PositionUpdate(PositionUpdate),
+    SnapshotAck(u32),
     TerrainUpdate(TerrainUpdate),
     TextureUpdate(TextureUpdate),
 }
@@ -33,6 +49,14 @@ pub enum Update {
 #[derive(Clone)]
 pub struct CameraUpdate(pub nalgebra::Point3<f64>);
 
+#[derive(Clone)]
+pub struct InputCommand {
+    pub tick: u64,
+    pub keys: MoveKeys,
+    pub jump: bool,
+    pub yaw: f64,
+}
+
 #[derive(Clone)]
 pub struct ModelUpdate {
     pub entity: specs::Entity,
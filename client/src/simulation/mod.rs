@@ -3,10 +3,12 @@ pub mod event;
 pub mod resource;
 pub mod system;
 mod simulation;
-mod physicsconfig;
+mod snapshot;
 
 pub use simulation::{
     build_simulation,
     SimulationConfig,
 };
-pub use physicsconfig::PhysicsConfig;
\ No newline at end of file
+pub use snapshot::snapshotter;
+
+pub use eternalreckoning_core::simulation::PhysicsConfig;
\ No newline at end of file
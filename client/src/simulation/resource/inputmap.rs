@@ -1,3 +1,5 @@
+use eternalreckoning_core::simulation::movement::MoveKeys;
+
 use crate::input::InputTypes;
 
 #[derive(Default)]
@@ -21,4 +23,13 @@ impl InputMap {
         };
         *field = value;
     }
+
+    pub fn keys(&self) -> MoveKeys {
+        let mut keys = MoveKeys::default();
+        keys.set(MoveKeys::FORWARD, self.move_forward);
+        keys.set(MoveKeys::BACKWARD, self.move_backward);
+        keys.set(MoveKeys::LEFT, self.move_left);
+        keys.set(MoveKeys::RIGHT, self.move_right);
+        keys
+    }
 }
\ No newline at end of file
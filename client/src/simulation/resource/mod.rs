@@ -1,13 +1,15 @@
 mod activecamera;
 mod activecharacter;
 mod inputmap;
-mod ticklength;
+mod predictionhistory;
 
 use super::event::Event;
 
 pub use activecamera::ActiveCamera;
 pub use activecharacter::ActiveCharacter;
 pub use inputmap::InputMap;
-pub use ticklength::TickLength;
+pub use predictionhistory::PredictionHistory;
+
+pub use eternalreckoning_core::simulation::TickLength;
 
 pub type EventQueue = Vec<Event>;
\ No newline at end of file
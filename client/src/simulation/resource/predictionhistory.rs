@@ -0,0 +1,85 @@
+use std::collections::VecDeque;
+
+/// Ticks of predictions kept for the server to confirm
+const PREDICTION_HISTORY: usize = 256;
+/// Disagreements smaller than this are quantization noise
+const PREDICTION_TOLERANCE: f64 = 0.05;
+
+/**
+ * Where the player was predicted to be after each recent input tick. When
+ * the server reports where the player was after one of them, shifting the
+ * current position by the difference is the same as replaying the inputs
+ * since then from the server's position.
+ */
+#[derive(Default)]
+pub struct PredictionHistory {
+    predictions: VecDeque<(u64, nalgebra::Point3<f64>)>,
+}
+
+impl PredictionHistory {
+    pub fn record(&mut self, tick: u64, position: nalgebra::Point3<f64>) {
+        if self.predictions.len() == PREDICTION_HISTORY {
+            self.predictions.pop_front();
+        }
+        self.predictions.push_back((tick, position));
+    }
+
+    /**
+     * Compares the prediction for `tick` with the server's position,
+     * returning the correction to apply to the player if they disagree.
+     * Predictions before `tick` are dropped, so older reports are ignored.
+     */
+    pub fn reconcile(&mut self, tick: u64, server_position: &nalgebra::Point3<f64>)
+        -> Option<nalgebra::Vector3<f64>>
+    {
+        while self.predictions.front().is_some_and(|(predicted, _)| *predicted < tick) {
+            self.predictions.pop_front();
+        }
+
+        let (predicted_tick, predicted) = *self.predictions.front()?;
+        if predicted_tick != tick {
+            return None;
+        }
+
+        let correction = server_position - predicted;
+        if correction.norm() < PREDICTION_TOLERANCE {
+            return None;
+        }
+
+        for (_, position) in self.predictions.iter_mut() {
+            *position += correction;
+        }
+
+        Some(correction)
+    }
+
+    pub fn clear(&mut self) {
+        self.predictions.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconcile() {
+        let mut history = PredictionHistory::default();
+        for tick in 1..=5 {
+            history.record(tick, nalgebra::Point3::new(tick as f64, 0.0, 0.0));
+        }
+
+        // agreeing with the prediction
+        assert_eq!(history.reconcile(2, &nalgebra::Point3::new(2.01, 0.0, 0.0)), None);
+
+        // the server was held back by a dropped input
+        let correction = history.reconcile(3, &nalgebra::Point3::new(2.0, 0.0, 0.0));
+        assert_eq!(correction, Some(nalgebra::Vector3::new(-1.0, 0.0, 0.0)));
+
+        // later reports compare against the corrected predictions
+        assert_eq!(history.reconcile(4, &nalgebra::Point3::new(3.0, 0.0, 0.0)), None);
+
+        // reports older than the last one are stale
+        assert_eq!(history.reconcile(1, &nalgebra::Point3::new(9.0, 0.0, 0.0)), None);
+    }
+}
\ No newline at end of file
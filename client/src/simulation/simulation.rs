@@ -8,7 +8,6 @@ use specs::{
 use futures::sync::mpsc::UnboundedSender;
 
 use crate::input::MouseEuler;
-use crate::loaders::heightmap_from_bmp;
 use super::event::{
     Event,
     Update,
@@ -20,6 +19,7 @@ use super::component::{
     Model,
     Movement,
     Name,
+    PlayerInput,
     Position,
     ServerID,
     Terrain,
@@ -30,41 +30,92 @@ use super::resource::{
     ActiveCamera,
     ActiveCharacter,
     InputMap,
+    PredictionHistory,
     TickLength,
 };
 use super::system::{
+    ClockSync,
     CollisionDetection,
     CollisionResolver,
+    InputSender,
     Physics,
     PlayerMovement,
+    RecordPrediction,
+    ReloadConfig,
     UpdateInputs,
     UpdateSender,
     UpdateWorld,
 };
 use super::PhysicsConfig;
 
-use eternalreckoning_core::simulation::Simulation;
+use eternalreckoning_core::net::clock::NetworkClock;
+use eternalreckoning_core::simulation::{
+    Profiler,
+    ProfilerConfig,
+    Simulation,
+    TerrainConfig,
+};
+use eternalreckoning_core::util::config::{
+    Validate,
+    Validator,
+};
 
-#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct SimulationConfig {
     pub movement_speed: f64,
     pub jump_force: f64,
     pub physics: PhysicsConfig,
+    /// the ground to collide with, which must match the server's
+    pub terrain: TerrainConfig,
+    /// per-system tick timings
+    pub profiler: ProfilerConfig,
 }
 
 impl Default for SimulationConfig {
     fn default() -> SimulationConfig {
         SimulationConfig {
-            movement_speed: 6.0,
+            movement_speed: 8.5,
             jump_force: 10.35,
             physics: PhysicsConfig::default(),
+            terrain: TerrainConfig::default(),
+            profiler: ProfilerConfig::default(),
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// Converts the per-second rates into per-tick ones
+    pub fn scaled(&self, tick_length: &TickLength) -> SimulationConfig {
+        SimulationConfig {
+            movement_speed: tick_length.scale_to(self.movement_speed),
+            jump_force: tick_length.scale_to(self.jump_force),
+            physics: self.physics.scaled(tick_length),
+            ..self.clone()
         }
     }
 }
 
+impl Validate for SimulationConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check(
+            "movement-speed",
+            self.movement_speed.is_finite() && self.movement_speed >= 0.0,
+            "must not be negative"
+        );
+        validator.check(
+            "jump-force",
+            self.jump_force.is_finite() && self.jump_force >= 0.0,
+            "must not be negative"
+        );
+        validator.nested("physics", &self.physics);
+        validator.nested("terrain", &self.terrain);
+        validator.nested("profiler", &self.profiler);
+    }
+}
+
 pub fn build_simulation<'a, 'b>(
-    mut config: SimulationConfig,
+    config: SimulationConfig,
     update_tx: Sender<Update>,
     net_update_tx: UnboundedSender<Update>,
     tick_length: std::time::Duration,
@@ -72,16 +123,17 @@ pub fn build_simulation<'a, 'b>(
 {
     let mut world = World::new();
 
+    let profiler = Profiler::new(&config.profiler, tick_length);
     let tick_length = TickLength(tick_length);
-
-    config.movement_speed = tick_length.scale_to(config.movement_speed);
-    config.jump_force = tick_length.scale_to(config.jump_force);
-    
-    config.physics.gravity = tick_length.scale_to(config.physics.gravity);
+    let reload_config = ReloadConfig::new(config.clone());
+    let config = config.scaled(&tick_length);
 
     world.insert(InputMap::default());
     world.insert(MouseEuler::default());
+    world.insert(NetworkClock::new());
+    world.insert(PredictionHistory::default());
     world.insert(tick_length);
+    world.insert(config.physics.clone());
 
     world.register::<Collider>();
     world.register::<Health>();
@@ -89,19 +141,20 @@ pub fn build_simulation<'a, 'b>(
     world.register::<Model>();
     world.register::<Movement>();
     world.register::<Name>();
+    world.register::<PlayerInput>();
     world.register::<Position>();
     world.register::<ServerID>();
     world.register::<Terrain>();
     world.register::<Texture>();
     world.register::<Velocity>();
 
-    // Floor collision plane
+    // Terrain, the same the server collides with
     world.create_entity()
-        .with(Position(nalgebra::Point3::new(-64.0, 5.0, -64.0)))
+        .with(Position(config.terrain.origin()))
         .with(Collider::new(collider::ColliderType::HeightMap(
-            heightmap_from_bmp("assets/terrain.bmp", 25.0).unwrap()
+            config.terrain.load().unwrap()
         )))
-        .with(Terrain::new("assets/terrain.bmp", 25.0))
+        .with(Terrain::new(&config.terrain.heightmap, config.terrain.scale))
         .with(Texture {
             path: "assets/sand.png".to_string(),
             wrap_mode: rendy::resource::WrapMode::Tile,
@@ -116,6 +169,7 @@ pub fn build_simulation<'a, 'b>(
         .with(Velocity(nalgebra::Vector3::new(0.0, 0.0, 0.0)))
         .with(Movement { speed: config.movement_speed, on_ground: true })
         .with(Jump { force: config.jump_force })
+        .with(PlayerInput::default())
         .with(Collider::new(collider::ColliderType::Sphere(1.0)))
         .with(Model {
             path: "assets/marker.erm".to_string(),
@@ -159,26 +213,55 @@ pub fn build_simulation<'a, 'b>(
         .build();
 
     let dispatcher = DispatcherBuilder::new()
-        .with(UpdateInputs, "update_inputs", &[])
-        .with(PlayerMovement, "player_movement", &["update_inputs"])
-        .with(Physics::new(&config.physics), "physics", &["player_movement"])
+        .with(profiler.timed("update_inputs", UpdateInputs), "update_inputs", &[])
+        .with(profiler.timed("reload_config", reload_config), "reload_config", &[])
         .with(
-            CollisionDetection::new(&config.physics),
+            profiler.timed("player_movement", PlayerMovement),
+            "player_movement",
+            &["update_inputs", "reload_config"]
+        )
+        .with(
+            profiler.timed("physics", Physics),
+            "physics",
+            &["player_movement"]
+        )
+        .with(
+            profiler.timed("collision_detection", CollisionDetection::default()),
             "collision_detection",
             &["physics"]
         )
         .with(
-            CollisionResolver::new(&config.physics),
+            profiler.timed("collision_resolver", CollisionResolver),
             "collision_resolver",
             &["collision_detection"]
         )
         .with(
-            UpdateSender::new(update_tx, net_update_tx),
+            profiler.timed("record_prediction", RecordPrediction),
+            "record_prediction",
+            &["collision_resolver"]
+        )
+        .with(
+            profiler.timed("input_sender", InputSender::new(net_update_tx.clone())),
+            "input_sender",
+            &["update_inputs"]
+        )
+        .with(
+            profiler.timed("update_sender", UpdateSender::new(update_tx)),
             "update_sender",
             &["player_movement", "physics", "collision_detection", "collision_resolver"]
         )
-        .with(UpdateWorld, "update_world", &[])
+        .with(
+            profiler.timed("clock_sync", ClockSync::new(net_update_tx.clone())),
+            "clock_sync",
+            &[]
+        )
+        .with(
+            profiler.timed("update_world", UpdateWorld::new(net_update_tx)),
+            "update_world",
+            &[]
+        )
         .build();
 
     Simulation::new(dispatcher, world)
+        .with_profiler(profiler)
 }
\ No newline at end of file
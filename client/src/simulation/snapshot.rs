@@ -0,0 +1,22 @@
+use eternalreckoning_core::simulation::WorldSnapshotter;
+
+use super::component::{
+    Health,
+    Name,
+    Position,
+    ServerID,
+    Velocity,
+};
+
+/**
+ * Components saved in client world snapshots. Render state, colliders and
+ * terrain are rebuilt from assets, so they are left out.
+ */
+pub fn snapshotter() -> WorldSnapshotter {
+    WorldSnapshotter::new()
+        .with::<Health>()
+        .with::<Name>()
+        .with::<Position>()
+        .with::<ServerID>()
+        .with::<Velocity>()
+}
\ No newline at end of file
@@ -0,0 +1,94 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use futures::sync::mpsc::UnboundedSender;
+use specs::prelude::*;
+
+use eternalreckoning_core::net::clock::NetworkClock;
+use eternalreckoning_core::simulation::TickTime;
+
+use crate::simulation::{
+    event::{
+        Event,
+        ConnectionEvent,
+        Update,
+    },
+    resource::EventQueue,
+};
+
+const SYNC_INTERVAL: Duration = Duration::from_secs(1);
+
+/**
+ * Requests a clock sync from the server once a second while connected and
+ * folds the answers into the `NetworkClock` resource.
+ */
+pub struct ClockSync {
+    net_sender: Option<UnboundedSender<Update>>,
+    connected: bool,
+    last_sync: Option<Instant>,
+}
+
+impl ClockSync {
+    pub fn new(net_sender: UnboundedSender<Update>) -> ClockSync {
+        ClockSync {
+            net_sender: Some(net_sender),
+            connected: false,
+            last_sync: None,
+        }
+    }
+}
+
+impl<'a> System<'a> for ClockSync {
+    type SystemData = (
+        Read<'a, EventQueue>,
+        Read<'a, TickTime>,
+        Write<'a, NetworkClock>,
+    );
+
+    fn run(&mut self, (events, tick_time, mut clock): Self::SystemData) {
+        for event in &*events {
+            match event {
+                Event::ConnectionEvent(ConnectionEvent::Connected(..)) => {
+                    self.connected = true;
+                    self.last_sync = None;
+                    *clock = NetworkClock::new();
+                },
+                Event::ConnectionEvent(ConnectionEvent::Disconnected(_)) => {
+                    self.connected = false;
+                },
+                Event::ConnectionEvent(ConnectionEvent::ClockSample(sample)) => {
+                    clock.sample(sample);
+                    log::trace!(
+                        "Clock sync: rtt {:?}, jitter {:?}, offset {}us",
+                        clock.rtt(),
+                        clock.jitter(),
+                        clock.offset()
+                    );
+                },
+                _ => (),
+            }
+        }
+
+        if !self.connected {
+            return;
+        }
+
+        let due = match self.last_sync {
+            Some(last_sync) => tick_time.0.duration_since(last_sync) >= SYNC_INTERVAL,
+            None => true,
+        };
+        if !due {
+            return;
+        }
+
+        if let Some(net_sender) = &self.net_sender {
+            net_sender.unbounded_send(Update::ClockSync).unwrap_or_else(|err| {
+                log::error!("failed to send clock sync: {}", err);
+                self.net_sender = None;
+            });
+            self.last_sync = Some(tick_time.0);
+        }
+    }
+}
\ No newline at end of file
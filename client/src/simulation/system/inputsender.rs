@@ -0,0 +1,50 @@
+use futures::sync::mpsc::UnboundedSender;
+use specs::prelude::*;
+
+use eternalreckoning_core::simulation::TickNumber;
+
+use crate::input::MouseEuler;
+use crate::simulation::{
+    event::{
+        InputCommand,
+        Update,
+    },
+    resource::InputMap,
+};
+
+/// Sends the player input of every tick to the server, which moves the player
+pub struct InputSender {
+    net_sender: Option<UnboundedSender<Update>>,
+}
+
+impl InputSender {
+    pub fn new(net_sender: UnboundedSender<Update>) -> InputSender {
+        InputSender {
+            net_sender: Some(net_sender),
+        }
+    }
+}
+
+impl<'a> System<'a> for InputSender {
+    type SystemData = (
+        Read<'a, TickNumber>,
+        Read<'a, InputMap>,
+        Read<'a, MouseEuler>,
+    );
+
+    fn run(&mut self, (tick, input, mouse_euler): Self::SystemData) {
+        let command = Update::InputCommand(InputCommand {
+            tick: tick.0,
+            keys: input.keys(),
+            jump: input.move_up,
+            yaw: mouse_euler.yaw,
+        });
+
+        if let Some(net_sender) = &self.net_sender {
+            net_sender.unbounded_send(command).unwrap_or_else(|err| {
+                log::error!("failed to send input: {}", err);
+                self.net_sender = None;
+            });
+        }
+    }
+}
\ No newline at end of file
@@ -1,15 +1,22 @@
-mod collisiondetection;
-mod collisionresolver;
-mod physics;
-mod playermovement;
+mod clocksync;
+mod inputsender;
+mod recordprediction;
+mod reloadconfig;
 mod updateinputs;
 mod updatesender;
 mod updateworld;
 
-pub use collisiondetection::CollisionDetection;
-pub use collisionresolver::CollisionResolver;
-pub use physics::Physics;
-pub use playermovement::PlayerMovement;
+pub use clocksync::ClockSync;
+pub use inputsender::InputSender;
+pub use recordprediction::RecordPrediction;
+pub use reloadconfig::ReloadConfig;
 pub use updateinputs::UpdateInputs;
 pub use updatesender::UpdateSender;
-pub use updateworld::UpdateWorld;
\ No newline at end of file
+pub use updateworld::UpdateWorld;
+
+pub use eternalreckoning_core::simulation::system::{
+    CollisionDetection,
+    CollisionResolver,
+    Physics,
+    PlayerMovement,
+};
\ No newline at end of file
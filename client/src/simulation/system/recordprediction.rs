@@ -0,0 +1,38 @@
+use specs::prelude::*;
+
+use eternalreckoning_core::simulation::TickNumber;
+
+use crate::simulation::{
+    component::{
+        Position,
+        ServerID,
+    },
+    resource::{
+        ActiveCharacter,
+        PredictionHistory,
+    },
+};
+
+/// Records where the player ended up after this tick's input while connected
+pub struct RecordPrediction;
+
+impl<'a> System<'a> for RecordPrediction {
+    type SystemData = (
+        Read<'a, TickNumber>,
+        Read<'a, ActiveCharacter>,
+        ReadStorage<'a, ServerID>,
+        ReadStorage<'a, Position>,
+        Write<'a, PredictionHistory>,
+    );
+
+    fn run(&mut self, (tick, character, ids, pos, mut history): Self::SystemData) {
+        let character = match character.0 {
+            Some(character) if ids.get(character).is_some() => character,
+            _ => return,
+        };
+
+        if let Some(pos) = pos.get(character) {
+            history.record(tick.0, pos.0);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,118 @@
+use specs::prelude::*;
+
+use eternalreckoning_core::simulation::PhysicsConfig;
+
+use crate::simulation::{
+    SimulationConfig,
+    component::{
+        Jump,
+        Movement,
+    },
+    event::{
+        ConnectionEvent,
+        Event,
+    },
+    resource::{
+        EventQueue,
+        TickLength,
+    },
+};
+
+/**
+ * Keeps the movement and physics settings used for prediction in line with
+ * the simulation the player is in. On connect the server's settings replace
+ * the local ones, and on disconnect the local ones come back. Settings
+ * edited in the configuration file are applied right away, on top of the
+ * server's while connected, even though prediction then disagrees with the
+ * server. Settings are scaled to ticks the same way `build_simulation` does.
+ */
+pub struct ReloadConfig {
+    /// the local configuration, unscaled
+    local: SimulationConfig,
+    /// the settings in use, unscaled
+    applied: SimulationConfig,
+    connected: bool,
+}
+
+impl ReloadConfig {
+    pub fn new(local: SimulationConfig) -> ReloadConfig {
+        ReloadConfig {
+            applied: local.clone(),
+            local,
+            connected: false,
+        }
+    }
+
+    /// Copies the settings that differ between the old and new local
+    /// configuration into the applied ones
+    fn apply_edits(&mut self, edited: &SimulationConfig) {
+        fn edit(applied: &mut f64, old: f64, new: f64) {
+            if old != new {
+                *applied = new;
+            }
+        }
+
+        let (old, applied) = (&self.local, &mut self.applied);
+        edit(&mut applied.movement_speed, old.movement_speed, edited.movement_speed);
+        edit(&mut applied.jump_force, old.jump_force, edited.jump_force);
+
+        let (old, new, applied) = (&old.physics, &edited.physics, &mut applied.physics);
+        edit(&mut applied.gravity, old.gravity, new.gravity);
+        edit(&mut applied.min_collision_depth, old.min_collision_depth, new.min_collision_depth);
+        edit(&mut applied.max_ground_slope, old.max_ground_slope, new.max_ground_slope);
+        edit(&mut applied.horisontal_drag, old.horisontal_drag, new.horisontal_drag);
+        edit(&mut applied.vertical_drag, old.vertical_drag, new.vertical_drag);
+
+        self.local = edited.clone();
+    }
+}
+
+impl<'a> System<'a> for ReloadConfig {
+    type SystemData = (
+        Read<'a, EventQueue>,
+        Read<'a, TickLength>,
+        Write<'a, PhysicsConfig>,
+        WriteStorage<'a, Movement>,
+        WriteStorage<'a, Jump>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (events, tick_length, mut physics, mut movement, mut jump) = data;
+
+        for event in &*events {
+            match event {
+                Event::ConfigChanged(config) => {
+                    self.apply_edits(config);
+                    if self.connected {
+                        log::info!("Applied reloaded simulation configuration over the server's settings");
+                    } else {
+                        log::info!("Applied reloaded simulation configuration");
+                    }
+                },
+                Event::ConnectionEvent(ConnectionEvent::Connected(_, _, params)) => {
+                    self.connected = true;
+                    self.applied = SimulationConfig {
+                        movement_speed: params.movement_speed,
+                        jump_force: params.jump_force,
+                        physics: params.physics.clone(),
+                        ..self.local.clone()
+                    };
+                },
+                Event::ConnectionEvent(ConnectionEvent::Disconnected(_)) => {
+                    self.connected = false;
+                    self.applied = self.local.clone();
+                },
+                _ => continue,
+            }
+            let config = self.applied.scaled(&tick_length);
+
+            *physics = config.physics;
+            for mov in (&mut movement).join() {
+                mov.speed = config.movement_speed;
+            }
+            for jump in (&mut jump).join() {
+                jump.force = config.jump_force;
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -2,16 +2,19 @@ use specs::prelude::*;
 
 use crate::input::MouseEuler;
 use crate::simulation::{
+    component::PlayerInput,
     event::{
         Event,
         InputEvent,
     },
     resource::{
+        ActiveCharacter,
         EventQueue,
         InputMap,
     },
 };
 
+/// Applies input events, and hands the held input to the active character
 pub struct UpdateInputs;
 
 impl<'a> System<'a> for UpdateInputs {
@@ -19,10 +22,12 @@ impl<'a> System<'a> for UpdateInputs {
         Read<'a, EventQueue>,
         Write<'a, MouseEuler>,
         Write<'a, InputMap>,
+        Read<'a, ActiveCharacter>,
+        WriteStorage<'a, PlayerInput>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (events, mut mouse_euler, mut inputs) = data;
+        let (events, mut mouse_euler, mut inputs, character, mut player_inputs) = data;
 
         for event in &*events {
             match event {
@@ -39,5 +44,13 @@ impl<'a> System<'a> for UpdateInputs {
                 _ => (),
             }
         }
+
+        if let Some(player_input) = character.0
+            .and_then(|character| player_inputs.get_mut(character))
+        {
+            player_input.keys = inputs.keys();
+            player_input.jump = inputs.move_up;
+            player_input.yaw = mouse_euler.yaw;
+        }
     }
 }
\ No newline at end of file
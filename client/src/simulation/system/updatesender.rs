@@ -1,6 +1,6 @@
+use std::collections::HashSet;
 use std::sync::mpsc::Sender;
 
-use futures::sync::mpsc::UnboundedSender;
 use specs::prelude::*;
 
 use eternalreckoning_core::simulation::TickTime;
@@ -21,22 +21,21 @@ use super::super::{
         TerrainUpdate,
         TextureUpdate,
     },
-    resource::{
-        ActiveCamera,
-        ActiveCharacter,
-    },
+    resource::ActiveCamera,
 };
 
 pub struct UpdateSender {
     sender: Sender<Update>,
-    net_sender: Option<UnboundedSender<Update>>,
+    /// entities with a position sent to the main loop
+    visible: HashSet<Entity>,
 }
 
 impl UpdateSender {
-    pub fn new(sender: Sender<Update>, net_sender: UnboundedSender<Update>)
-        -> UpdateSender
-    {
-        UpdateSender { sender, net_sender: Some(net_sender) }
+    pub fn new(sender: Sender<Update>) -> UpdateSender {
+        UpdateSender {
+            sender,
+            visible: HashSet::new(),
+        }
     }
 }
 
@@ -54,7 +53,6 @@ impl<'a> System<'a> for UpdateSender {
         Entities<'a>,
         Read<'a, TickTime>,
         Read<'a, ActiveCamera>,
-        Read<'a, ActiveCharacter>,
         ReadStorage<'a, Model>,
         ReadStorage<'a, Terrain>,
         ReadStorage<'a, Position>,
@@ -67,7 +65,6 @@ impl<'a> System<'a> for UpdateSender {
             entities,
             tick_time,
             camera,
-            character,
             model,
             terrain,
             pos,
@@ -79,6 +76,15 @@ impl<'a> System<'a> for UpdateSender {
 
         self.send_event(Update::SimulationTick(tick_time.0));
 
+        let removed: Vec<Entity> = self.visible.iter()
+            .filter(|ent| !entities.is_alive(**ent) || pos.get(**ent).is_none())
+            .cloned()
+            .collect();
+        for ent in removed {
+            self.visible.remove(&ent);
+            self.send_event(Update::EntityRemoved(ent));
+        }
+
         for (ent, pos) in (&entities, &pos).join() {
             if Some(ent) == camera.0 {
                 self.send_event(Update::CameraUpdate(
@@ -97,16 +103,8 @@ impl<'a> System<'a> for UpdateSender {
                 }
             );
 
-            self.send_event(event.clone());
-
-            if let Some(net_sender) = &self.net_sender {
-                if character.0.is_some() && ent == character.0.unwrap() {
-                    net_sender.unbounded_send(event).unwrap_or_else(|err| {
-                        log::error!("failed to send update event: {}", err);
-                        self.net_sender = None;
-                    });
-                }
-            }
+            self.visible.insert(ent);
+            self.send_event(event);
         }
 
         for (ent, model) in (&entities, &model).join() {
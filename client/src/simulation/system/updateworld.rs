@@ -1,11 +1,24 @@
+use std::collections::HashMap;
+
+use futures::sync::mpsc::UnboundedSender;
 use specs::prelude::*;
+use uuid::Uuid;
 
-use eternalreckoning_core::net::operation;
+use eternalreckoning_core::net::{
+    operation,
+    snapshot::{
+        Snapshot,
+        SnapshotId,
+        SnapshotRing,
+        SNAPSHOT_HISTORY,
+    },
+};
 
 use crate::simulation::{
     event::{
         Event,
         ConnectionEvent,
+        Update,
     },
     component::{
         Model,
@@ -17,10 +30,156 @@ use crate::simulation::{
     resource::{
         ActiveCharacter,
         EventQueue,
+        PredictionHistory,
     },
 };
 
-pub struct UpdateWorld;
+pub struct UpdateWorld {
+    net_sender: Option<UnboundedSender<Update>>,
+    history: SnapshotRing,
+    zone_origin: nalgebra::Point3<f64>,
+}
+
+impl UpdateWorld {
+    pub fn new(net_sender: UnboundedSender<Update>) -> UpdateWorld {
+        UpdateWorld {
+            net_sender: Some(net_sender),
+            history: SnapshotRing::new(SNAPSHOT_HISTORY),
+            zone_origin: nalgebra::Point3::origin(),
+        }
+    }
+
+    /// Rebuilds the full server state from a delta update
+    fn rebuild_snapshot(&mut self, data: &operation::SvUpdateWorld)
+        -> Option<Snapshot>
+    {
+        let baseline = match data.baseline {
+            Some(id) => match self.history.get(id) {
+                Some(baseline) => Some(baseline),
+                None => {
+                    log::debug!(
+                        "Dropping world update {}, baseline {} not available",
+                        data.snapshot,
+                        id
+                    );
+                    return None;
+                },
+            },
+            None => None,
+        };
+
+        let snapshot = Snapshot::from_delta(baseline, &data.updates, &data.removed);
+        self.history.push(data.snapshot, snapshot.clone());
+
+        Some(snapshot)
+    }
+
+    fn acknowledge(&mut self, snapshot: SnapshotId) {
+        if let Some(net_sender) = &self.net_sender {
+            net_sender.unbounded_send(Update::SnapshotAck(snapshot))
+                .unwrap_or_else(|err| {
+                    log::error!("failed to send snapshot ack: {}", err);
+                    self.net_sender = None;
+                });
+        }
+    }
+
+    fn apply_components<'a>(
+        &self,
+        entity: Entity,
+        components: &[operation::EntityComponent],
+        hp: &mut WriteStorage<'a, Health>,
+        pos: &mut WriteStorage<'a, Position>,
+    ) {
+        for component in components {
+            match component {
+                operation::EntityComponent::Health(data) => {
+                    match hp.get_mut(entity) {
+                        Some(ref mut health) => health.0 = *data,
+                        None => {
+                            hp.insert(entity, Health(*data)).unwrap();
+                        },
+                    }
+                },
+                operation::EntityComponent::Position(data) => {
+                    match pos.get_mut(entity) {
+                        Some(ref mut position) => position.0 = *data,
+                        None => {
+                            pos.insert(entity, Position(*data)).unwrap();
+                        },
+                    };
+                },
+                operation::EntityComponent::QuantizedPosition(data) => {
+                    let data = data.dequantize(&self.zone_origin);
+                    match pos.get_mut(entity) {
+                        Some(ref mut position) => position.0 = data,
+                        None => {
+                            pos.insert(entity, Position(data)).unwrap();
+                        },
+                    };
+                },
+                // entities are not rendered with a facing yet
+                operation::EntityComponent::Orientation(_) => (),
+            };
+        }
+    }
+
+    /**
+     * Applies the server's state of the player, except that the position is
+     * only corrected by how far the prediction for the input tick the server
+     * reflects was off.
+     */
+    fn reconcile_player<'a>(
+        &self,
+        entity: Entity,
+        components: &[operation::EntityComponent],
+        input_tick: Option<u64>,
+        history: &mut PredictionHistory,
+        hp: &mut WriteStorage<'a, Health>,
+        pos: &mut WriteStorage<'a, Position>,
+    ) {
+        let (positions, others): (Vec<_>, Vec<_>) = components.iter()
+            .cloned()
+            .partition(|component| matches!(
+                component,
+                operation::EntityComponent::Position(_)
+                    | operation::EntityComponent::QuantizedPosition(_)
+            ));
+        self.apply_components(entity, &others, hp, pos);
+
+        let server_position = match positions.last() {
+            Some(operation::EntityComponent::Position(data)) => *data,
+            Some(operation::EntityComponent::QuantizedPosition(data)) => {
+                data.dequantize(&self.zone_origin)
+            },
+            _ => return,
+        };
+
+        if let Some(position) = pos.get_mut(entity) {
+            let correction = match input_tick {
+                Some(tick) => history.reconcile(tick, &server_position),
+                // no input applied yet, so there is nothing to replay
+                None => Some(server_position - position.0),
+            };
+
+            if let Some(correction) = correction {
+                log::trace!("Correcting predicted position by {:?}", correction);
+                position.0 += correction;
+            }
+        }
+    }
+}
+
+fn find_entity<'a>(
+    entities: &Entities<'a>,
+    id: &WriteStorage<'a, ServerID>,
+    uuid: &Uuid,
+) -> Option<Entity>
+{
+    (entities, id).join()
+        .find(|(_, server_id)| server_id.0 == *uuid)
+        .map(|(entity, _)| entity)
+}
 
 impl<'a> System<'a> for UpdateWorld {
     type SystemData = (
@@ -32,14 +191,28 @@ impl<'a> System<'a> for UpdateWorld {
         WriteStorage<'a, Texture>,
         WriteStorage<'a, Health>,
         WriteStorage<'a, Position>,
+        Write<'a, PredictionHistory>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, events, character, mut id, mut model, mut texture, mut hp, mut pos) = data;
+        let (
+            entities,
+            events,
+            character,
+            mut id,
+            mut model,
+            mut texture,
+            mut hp,
+            mut pos,
+            mut history,
+        ) = data;
 
         for event in &*events {
             match event {
-                Event::ConnectionEvent(ConnectionEvent::Connected(uuid)) => {
+                Event::ConnectionEvent(ConnectionEvent::Connected(uuid, zone_origin, _)) => {
+                    self.zone_origin = *zone_origin;
+                    history.clear();
+
                     if let Some(entity) = character.0 {
                         match id.get_mut(entity) {
                             Some(ref mut id) => id.0 = uuid.clone(),
@@ -54,56 +227,95 @@ impl<'a> System<'a> for UpdateWorld {
                     }
                 },
                 Event::ConnectionEvent(ConnectionEvent::Disconnected(_)) => {
-                    if let Some(entity) = character.0 {
+                    // whatever the server spawned is gone with the connection
+                    let spawned: Vec<Entity> = (&entities, &id).join()
+                        .map(|(entity, _)| entity)
+                        .collect();
+                    for entity in spawned {
                         id.remove(entity);
-                    };
+                        if Some(entity) != character.0 {
+                            entities.delete(entity).unwrap_or_else(|err| {
+                                log::error!("failed to despawn entity: {}", err);
+                            });
+                        }
+                    }
+                    self.history = SnapshotRing::new(SNAPSHOT_HISTORY);
                 },
+                Event::ConnectionEvent(ConnectionEvent::ClockSample(_)) => (),
                 Event::NetworkEvent(op) => {
                     match op {
-                        operation::Operation::SvUpdateWorld(data) => {
-                            for update in &data.updates {
-                                let mut entity = None;
-                                for (sim_entity, server_id) in (&entities, &id).join() {
-                                    if server_id.0 == update.uuid {
-                                        entity = Some(sim_entity);
-                                        break;
-                                    }
-                                }
+                        operation::Operation::SvSpawnEntity(data) => {
+                            let entity = match find_entity(&entities, &id, &data.uuid) {
+                                Some(entity) => entity,
+                                None => {
+                                    log::debug!("Spawned entity: {} ({:?})", data.uuid, data.archetype);
+                                    let entity = entities.create();
+                                    id.insert(entity, ServerID(data.uuid)).unwrap();
+                                    entity
+                                },
+                            };
 
-                                if entity.is_none() {
-                                    log::debug!("New entity: {}", update.uuid);
-                                    entity = Some(entities.create());
-                                    id.insert(entity.unwrap(), ServerID(update.uuid)).unwrap();
-                                    model.insert(entity.unwrap(), Model::new("assets/marker.erm")).unwrap();
-                                    texture.insert(entity.unwrap(), Texture::new("assets/marker.png")).unwrap();
+                            model.insert(entity, Model::new(&data.model)).unwrap();
+                            texture.insert(entity, Texture::new(&data.texture)).unwrap();
+                            self.apply_components(entity, &data.components, &mut hp, &mut pos);
+                        },
+                        operation::Operation::SvDespawnEntity(data) => {
+                            if let Some(entity) = find_entity(&entities, &id, &data.uuid) {
+                                if Some(entity) == character.0 {
+                                    log::warn!("Server despawned the player entity, ignoring");
+                                    continue;
                                 }
-                                let entity = entity.unwrap();
-
-                                for component in &update.data {
-                                    match component {
-                                        operation::EntityComponent::Health(data) => {
-                                            match hp.get_mut(entity) {
-                                                Some(ref mut health) => health.0 = *data,
-                                                None => {
-                                                    hp.insert(entity, Health(*data)).unwrap();
-                                                },
-                                            }
-                                        },
-                                        operation::EntityComponent::Position(data) => {
-                                            match pos.get_mut(entity) {
-                                                Some(ref mut position) => position.0 = *data,
-                                                None => {
-                                                    pos.insert(entity, Position(*data)).unwrap();
-                                                },
-                                            };
-                                        },
-                                    };
+
+                                log::debug!("Despawned entity: {}", data.uuid);
+                                entities.delete(entity).unwrap_or_else(|err| {
+                                    log::error!("failed to despawn {}: {}", data.uuid, err);
+                                });
+                            }
+                        },
+                        operation::Operation::SvUpdateWorld(data) => {
+                            let snapshot = match self.rebuild_snapshot(data) {
+                                Some(snapshot) => snapshot,
+                                None => continue,
+                            };
+
+                            // the server resends spawns until a snapshot holding
+                            // only known entities is acknowledged
+                            let mut complete = true;
+
+                            let known: HashMap<Uuid, Entity> = (&entities, &id).join()
+                                .map(|(entity, server_id)| (server_id.0, entity))
+                                .collect();
+
+                            for (uuid, components) in snapshot.entities() {
+                                match known.get(uuid).copied() {
+                                    Some(entity) if Some(entity) == character.0 => {
+                                        self.reconcile_player(
+                                            entity,
+                                            components,
+                                            data.input_tick,
+                                            &mut history,
+                                            &mut hp,
+                                            &mut pos
+                                        );
+                                    },
+                                    Some(entity) => {
+                                        self.apply_components(entity, components, &mut hp, &mut pos);
+                                    },
+                                    None => {
+                                        log::debug!("Update for unspawned entity: {}", uuid);
+                                        complete = false;
+                                    },
                                 }
                             }
+
+                            if complete {
+                                self.acknowledge(data.snapshot);
+                            }
                         },
                         _ => (),
                     };
                 },
+                Event::ConfigChanged(_) => (),
                 Event::InputEvent(_) => (),
             }
         }
@@ -1,5 +1,9 @@
 use serde::{Serialize, Deserialize};
 
+use eternalreckoning_core::util::config::{
+    Validate,
+    Validator,
+};
 use eternalreckoning_core::util::logging::LoggingConfig;
 
 use crate::client::ClientConfig;
@@ -30,4 +34,13 @@ impl Default for Config {
             simulation: SimulationConfig::default(),
         }
     }
+}
+
+impl Validate for Config {
+    fn validate(&self, validator: &mut Validator) {
+        validator.nested("client", &self.client);
+        validator.nested("display", &self.display);
+        validator.nested("mouse", &self.mouse);
+        validator.nested("simulation", &self.simulation);
+    }
 }
\ No newline at end of file
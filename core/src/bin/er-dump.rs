@@ -0,0 +1,268 @@
+use std::collections::{
+    BTreeMap,
+    HashMap,
+};
+use std::env;
+use std::fs::File;
+use std::io::{
+    BufReader,
+    Cursor,
+    Read,
+};
+use std::net::SocketAddr;
+use std::process;
+
+use bytes::BytesMut;
+use failure::{
+    format_err,
+    Error,
+};
+use tokio::codec::Decoder;
+
+use eternalreckoning_core::net::{
+    capture::{
+        pcap::PcapReader,
+        CaptureError,
+        CaptureReader,
+        CaptureRecord,
+        Direction,
+        CAPTURE_MAGIC,
+    },
+    codec::{
+        EternalReckoningCodec,
+        OpcodeType,
+    },
+    operation::Operation,
+    PROTOCOL_VERSION,
+};
+
+const USAGE: &str = "\
+Usage: er-dump [options] <capture or pcap file>
+
+Decodes recorded Eternal Reckoning traffic.
+
+Options:
+    -s, --summary            only print the per-opcode summary
+    -o, --opcode <code>      only show operations with this opcode, repeatable
+    -d, --direction <in|out> only show traffic received or sent by the recorder
+        --peer <address>     only show traffic exchanged with this peer
+    -p, --port <port>        server port in pcap files [default: 6142]
+    -h, --help               print this message";
+
+type Records = Box<dyn Iterator<Item = Result<CaptureRecord, CaptureError>>>;
+
+struct Options {
+    path: String,
+    summary_only: bool,
+    opcodes: Vec<OpcodeType>,
+    direction: Option<Direction>,
+    peer: Option<SocketAddr>,
+    port: u16,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, Error> {
+        let mut options = Options {
+            path: String::new(),
+            summary_only: false,
+            opcodes: Vec::new(),
+            direction: None,
+            peer: None,
+            port: 6142,
+        };
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next().ok_or_else(|| format_err!("{} requires a value", arg))
+            };
+
+            match &arg[..] {
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    process::exit(0);
+                },
+                "-s" | "--summary" => options.summary_only = true,
+                "-o" | "--opcode" => options.opcodes.push(parse_opcode(&value()?)?),
+                "-d" | "--direction" => {
+                    options.direction = Some(match &value()?[..] {
+                        "in" => Direction::Inbound,
+                        "out" => Direction::Outbound,
+                        direction => return Err(format_err!("invalid direction: {}", direction)),
+                    });
+                },
+                "--peer" => options.peer = Some(value()?.parse()?),
+                "-p" | "--port" => options.port = value()?.parse()?,
+                _ if arg.starts_with('-') => return Err(format_err!("unknown option: {}", arg)),
+                _ if options.path.is_empty() => options.path = arg.clone(),
+                _ => return Err(format_err!("unexpected argument: {}", arg)),
+            }
+        }
+
+        if options.path.is_empty() {
+            return Err(format_err!("no input file given"));
+        }
+
+        Ok(options)
+    }
+
+    fn shows(&self, record: &CaptureRecord) -> bool {
+        self.direction.is_none_or(|direction| record.direction == direction)
+            && self.peer.is_none_or(|peer| record.peer == peer)
+    }
+}
+
+fn parse_opcode(value: &str) -> Result<OpcodeType, Error> {
+    let opcode = match value.strip_prefix("0x") {
+        Some(hex) => OpcodeType::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    opcode.map_err(|_| format_err!("invalid opcode: {}", value))
+}
+
+/// Opens a capture file, or a pcap file if it does not start with the capture magic
+fn open(options: &Options) -> Result<Records, Error> {
+    let mut input = BufReader::new(File::open(&options.path)?);
+
+    let mut magic = [0; 4];
+    input.read_exact(&mut magic)?;
+    let input = Cursor::new(magic).chain(input);
+
+    if magic == CAPTURE_MAGIC {
+        let reader = CaptureReader::new(input)?;
+        if reader.protocol_version() != PROTOCOL_VERSION {
+            eprintln!(
+                "warning: captured with protocol version {}, decoding as version {}",
+                reader.protocol_version(),
+                PROTOCOL_VERSION
+            );
+        }
+        return Ok(Box::new(reader));
+    }
+
+    let port = options.port;
+    let reader = PcapReader::new(input)?
+        .filter_map(move |datagram| match datagram {
+            Ok(datagram) => datagram.to_record(port).map(Ok),
+            Err(err) => Some(Err(err)),
+        });
+    Ok(Box::new(reader))
+}
+
+#[derive(Default)]
+struct OpcodeSummary {
+    name: String,
+    count: u64,
+    bytes: u64,
+}
+
+fn run(options: Options) -> Result<(), Error> {
+    let mut codecs = HashMap::<(SocketAddr, Direction), EternalReckoningCodec>::new();
+    let mut summary = BTreeMap::<(OpcodeType, Direction), OpcodeSummary>::new();
+    let mut start = None;
+    let mut packets = 0;
+
+    for record in open(&options)? {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                eprintln!("warning: stopped reading input: {}", err);
+                break;
+            },
+        };
+
+        let start = *start.get_or_insert(record.time);
+        if !options.shows(&record) {
+            continue;
+        }
+        packets += 1;
+
+        // every peer and direction has a sequence of its own
+        let codec = codecs.entry((record.peer, record.direction))
+            .or_default();
+
+        let mut buf = BytesMut::from(&record.data[..]);
+        while !buf.is_empty() {
+            let op = match codec.decode(&mut buf)? {
+                Some(op) => op,
+                None => break,
+            };
+            if !options.opcodes.is_empty() && !options.opcodes.contains(&op.opcode()) {
+                continue;
+            }
+
+            let mut payload = BytesMut::new();
+            op.encode_payload(&mut payload);
+
+            let entry = summary.entry((op.opcode(), record.direction))
+                .or_default();
+            entry.name = op.to_string();
+            entry.count += 1;
+            entry.bytes += payload.len() as u64;
+
+            if !options.summary_only {
+                print_operation(&record, start, &op, payload.len());
+            }
+        }
+    }
+
+    if !options.summary_only {
+        println!();
+    }
+    println!("{:<8} {:<4} {:>8} {:>10}  name", "opcode", "dir", "count", "bytes");
+    for ((opcode, direction), entry) in &summary {
+        println!(
+            "{:<8} {:<4} {:>8} {:>10}  {}",
+            format!("{:#04x}", opcode),
+            direction_name(*direction),
+            entry.count,
+            entry.bytes,
+            entry.name
+        );
+    }
+    println!(
+        "{} packets, {} operations",
+        packets,
+        summary.values().map(|entry| entry.count).sum::<u64>()
+    );
+
+    Ok(())
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Inbound => "in",
+        Direction::Outbound => "out",
+    }
+}
+
+fn print_operation(record: &CaptureRecord, start: u64, op: &Operation, size: usize) {
+    let arrow = match record.direction {
+        Direction::Inbound => "<-",
+        Direction::Outbound => "->",
+    };
+
+    println!(
+        "{:>12.6} {} {:<22} {:#04x} {} ({} bytes)",
+        record.time.saturating_sub(start) as f64 / 1_000_000.0,
+        arrow,
+        record.peer,
+        op.opcode(),
+        op,
+        size
+    );
+}
+
+fn main() {
+    let options = match Options::parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            process::exit(2);
+        },
+    };
+
+    if let Err(err) = run(options) {
+        eprintln!("er-dump: {}", err);
+        process::exit(1);
+    }
+}
\ No newline at end of file
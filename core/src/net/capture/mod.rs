@@ -0,0 +1,316 @@
+use std::fs::File;
+use std::io::{
+    BufWriter,
+    Read,
+    Write,
+};
+use std::net::{
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+    SocketAddr,
+};
+use std::sync::{
+    Arc,
+    Mutex,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use bytes::{BufMut, BytesMut};
+use failure_derive::Fail;
+
+use super::clock::{
+    timestamp_now,
+    Timestamp,
+};
+use super::PROTOCOL_VERSION;
+
+pub mod pcap;
+
+/// How often buffered records are written out to the capture file
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// First bytes of every capture file
+pub const CAPTURE_MAGIC: [u8; 4] = *b"ERCP";
+const CAPTURE_VERSION: u16 = 1;
+
+#[derive(Debug, Fail)]
+pub enum CaptureError {
+    #[fail(display = "i/o error: {}", _0)]
+    Io(#[cause] std::io::Error),
+    #[fail(display = "not a capture file")]
+    BadMagic,
+    #[fail(display = "unsupported capture format version {}", _0)]
+    UnsupportedVersion(u16),
+    #[fail(display = "unsupported pcap link type {}", _0)]
+    UnsupportedLinkType(u32),
+    #[fail(display = "invalid address family {}", _0)]
+    InvalidAddress(u8),
+    #[fail(display = "capture ends in the middle of a record")]
+    Truncated,
+}
+
+impl From<std::io::Error> for CaptureError {
+    fn from(err: std::io::Error) -> CaptureError {
+        match err.kind() {
+            std::io::ErrorKind::UnexpectedEof => CaptureError::Truncated,
+            _ => CaptureError::Io(err),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    /// received from the peer
+    Inbound,
+    /// sent to the peer
+    Outbound,
+}
+
+/// A single packet as it was sent or received
+#[derive(Clone, Debug)]
+pub struct CaptureRecord {
+    pub time: Timestamp,
+    pub direction: Direction,
+    pub peer: SocketAddr,
+    pub data: Vec<u8>,
+}
+
+/**
+ * Writes capture files: a magic, the capture format version and the
+ * protocol version, followed by one record per packet. A record is the
+ * time, direction, peer address and length of the packet, then the
+ * packet as it appeared on the wire.
+ */
+pub struct CaptureWriter<W: Write> {
+    output: W,
+    last_flush: Instant,
+}
+
+impl<W: Write> CaptureWriter<W> {
+    pub fn new(mut output: W) -> Result<CaptureWriter<W>, CaptureError> {
+        let mut buf = BytesMut::with_capacity(8);
+        buf.put_slice(&CAPTURE_MAGIC[..]);
+        buf.put_u16_le(CAPTURE_VERSION);
+        buf.put_u16_le(PROTOCOL_VERSION);
+        output.write_all(&buf[..])?;
+
+        Ok(CaptureWriter {
+            output,
+            last_flush: Instant::now(),
+        })
+    }
+
+    pub fn write(&mut self, record: &CaptureRecord) -> Result<(), CaptureError> {
+        let mut buf = BytesMut::with_capacity(32 + record.data.len());
+
+        buf.put_u64_le(record.time);
+        buf.put_u8(match record.direction {
+            Direction::Inbound => 0,
+            Direction::Outbound => 1,
+        });
+        match record.peer.ip() {
+            IpAddr::V4(ip) => {
+                buf.put_u8(4);
+                buf.put_slice(&ip.octets()[..]);
+            },
+            IpAddr::V6(ip) => {
+                buf.put_u8(6);
+                buf.put_slice(&ip.octets()[..]);
+            },
+        }
+        buf.put_u16_le(record.peer.port());
+        buf.put_u32_le(record.data.len() as u32);
+        buf.put_slice(&record.data[..]);
+
+        self.output.write_all(&buf[..])?;
+
+        // flushing every record would cost a write per packet
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.flush()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), CaptureError> {
+        self.output.flush()?;
+        self.last_flush = Instant::now();
+
+        Ok(())
+    }
+}
+
+pub struct CaptureReader<R: Read> {
+    input: R,
+    protocol_version: u16,
+}
+
+impl<R: Read> CaptureReader<R> {
+    pub fn new(mut input: R) -> Result<CaptureReader<R>, CaptureError> {
+        let mut magic = [0; 4];
+        input.read_exact(&mut magic)?;
+        if magic != CAPTURE_MAGIC {
+            return Err(CaptureError::BadMagic);
+        }
+
+        let version = read_u16(&mut input)?;
+        if version != CAPTURE_VERSION {
+            return Err(CaptureError::UnsupportedVersion(version));
+        }
+
+        let protocol_version = read_u16(&mut input)?;
+
+        Ok(CaptureReader { input, protocol_version })
+    }
+
+    /// Protocol version of the process that wrote the capture
+    pub fn protocol_version(&self) -> u16 {
+        self.protocol_version
+    }
+
+    pub fn next_record(&mut self) -> Result<Option<CaptureRecord>, CaptureError> {
+        let mut time = [0; 8];
+        match self.input.read(&mut time[..1])? {
+            0 => return Ok(None),
+            _ => self.input.read_exact(&mut time[1..])?,
+        }
+        let time = u64::from_le_bytes(time);
+
+        let mut byte = [0; 1];
+        self.input.read_exact(&mut byte)?;
+        let direction = match byte[0] {
+            0 => Direction::Inbound,
+            _ => Direction::Outbound,
+        };
+
+        self.input.read_exact(&mut byte)?;
+        let ip = match byte[0] {
+            4 => {
+                let mut octets = [0; 4];
+                self.input.read_exact(&mut octets)?;
+                IpAddr::V4(Ipv4Addr::from(octets))
+            },
+            6 => {
+                let mut octets = [0; 16];
+                self.input.read_exact(&mut octets)?;
+                IpAddr::V6(Ipv6Addr::from(octets))
+            },
+            family => return Err(CaptureError::InvalidAddress(family)),
+        };
+        let port = read_u16(&mut self.input)?;
+
+        let mut len = [0; 4];
+        self.input.read_exact(&mut len)?;
+        let mut data = vec![0; u32::from_le_bytes(len) as usize];
+        self.input.read_exact(&mut data)?;
+
+        Ok(Some(CaptureRecord {
+            time,
+            direction,
+            peer: SocketAddr::new(ip, port),
+            data,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = Result<CaptureRecord, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+fn read_u16<R: Read>(input: &mut R) -> Result<u16, CaptureError> {
+    let mut bytes = [0; 2];
+    input.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+/**
+ * Shared handle to a capture file. Codecs holding a clone record every
+ * packet they encode or decode. Records are buffered, and written out
+ * at least once a second and when the last handle is dropped.
+ */
+#[derive(Clone)]
+pub struct Capture {
+    writer: Arc<Mutex<CaptureWriter<BufWriter<File>>>>,
+}
+
+impl Capture {
+    pub fn create(path: &str) -> Result<Capture, CaptureError> {
+        let writer = CaptureWriter::new(BufWriter::new(File::create(path)?))?;
+        log::info!("Recording packets to {}", path);
+
+        Ok(Capture { writer: Arc::new(Mutex::new(writer)) })
+    }
+
+    pub fn record(&self, direction: Direction, peer: SocketAddr, data: &[u8]) {
+        let record = CaptureRecord {
+            time: timestamp_now(),
+            direction,
+            peer,
+            data: data.to_vec(),
+        };
+
+        let result = match self.writer.lock() {
+            Ok(mut writer) => writer.write(&record),
+            Err(_) => return,
+        };
+        result.unwrap_or_else(|err| {
+            log::error!("Failed to record packet: {}", err);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_round_trip() {
+        let records = vec![
+            CaptureRecord {
+                time: 1_000_000,
+                direction: Direction::Inbound,
+                peer: "127.0.0.1:50000".parse().unwrap(),
+                data: vec![0xEC, 0xAA, 0x00],
+            },
+            CaptureRecord {
+                time: 1_000_100,
+                direction: Direction::Outbound,
+                peer: "[::1]:6142".parse().unwrap(),
+                data: Vec::new(),
+            },
+        ];
+
+        let mut writer = CaptureWriter::new(Vec::new()).unwrap();
+        for record in &records {
+            writer.write(record).unwrap();
+        }
+        let file = writer.output;
+
+        let reader = CaptureReader::new(&file[..]).unwrap();
+        assert_eq!(reader.protocol_version(), PROTOCOL_VERSION);
+
+        let read: Vec<CaptureRecord> = reader.map(Result::unwrap).collect();
+        assert_eq!(read.len(), 2);
+        for (read, record) in read.iter().zip(&records) {
+            assert_eq!(read.time, record.time);
+            assert_eq!(read.direction, record.direction);
+            assert_eq!(read.peer, record.peer);
+            assert_eq!(read.data, record.data);
+        }
+
+        let mut truncated = CaptureReader::new(&file[..file.len() - 1]).unwrap();
+        assert!(truncated.next_record().unwrap().is_some());
+        match truncated.next_record() {
+            Err(CaptureError::Truncated) => (),
+            _ => panic!("truncated record accepted"),
+        }
+    }
+}
\ No newline at end of file
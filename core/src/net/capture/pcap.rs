@@ -0,0 +1,284 @@
+use std::io::Read;
+use std::net::{
+    IpAddr,
+    Ipv4Addr,
+    Ipv6Addr,
+    SocketAddr,
+};
+
+use super::{
+    CaptureError,
+    CaptureRecord,
+    Direction,
+};
+use crate::net::clock::Timestamp;
+
+const MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+
+const ETHERTYPE_IPV4: u16 = 0x0800;
+const ETHERTYPE_IPV6: u16 = 0x86DD;
+const ETHERTYPE_VLAN: u16 = 0x8100;
+
+const IP_PROTOCOL_UDP: u8 = 17;
+
+/// A UDP datagram found in a pcap file
+#[derive(Clone, Debug)]
+pub struct UdpDatagram {
+    pub time: Timestamp,
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    pub payload: Vec<u8>,
+}
+
+impl UdpDatagram {
+    /**
+     * Converts the datagram to a capture record as seen by the server
+     * listening on `server_port`, or None if the server took no part in it.
+     */
+    pub fn to_record(&self, server_port: u16) -> Option<CaptureRecord> {
+        let (direction, peer) = if self.destination.port() == server_port {
+            (Direction::Inbound, self.source)
+        } else if self.source.port() == server_port {
+            (Direction::Outbound, self.destination)
+        } else {
+            return None;
+        };
+
+        Some(CaptureRecord {
+            time: self.time,
+            direction,
+            peer,
+            data: self.payload.clone(),
+        })
+    }
+}
+
+/**
+ * Reads the UDP datagrams of a classic pcap file. Other traffic, IP
+ * fragments and IPv6 extension headers are skipped.
+ */
+pub struct PcapReader<R: Read> {
+    input: R,
+    big_endian: bool,
+    nanos: bool,
+    link_type: u32,
+}
+
+impl<R: Read> PcapReader<R> {
+    pub fn new(mut input: R) -> Result<PcapReader<R>, CaptureError> {
+        let mut header = [0; 24];
+        input.read_exact(&mut header)?;
+
+        let magic = [header[0], header[1], header[2], header[3]];
+        let (big_endian, nanos) = if u32::from_le_bytes(magic) == MAGIC_MICROS {
+            (false, false)
+        } else if u32::from_be_bytes(magic) == MAGIC_MICROS {
+            (true, false)
+        } else if u32::from_le_bytes(magic) == MAGIC_NANOS {
+            (false, true)
+        } else if u32::from_be_bytes(magic) == MAGIC_NANOS {
+            (true, true)
+        } else {
+            return Err(CaptureError::BadMagic);
+        };
+
+        let mut reader = PcapReader { input, big_endian, nanos, link_type: 0 };
+        reader.link_type = reader.u32_at(&header, 20);
+
+        match reader.link_type {
+            LINKTYPE_NULL | LINKTYPE_ETHERNET | LINKTYPE_RAW |
+            LINKTYPE_LINUX_SLL | LINKTYPE_IPV4 | LINKTYPE_IPV6 => Ok(reader),
+            link_type => Err(CaptureError::UnsupportedLinkType(link_type)),
+        }
+    }
+
+    fn u32_at(&self, data: &[u8], offset: usize) -> u32 {
+        let bytes = [data[offset], data[offset + 1], data[offset + 2], data[offset + 3]];
+        match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        }
+    }
+
+    /// Next UDP datagram in the file, skipping other packets
+    pub fn next_datagram(&mut self) -> Result<Option<UdpDatagram>, CaptureError> {
+        loop {
+            let mut header = [0; 16];
+            match self.input.read(&mut header[..1])? {
+                0 => return Ok(None),
+                _ => self.input.read_exact(&mut header[1..])?,
+            }
+
+            let seconds = self.u32_at(&header, 0) as u64;
+            let fraction = self.u32_at(&header, 4) as u64;
+            let time = seconds * 1_000_000 + match self.nanos {
+                true => fraction / 1_000,
+                false => fraction,
+            };
+
+            let mut frame = vec![0; self.u32_at(&header, 8) as usize];
+            self.input.read_exact(&mut frame)?;
+
+            if let Some(datagram) = self.parse_frame(&frame, time) {
+                return Ok(Some(datagram));
+            }
+        }
+    }
+
+    fn parse_frame(&self, frame: &[u8], time: Timestamp) -> Option<UdpDatagram> {
+        let ip = match self.link_type {
+            LINKTYPE_NULL => frame.get(4..)?,
+            LINKTYPE_ETHERNET => {
+                let mut ethertype = be_u16(frame, 12)?;
+                let mut offset = 14;
+                while ethertype == ETHERTYPE_VLAN {
+                    ethertype = be_u16(frame, offset + 2)?;
+                    offset += 4;
+                }
+                match ethertype {
+                    ETHERTYPE_IPV4 | ETHERTYPE_IPV6 => frame.get(offset..)?,
+                    _ => return None,
+                }
+            },
+            LINKTYPE_LINUX_SLL => match be_u16(frame, 14)? {
+                ETHERTYPE_IPV4 | ETHERTYPE_IPV6 => frame.get(16..)?,
+                _ => return None,
+            },
+            _ => frame,
+        };
+
+        parse_ip(ip, time)
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<UdpDatagram, CaptureError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_datagram().transpose()
+    }
+}
+
+fn be_u16(data: &[u8], offset: usize) -> Option<u16> {
+    let bytes = data.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+fn parse_ip(packet: &[u8], time: Timestamp) -> Option<UdpDatagram> {
+    let (source, destination, udp) = match packet.first()? >> 4 {
+        4 => {
+            let header_len = ((packet[0] & 0x0F) as usize) * 4;
+            let total_len = be_u16(packet, 2)? as usize;
+            let fragment = be_u16(packet, 6)?;
+            // more fragments flag or a fragment offset
+            if fragment & 0x3FFF != 0 || *packet.get(9)? != IP_PROTOCOL_UDP {
+                return None;
+            }
+
+            let source = packet.get(12..16)?;
+            let destination = packet.get(16..20)?;
+            (
+                IpAddr::V4(Ipv4Addr::new(source[0], source[1], source[2], source[3])),
+                IpAddr::V4(Ipv4Addr::new(
+                    destination[0], destination[1], destination[2], destination[3]
+                )),
+                packet.get(header_len..total_len)?,
+            )
+        },
+        6 => {
+            if *packet.get(6)? != IP_PROTOCOL_UDP {
+                return None;
+            }
+            let payload_len = be_u16(packet, 4)? as usize;
+
+            let mut source = [0; 16];
+            source.copy_from_slice(packet.get(8..24)?);
+            let mut destination = [0; 16];
+            destination.copy_from_slice(packet.get(24..40)?);
+            (
+                IpAddr::V6(Ipv6Addr::from(source)),
+                IpAddr::V6(Ipv6Addr::from(destination)),
+                packet.get(40..40 + payload_len)?,
+            )
+        },
+        _ => return None,
+    };
+
+    let source_port = be_u16(udp, 0)?;
+    let destination_port = be_u16(udp, 2)?;
+    let len = be_u16(udp, 4)? as usize;
+
+    Some(UdpDatagram {
+        time,
+        source: SocketAddr::new(source, source_port),
+        destination: SocketAddr::new(destination, destination_port),
+        payload: udp.get(8..len)?.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ethernet_udp_frame(payload: &[u8]) -> Vec<u8> {
+        let mut frame = vec![0; 12];
+        frame.extend_from_slice(&ETHERTYPE_IPV4.to_be_bytes());
+
+        let total_len = 20 + 8 + payload.len() as u16;
+        frame.extend_from_slice(&[0x45, 0]);
+        frame.extend_from_slice(&total_len.to_be_bytes());
+        frame.extend_from_slice(&[0, 0, 0x40, 0, 64, IP_PROTOCOL_UDP, 0, 0]);
+        frame.extend_from_slice(&[127, 0, 0, 1, 127, 0, 0, 2]);
+
+        frame.extend_from_slice(&50000u16.to_be_bytes());
+        frame.extend_from_slice(&6142u16.to_be_bytes());
+        frame.extend_from_slice(&(8 + payload.len() as u16).to_be_bytes());
+        frame.extend_from_slice(&[0, 0]);
+        frame.extend_from_slice(payload);
+
+        // ethernet padding is not part of the datagram
+        frame.extend_from_slice(&[0; 6]);
+        frame
+    }
+
+    #[test]
+    fn test_read_ethernet_pcap() {
+        let mut file = Vec::new();
+        file.extend_from_slice(&MAGIC_MICROS.to_le_bytes());
+        file.extend_from_slice(&[2, 0, 4, 0]);
+        file.extend_from_slice(&[0; 8]);
+        file.extend_from_slice(&65535u32.to_le_bytes());
+        file.extend_from_slice(&LINKTYPE_ETHERNET.to_le_bytes());
+
+        let frame = ethernet_udp_frame(b"payload");
+        file.extend_from_slice(&2u32.to_le_bytes());
+        file.extend_from_slice(&500u32.to_le_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&(frame.len() as u32).to_le_bytes());
+        file.extend_from_slice(&frame);
+
+        let datagrams: Vec<UdpDatagram> = PcapReader::new(&file[..])
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(datagrams.len(), 1);
+
+        let datagram = &datagrams[0];
+        assert_eq!(datagram.time, 2_000_500);
+        assert_eq!(datagram.source, "127.0.0.1:50000".parse().unwrap());
+        assert_eq!(&datagram.payload[..], &b"payload"[..]);
+
+        let record = datagram.to_record(6142).unwrap();
+        assert_eq!(record.direction, Direction::Inbound);
+        assert_eq!(record.peer, datagram.source);
+        assert!(datagram.to_record(7000).is_none());
+    }
+}
\ No newline at end of file
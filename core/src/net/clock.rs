@@ -0,0 +1,151 @@
+use std::time::{
+    Duration,
+    SystemTime,
+    UNIX_EPOCH,
+};
+
+/// Microseconds since the UNIX epoch
+pub type Timestamp = u64;
+
+// smoothing factors from RFC 6298
+const RTT_GAIN: f64 = 1.0 / 8.0;
+const JITTER_GAIN: f64 = 1.0 / 4.0;
+const OFFSET_GAIN: f64 = 1.0 / 8.0;
+
+pub fn timestamp_now() -> Timestamp {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_micros() as Timestamp)
+        .unwrap_or(0)
+}
+
+/// One ClSync/SvSync exchange, as seen by the client
+#[derive(Clone, Debug)]
+pub struct ClockSample {
+    /// client clock when the ClSync was sent
+    pub sent: Timestamp,
+    /// server clock when the SvSync was sent
+    pub server_time: Timestamp,
+    pub server_tick: u64,
+    /// client clock when the SvSync arrived
+    pub received: Timestamp,
+}
+
+/**
+ * Smoothed round-trip time, jitter and server clock offset, estimated from
+ * clock sync exchanges. The offset assumes the server answered halfway
+ * through the round trip.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct NetworkClock {
+    rtt: Option<f64>,
+    jitter: f64,
+    offset: f64,
+    server_tick: Option<u64>,
+    samples: u64,
+}
+
+impl NetworkClock {
+    pub fn new() -> NetworkClock {
+        NetworkClock::default()
+    }
+
+    pub fn sample(&mut self, sample: &ClockSample) {
+        if sample.received < sample.sent {
+            log::debug!("Ignoring clock sample from before a clock change");
+            return;
+        }
+
+        let rtt = (sample.received - sample.sent) as f64;
+        let offset = sample.server_time as f64 - (sample.sent as f64 + rtt / 2.0);
+
+        match self.rtt {
+            Some(smoothed) => {
+                self.jitter += JITTER_GAIN * ((smoothed - rtt).abs() - self.jitter);
+                self.rtt = Some(smoothed + RTT_GAIN * (rtt - smoothed));
+                self.offset += OFFSET_GAIN * (offset - self.offset);
+            },
+            None => {
+                self.jitter = rtt / 2.0;
+                self.rtt = Some(rtt);
+                self.offset = offset;
+            },
+        }
+
+        self.server_tick = Some(sample.server_tick);
+        self.samples += 1;
+    }
+
+    /// Smoothed round-trip time, once at least one sample has arrived
+    pub fn rtt(&self) -> Option<Duration> {
+        self.rtt.map(|rtt| Duration::from_micros(rtt as u64))
+    }
+
+    /// Smoothed mean deviation of the round-trip time
+    pub fn jitter(&self) -> Duration {
+        Duration::from_micros(self.jitter as u64)
+    }
+
+    /// Server clock minus client clock, in microseconds
+    pub fn offset(&self) -> i64 {
+        self.offset as i64
+    }
+
+    /// Estimated server clock at the given client time
+    pub fn server_time(&self, client_time: Timestamp) -> Timestamp {
+        (client_time as i64 + self.offset()) as Timestamp
+    }
+
+    /// Server tick reported by the most recent sample
+    pub fn server_tick(&self) -> Option<u64> {
+        self.server_tick
+    }
+
+    pub fn samples(&self) -> u64 {
+        self.samples
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(sent: Timestamp, server_time: Timestamp, received: Timestamp) -> ClockSample {
+        ClockSample { sent, server_time, server_tick: 1, received }
+    }
+
+    #[test]
+    fn test_first_sample() {
+        let mut clock = NetworkClock::new();
+        assert!(clock.rtt().is_none());
+
+        // server clock 5s ahead, 40ms round trip
+        clock.sample(&sample(1_000_000, 6_020_000, 1_040_000));
+
+        assert_eq!(clock.rtt(), Some(Duration::from_millis(40)));
+        assert_eq!(clock.offset(), 5_000_000);
+        assert_eq!(clock.server_time(2_000_000), 7_000_000);
+        assert_eq!(clock.server_tick(), Some(1));
+    }
+
+    #[test]
+    fn test_smoothing() {
+        let mut clock = NetworkClock::new();
+        for i in 0..100 {
+            let sent = i * 1_000_000;
+            clock.sample(&sample(sent, sent + 25_000, sent + 50_000));
+        }
+        assert_eq!(clock.rtt(), Some(Duration::from_millis(50)));
+
+        // a single spike moves the estimate only partially
+        clock.sample(&sample(200_000_000, 200_125_000, 200_250_000));
+        let rtt = clock.rtt().unwrap();
+        assert!(rtt > Duration::from_millis(50) && rtt < Duration::from_millis(100));
+        assert!(clock.jitter() > Duration::from_millis(0));
+        assert_eq!(clock.offset(), 0);
+
+        // samples from before a clock change are ignored
+        clock.sample(&sample(300_000_000, 0, 100_000_000));
+        assert_eq!(clock.samples(), 101);
+    }
+}
@@ -2,10 +2,26 @@ use failure_derive::Fail;
 
 use super::opcode::OpcodeType;
 
-#[derive(Debug, Fail)]
+#[derive(Debug, Fail, PartialEq)]
 pub enum CodecError {
     #[fail(display = "invalid opcode: {:02X}", _0)]
     InvalidOpcode(OpcodeType),
-    #[fail(display = "invalid data")]
-    BadData,
+    #[fail(display = "packet magic not found")]
+    BadMagic,
+    #[fail(display = "data ended before the value was complete")]
+    Truncated,
+    #[fail(display = "payload size {} does not match the {} decoded bytes", declared, decoded)]
+    SizeMismatch { declared: usize, decoded: usize },
+    #[fail(display = "unknown component code: {:02X}", _0)]
+    UnknownComponent(u8),
+    #[fail(display = "invalid option flag: {:02X}", _0)]
+    InvalidFlag(u8),
+    #[fail(display = "string is not valid UTF-8")]
+    InvalidUtf8,
+    #[fail(display = "position precision out of range: {}", _0)]
+    InvalidPrecision(u8),
+    #[fail(display = "invalid fragment")]
+    InvalidFragment,
+    #[fail(display = "operation too large to fragment: {} bytes", _0)]
+    OperationTooLarge(usize),
 }
\ No newline at end of file
@@ -1,52 +1,236 @@
-use bytes::BytesMut;
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use bytes::{BufMut, BytesMut};
 use failure::Error;
-use lazy_static::lazy_static;
 use tokio::codec::{Decoder, Encoder};
 
 #[cfg(test)]
-use bytes::{Buf, BufMut};
+use bytes::Buf;
 
+use crate::net::capture::{
+    Capture,
+    Direction,
+};
 use crate::net::operation::Operation;
 
 #[cfg(test)]
 use crate::net::operation;
 
 use super::{
-    encdec,
     error::CodecError,
+    fragment::{
+        FragmentHeader,
+        Reassembler,
+        FRAGMENT_HEADER_SIZE,
+        MAX_FRAGMENTS,
+        MAX_FRAGMENT_SIZE,
+        MAX_PAYLOAD_SIZE,
+    },
     header::{
         Header,
         HEADER_SIZE,
     },
     opcode::{
         self,
-        opcode_from_operation,
+        OpcodeType,
+    },
+    sequence::{
+        SequenceNumber,
+        SequenceState,
     },
 };
 
-type EncoderFn = fn(Operation, &mut BytesMut);
-type DecoderFn = fn(&Header, &mut BytesMut) -> Result<Option<Operation>, CodecError>;
-lazy_static! {
-    static ref ENCODER_TABLE: [EncoderFn; std::u8::MAX as usize + 1] = {
-        let mut table = [encdec::encode_no_body as EncoderFn; std::u8::MAX as usize + 1];
-        table[opcode::SV_CONNECT_RESPONSE_OP as usize] = encdec::encode_sv_connect_response;
-        table[opcode::SV_UPDATE_WORLD_OP as usize] = encdec::encode_sv_update_world;
-        table[opcode::CL_MOVE_SET_POSITION_OP as usize] = encdec::encode_cl_move_set_position;
-        table
-    };
-
-    static ref DECODER_TABLE: [DecoderFn; std::u8::MAX as usize + 1] = {
-        let mut table = [encdec::decode_invalid_op as DecoderFn; std::u8::MAX as usize + 1];
-        table[opcode::CL_CONNECT_MESSAGE_OP as usize] = encdec::decode_cl_connect_message;
-        table[opcode::SV_CONNECT_RESPONSE_OP as usize] = encdec::decode_sv_connect_response;
-        table[opcode::SV_UPDATE_WORLD_OP as usize] = encdec::decode_sv_update_world;
-        table[opcode::DISCONNECT_MESSAGE_OP as usize] = encdec::decode_disconnect_message;
-        table[opcode::CL_MOVE_SET_POSITION_OP as usize] = encdec::decode_cl_move_set_position;
-        table
-    };
+/**
+ * Codec for a single connection. Besides framing operations, it stamps
+ * outgoing packets with sequence numbers and acknowledgements, drops
+ * duplicate or stale incoming packets, and reports which of the sent
+ * packets the peer has acknowledged or lost.
+ *
+ * Operations too large for one datagram are split into fragments, each sent
+ * as its own packet, and reassembled on receipt.
+ *
+ * With a capture attached, every packet encoded and every well-formed
+ * packet decoded is recorded.
+ */
+pub struct EternalReckoningCodec {
+    sequence: SequenceState,
+    next_fragment_group: u16,
+    reassembler: Reassembler,
+    capture: Option<(Capture, SocketAddr)>,
+    decode_errors: u64,
 }
 
-pub struct EternalReckoningCodec;
+impl EternalReckoningCodec {
+    pub fn new() -> EternalReckoningCodec {
+        EternalReckoningCodec {
+            sequence: SequenceState::new(),
+            next_fragment_group: 0,
+            reassembler: Reassembler::default(),
+            capture: None,
+            decode_errors: 0,
+        }
+    }
+
+    /// Records the packets exchanged with `peer` to `capture`
+    pub fn with_capture(mut self, capture: Capture, peer: SocketAddr)
+        -> EternalReckoningCodec
+    {
+        self.capture = Some((capture, peer));
+        self
+    }
+
+    /**
+     * Encodes an operation into one or more datagrams, none larger than
+     * `MAX_DATAGRAM_SIZE`. Every datagram must be sent separately.
+     */
+    pub fn encode_datagrams(&mut self, packet: Operation)
+        -> Result<Vec<BytesMut>, Error>
+    {
+        log::trace!("Encoding: {}", &packet);
+
+        let opcode = packet.opcode();
+        let mut payload = BytesMut::new();
+        packet.encode_payload(&mut payload);
+
+        if payload.len() <= MAX_PAYLOAD_SIZE {
+            return Ok(vec![self.encode_packet(opcode, &payload[..])]);
+        }
+
+        let count = payload.len().div_ceil(MAX_FRAGMENT_SIZE);
+        if count > MAX_FRAGMENTS {
+            return Err(CodecError::OperationTooLarge(payload.len()).into());
+        }
+
+        let group = self.next_fragment_group;
+        self.next_fragment_group = self.next_fragment_group.wrapping_add(1);
+
+        let mut datagrams = Vec::with_capacity(count);
+        for (index, chunk) in payload.chunks(MAX_FRAGMENT_SIZE).enumerate() {
+            let mut fragment = BytesMut::with_capacity(FRAGMENT_HEADER_SIZE + chunk.len());
+            FragmentHeader {
+                group,
+                index: index as u8,
+                count: count as u8,
+                opcode,
+            }.write(&mut fragment);
+            fragment.put_slice(chunk);
+
+            datagrams.push(self.encode_packet(opcode::FRAGMENT_OP, &fragment[..]));
+        }
+
+        Ok(datagrams)
+    }
+
+    fn encode_packet(&mut self, opcode: OpcodeType, payload: &[u8]) -> BytesMut {
+        let mut buf = BytesMut::with_capacity(HEADER_SIZE + payload.len());
+
+        let (sequence, ack, ack_bits) = self.sequence.next_outgoing();
+        Header::new(opcode, payload.len())
+            .with_sequence(sequence, ack, ack_bits)
+            .write(&mut buf);
+        buf.put_slice(payload);
+
+        if let Some((capture, peer)) = &self.capture {
+            capture.record(Direction::Outbound, *peer, &buf[..]);
+        }
+
+        buf
+    }
+
+    /**
+     * Decodes the packet at the start of `buf`, which must be complete. The
+     * buffer is only consumed if the packet is valid; duplicate packets and
+     * fragments of incomplete operations decode to None.
+     */
+    fn decode_packet(&mut self, buf: &mut BytesMut)
+        -> Result<Option<Operation>, CodecError>
+    {
+        let header = Header::read(&buf[..])?;
+        let end = HEADER_SIZE + header.size;
+        if buf.len() < end {
+            return Err(CodecError::Truncated);
+        }
+
+        let payload = &buf[HEADER_SIZE..end];
+        let (op, fragment) = if header.opcode == opcode::FRAGMENT_OP {
+            (None, Some(FragmentHeader::read(payload)?))
+        } else {
+            (Some(Operation::decode_payload(header.opcode, payload)?), None)
+        };
+
+        let packet = buf.split_to(end);
+
+        if let Some((capture, peer)) = &self.capture {
+            capture.record(Direction::Inbound, *peer, &packet[..]);
+        }
+
+        if !self.sequence.receive(header.sequence, header.ack, header.ack_bits) {
+            log::trace!("Dropping duplicate or stale packet {}", header.sequence);
+            return Ok(None);
+        }
+
+        if let Some(fragment) = fragment {
+            let data = &packet[HEADER_SIZE + FRAGMENT_HEADER_SIZE..];
+            return Ok(self.reassemble(&fragment, data));
+        }
+
+        Ok(op)
+    }
+
+    /**
+     * Feeds a fragment to the reassembler, decoding the fragmented
+     * operation once it is complete.
+     */
+    fn reassemble(&mut self, fragment: &FragmentHeader, data: &[u8])
+        -> Option<Operation>
+    {
+        let result = self.reassembler.insert(fragment, data, Instant::now())
+            .and_then(|complete| match complete {
+                Some((opcode, payload)) => {
+                    Operation::decode_payload(opcode, &payload[..]).map(Some)
+                },
+                None => Ok(None),
+            });
+
+        result.unwrap_or_else(|err| {
+            log::debug!("Dropping fragmented operation: {}", err);
+            self.decode_errors += 1;
+            None
+        })
+    }
+
+    /// Sequence number of the most recently encoded packet
+    pub fn last_sequence(&self) -> Option<SequenceNumber> {
+        self.sequence.last_sent()
+    }
+
+    /// Sent packets acknowledged by the peer since the last call
+    pub fn take_acknowledged(&mut self) -> Vec<SequenceNumber> {
+        self.sequence.take_acknowledged()
+    }
+
+    /// Sent packets that fell out of the acknowledgement window unacknowledged
+    pub fn take_lost(&mut self) -> Vec<SequenceNumber> {
+        self.sequence.take_lost()
+    }
+
+    /// Malformed packets and fragmented operations dropped so far
+    pub fn decode_errors(&self) -> u64 {
+        self.decode_errors
+    }
+
+    /// Bytes held by incompletely received fragmented operations
+    pub fn reassembly_buffered(&self) -> usize {
+        self.reassembler.buffered()
+    }
+}
+
+impl Default for EternalReckoningCodec {
+    fn default() -> EternalReckoningCodec {
+        EternalReckoningCodec::new()
+    }
+}
 
 impl Encoder for EternalReckoningCodec {
     type Item = Operation;
@@ -55,16 +239,11 @@ impl Encoder for EternalReckoningCodec {
     fn encode(&mut self, packet: Self::Item, buf: &mut BytesMut)
         -> Result<(), Self::Error>
     {
-        log::trace!("Encoding: {}", &packet);
-
-        buf.reserve(HEADER_SIZE);
-        let mut payload_buf = buf.split_off(HEADER_SIZE);
-
-        let opcode = opcode_from_operation(&packet);
-        ENCODER_TABLE[opcode as usize](packet, &mut payload_buf);
-        Header::new(opcode, payload_buf.len()).write(buf);
-
-        buf.unsplit(payload_buf);
+        // fragments are written back to back; use encode_datagrams to keep
+        // each of them in a datagram of its own
+        for datagram in self.encode_datagrams(packet)? {
+            buf.unsplit(datagram);
+        }
 
         Ok(())
     }
@@ -77,38 +256,31 @@ impl Decoder for EternalReckoningCodec {
     fn decode(&mut self, buf: &mut BytesMut)
         -> Result<Option<Self::Item>, Self::Error>
     {
-        if buf.len() == 0 {
+        if buf.is_empty() {
             return Ok(Some(Operation::DisconnectMessage));
         }
 
         loop {
-            if let Some(packet_pos) = Header::find(buf) {
-                buf.advance(packet_pos);
-            } else {
-                return Ok(None);
+            match Header::find(&buf[..]) {
+                Some(packet_pos) => buf.advance(packet_pos),
+                None => {
+                    // nothing left in the datagram can start a packet
+                    buf.clear();
+                    return Ok(None);
+                },
             }
 
-            match Header::read(buf) {
-                Ok(None) => return Ok(None),
-                Err(_) => (),
-                Ok(Some(header)) => {
-                    let mut payload_buf = buf.split_off(HEADER_SIZE);
-                    let result = DECODER_TABLE[header.opcode as usize](&header, &mut payload_buf);
-                    buf.unsplit(payload_buf);
-                    match result {
-                        Ok(None) => return Ok(None),
-                        Ok(Some(op)) => {
-                            buf.split_to(HEADER_SIZE + header.size);
-                            return Ok(Some(op));
-                        },
-                        Err(err) => {
-                            log::debug!("{}", err);
-                        },
-                    }
+            match self.decode_packet(buf) {
+                Ok(Some(op)) => return Ok(Some(op)),
+                Ok(None) => (),
+                Err(err) => {
+                    log::debug!("Skipping malformed packet: {}", err);
+                    self.decode_errors += 1;
+
+                    // resynchronise on the next magic
+                    buf.advance(1);
                 },
             }
-
-            buf.advance(1);
         }
     }
 }
@@ -116,13 +288,23 @@ impl Decoder for EternalReckoningCodec {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::net::codec::MAX_DATAGRAM_SIZE;
+    use crate::net::schema::Sample;
+
+    fn connect_message() -> Operation {
+        Operation::ClConnectMessage(operation::ClConnectMessage {
+            protocol_version: crate::net::PROTOCOL_VERSION,
+            client_build: "test".to_string(),
+            player_name: "player".to_string(),
+        })
+    }
 
     #[test]
     fn test_encode_header() {
-        let mut codec = EternalReckoningCodec;
-        let mut buf = BytesMut::with_capacity(8);
+        let mut codec = EternalReckoningCodec::new();
+        let mut buf = BytesMut::with_capacity(HEADER_SIZE);
 
-        let op = Operation::ClConnectMessage(operation::ClConnectMessage);
+        let op = connect_message();
 
         codec.encode(op, &mut buf).unwrap();
 
@@ -133,18 +315,22 @@ mod tests {
         assert_eq!(cursor.get_u8(), 0xAA);
 
         // size
-        assert_eq!(cursor.get_u16_le(), 0);
+        assert_eq!(cursor.get_u16_le(), 16);
 
         // opcode
-        assert_eq!(cursor.get_u8(), opcode::CL_CONNECT_MESSAGE_OP);
+        assert_eq!(cursor.get_u8(), operation::ClConnectMessage::OPCODE);
+
+        // sequence, ack, ack bits
+        assert_eq!(cursor.get_u16_le(), 0);
+        assert_eq!(cursor.get_u16_le(), 0);
+        assert_eq!(cursor.get_u32_le(), 0);
 
-        // padding
-        assert_eq!(cursor.remaining(), 3);
+        assert_eq!(cursor.remaining(), 16);
     }
 
     #[test]
     fn test_decode_empty_buffer() {
-        let mut codec = EternalReckoningCodec;
+        let mut codec = EternalReckoningCodec::new();
         let mut buf = BytesMut::from(&[][..]);
 
         match codec.decode(&mut buf) {
@@ -155,31 +341,44 @@ mod tests {
 
     #[test]
     fn test_decode_header() {
-        let mut codec = EternalReckoningCodec;
-        let mut buf = BytesMut::with_capacity(8);
+        let mut codec = EternalReckoningCodec::new();
+        let mut buf = BytesMut::with_capacity(HEADER_SIZE);
 
         // magic
         buf.put_slice(&[0xEC, 0xAA][..]);
 
         // size
-        buf.put_u16_le(0);
+        buf.put_u16_le(16);
 
         // opcode
-        buf.put_u8(opcode::CL_CONNECT_MESSAGE_OP);
+        buf.put_u8(operation::ClConnectMessage::OPCODE);
+
+        // sequence, ack, ack bits
+        buf.put_u16_le(0);
+        buf.put_u16_le(0);
+        buf.put_u32_le(0);
 
-        // padding
-        buf.put_slice(&[0x00, 0x00, 0x00][..]);
+        // protocol version, client build, player name
+        buf.put_u16_le(7);
+        buf.put_u16_le(4);
+        buf.put_slice(&b"test"[..]);
+        buf.put_u16_le(6);
+        buf.put_slice(&b"player"[..]);
 
         match codec.decode(&mut buf) {
-            Ok(Some(Operation::ClConnectMessage(operation::ClConnectMessage))) => (),
+            Ok(Some(Operation::ClConnectMessage(data))) => {
+                assert_eq!(data.protocol_version, 7);
+                assert_eq!(data.client_build, "test");
+                assert_eq!(data.player_name, "player");
+            },
             _ => panic!("Invalid decode for ClConnectMessage"),
         }
     }
 
     #[test]
     fn test_decode_empty_world_update() {
-        let mut codec = EternalReckoningCodec;
-        let mut buf = BytesMut::with_capacity(8 + 24);
+        let mut codec = EternalReckoningCodec::new();
+        let mut buf = BytesMut::with_capacity(HEADER_SIZE + 34);
 
         let uuid = uuid::Uuid::from_slice(
             &b"\xd1qHq\xdb\xbdNe\xa9f\xc6\xe5|I\xbaG"[..]
@@ -187,9 +386,16 @@ mod tests {
         
         // header
         buf.put_slice(&b"\xec\xaa"[..]);
-        buf.put_u16_le(24);
-        buf.put_u8(opcode::SV_UPDATE_WORLD_OP);
-        buf.put_slice(&b"\0\0\0"[..]);
+        buf.put_u16_le(34);
+        buf.put_u8(operation::SvUpdateWorld::OPCODE);
+        buf.put_u16_le(0);
+        buf.put_u16_le(0);
+        buf.put_u32_le(0);
+
+        // snapshot, no baseline, no input tick
+        buf.put_u32_le(1);
+        buf.put_u8(0);
+        buf.put_u8(0);
 
         // entity count
         buf.put_u32_le(1);
@@ -201,11 +407,17 @@ mod tests {
 
         // component count
         buf.put_u32_le(0);
+
+        // removed entity count
+        buf.put_u32_le(0);
         
         match codec.decode(&mut buf) {
             Ok(Some(Operation::SvUpdateWorld(data))) => {
+                assert_eq!(data.snapshot, 1);
+                assert!(data.baseline.is_none());
+                assert!(data.removed.is_empty());
                 assert_eq!(data.updates.len(), 1);
-                let update = data.updates.get(0).unwrap();
+                let update = data.updates.first().unwrap();
 
                 assert_eq!(&update.uuid, &uuid);
                 assert_eq!(update.data.len(), 0);
@@ -213,4 +425,255 @@ mod tests {
             _ => panic!("Invalid decode for SvUpdateWorld"),
         }
     }
+
+    #[test]
+    fn test_decode_errors() {
+        let snapshot_ack = operation::ClSnapshotAck::OPCODE;
+        assert_eq!(
+            Operation::decode_payload(snapshot_ack, &[1, 0, 0][..]).err(),
+            Some(CodecError::Truncated)
+        );
+        assert_eq!(
+            Operation::decode_payload(snapshot_ack, &[1, 0, 0, 0, 0][..]).err(),
+            Some(CodecError::SizeMismatch { declared: 5, decoded: 4 })
+        );
+
+        // snapshot, no baseline or input tick, one entity with one unknown component
+        let mut payload = BytesMut::with_capacity(4 + 1 + 1 + 4 + 16 + 4 + 1);
+        payload.put_u32_le(1);
+        payload.put_u8(0);
+        payload.put_u8(0);
+        payload.put_u32_le(1);
+        payload.put_slice(uuid::Uuid::nil().as_bytes());
+        payload.put_u32_le(1);
+        payload.put_u8(0x7F);
+        assert_eq!(
+            Operation::decode_payload(operation::SvUpdateWorld::OPCODE, &payload[..]).err(),
+            Some(CodecError::UnknownComponent(0x7F))
+        );
+
+        assert_eq!(
+            Operation::decode_payload(0x7F, &[][..]).err(),
+            Some(CodecError::InvalidOpcode(0x7F))
+        );
+    }
+
+    #[test]
+    fn test_resynchronise_after_garbage() {
+        let mut codec = EternalReckoningCodec::new();
+        let mut buf = BytesMut::with_capacity(64);
+
+        // a stray magic with an impossible size, then a valid packet
+        buf.put_slice(&[0x00, 0xEC, 0xAA, 0xFF, 0xFF, 0x11][..]);
+        EternalReckoningCodec::new().encode(connect_message(), &mut buf).unwrap();
+        buf.extend_from_slice(&[0xEC][..]);
+
+        match codec.decode(&mut buf) {
+            Ok(Some(Operation::ClConnectMessage(_))) => (),
+            _ => panic!("Decoder did not resynchronise"),
+        }
+        assert_eq!(codec.decode_errors(), 1);
+        assert!(codec.decode(&mut buf).unwrap().is_none());
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_drop_duplicate_packet() {
+        let mut client = EternalReckoningCodec::new();
+        let mut server = EternalReckoningCodec::new();
+
+        let mut buf = BytesMut::new();
+        client.encode(connect_message(), &mut buf).unwrap();
+        let datagram = buf.clone();
+
+        match server.decode(&mut buf) {
+            Ok(Some(Operation::ClConnectMessage(_))) => (),
+            _ => panic!("Invalid decode for ClConnectMessage"),
+        }
+
+        let mut buf = datagram;
+        match server.decode(&mut buf) {
+            Ok(None) => (),
+            _ => panic!("Duplicate packet was not dropped"),
+        }
+    }
+
+    #[test]
+    fn test_acknowledge_packets() {
+        let mut client = EternalReckoningCodec::new();
+        let mut server = EternalReckoningCodec::new();
+
+        // client sends 0..=2, packet 1 is lost
+        let mut received = BytesMut::new();
+        for sequence in 0..3 {
+            let mut buf = BytesMut::new();
+            client.encode(connect_message(), &mut buf).unwrap();
+            assert_eq!(client.last_sequence(), Some(sequence));
+            if sequence != 1 {
+                received.unsplit(buf);
+            }
+        }
+        while !received.is_empty() {
+            server.decode(&mut received).unwrap();
+        }
+
+        let mut buf = BytesMut::new();
+        server.encode(Operation::SvConnectResponse(
+            operation::SvConnectResponse {
+                uuid: uuid::Uuid::nil(),
+                zone_origin: nalgebra::Point3::origin(),
+                params: operation::SimulationParams::sample(),
+            }
+        ), &mut buf).unwrap();
+        assert!(client.decode(&mut buf).unwrap().is_some());
+
+        assert_eq!(client.take_acknowledged(), vec![0, 2]);
+        assert!(client.take_acknowledged().is_empty());
+        assert!(client.take_lost().is_empty());
+
+        // push packet 1 out of the acknowledgement window
+        for _ in 0..32 {
+            let mut buf = BytesMut::new();
+            client.encode(connect_message(), &mut buf).unwrap();
+            server.decode(&mut buf).unwrap();
+        }
+
+        let mut buf = BytesMut::new();
+        server.encode(Operation::SvConnectResponse(
+            operation::SvConnectResponse {
+                uuid: uuid::Uuid::nil(),
+                zone_origin: nalgebra::Point3::origin(),
+                params: operation::SimulationParams::sample(),
+            }
+        ), &mut buf).unwrap();
+        client.decode(&mut buf).unwrap();
+
+        assert_eq!(client.take_lost(), vec![1]);
+        assert_eq!(client.take_acknowledged().len(), 32);
+    }
+
+    #[test]
+    fn test_connect_rejected() {
+        let mut server = EternalReckoningCodec::new();
+        let mut client = EternalReckoningCodec::new();
+
+        let mut buf = BytesMut::new();
+        server.encode(Operation::SvConnectRejected(
+            operation::SvConnectRejected {
+                reason: operation::RejectReason::ServerFull,
+                message: "server is full (8/8)".to_string(),
+            }
+        ), &mut buf).unwrap();
+
+        match client.decode(&mut buf) {
+            Ok(Some(Operation::SvConnectRejected(data))) => {
+                assert_eq!(data.reason, operation::RejectReason::ServerFull);
+                assert_eq!(data.message, "server is full (8/8)");
+            },
+            _ => panic!("Invalid decode for SvConnectRejected"),
+        }
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_fragmented_world_update() {
+        let mut server = EternalReckoningCodec::new();
+        let mut client = EternalReckoningCodec::new();
+
+        let updates: Vec<operation::EntityUpdate> = (0..1000u32)
+            .map(|i| operation::EntityUpdate {
+                uuid: uuid::Uuid::from_u128(i as u128 + 1),
+                data: vec![operation::EntityComponent::Health(i as u64)],
+            })
+            .collect();
+
+        let datagrams = server.encode_datagrams(Operation::SvUpdateWorld(
+            operation::SvUpdateWorld {
+                snapshot: 1,
+                baseline: None,
+                input_tick: None,
+                updates: updates.clone(),
+                removed: Vec::new(),
+            }
+        )).unwrap();
+
+        assert!(datagrams.len() > 1);
+        assert!(datagrams.iter().all(|datagram| datagram.len() <= MAX_DATAGRAM_SIZE));
+
+        // deliver out of order, with one duplicate
+        let mut decoded = None;
+        let last = datagrams.len() - 1;
+        for index in (1..=last).rev().chain(std::iter::once(last)).chain(std::iter::once(0)) {
+            let mut buf = datagrams[index].clone();
+            if let Some(op) = client.decode(&mut buf).unwrap() {
+                assert!(decoded.is_none());
+                decoded = Some(op);
+            }
+        }
+
+        match decoded {
+            Some(Operation::SvUpdateWorld(data)) => {
+                assert_eq!(data.updates.len(), updates.len());
+                for (decoded, update) in data.updates.iter().zip(&updates) {
+                    assert_eq!(decoded.uuid, update.uuid);
+                    assert!(decoded.data == update.data);
+                }
+            },
+            _ => panic!("Fragmented world update was not reassembled"),
+        }
+        assert_eq!(client.reassembly_buffered(), 0);
+    }
+
+    fn encoded_world_update(component: operation::EntityComponent) -> BytesMut {
+        let mut codec = EternalReckoningCodec::new();
+        let mut buf = BytesMut::new();
+
+        let op = Operation::SvUpdateWorld(operation::SvUpdateWorld {
+            snapshot: 1,
+            baseline: None,
+            input_tick: None,
+            updates: vec![operation::EntityUpdate {
+                uuid: uuid::Uuid::nil(),
+                data: vec![component],
+            }],
+            removed: Vec::new(),
+        });
+        codec.encode(op, &mut buf).unwrap();
+
+        buf
+    }
+
+    #[test]
+    fn test_quantized_position_size() {
+        use crate::net::quantization::{
+            PositionEncoding,
+            QuantizationConfig,
+        };
+
+        let position = nalgebra::Point3::new(12.5, -1.0, 30.25);
+        let mut config = QuantizationConfig::default();
+
+        let mut sizes = Vec::new();
+        for encoding in &[
+            PositionEncoding::Float,
+            PositionEncoding::Fixed32,
+            PositionEncoding::Fixed16,
+        ] {
+            config.position_encoding = *encoding;
+            let component = config.position(&position);
+            let mut buf = encoded_world_update(component.clone());
+            sizes.push(buf.len());
+
+            match EternalReckoningCodec::new().decode(&mut buf) {
+                Ok(Some(Operation::SvUpdateWorld(data))) => {
+                    assert!(data.updates[0].data[0] == component);
+                },
+                _ => panic!("Invalid decode for SvUpdateWorld"),
+            }
+        }
+
+        // component code + 3 x f64, 3 x i32 or 3 x i16 with precision
+        assert_eq!(sizes[0] - sizes[1], 24 - 13);
+        assert_eq!(sizes[0] - sizes[2], 24 - 7);
+    }
 }
\ No newline at end of file
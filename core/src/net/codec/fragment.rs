@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use bytes::{Buf, BufMut, BytesMut};
+
+use super::error::CodecError;
+use super::header::HEADER_SIZE;
+use super::opcode::OpcodeType;
+
+pub type FragmentGroup = u16;
+
+/// Largest datagram the codec will produce, kept below common path MTUs
+pub const MAX_DATAGRAM_SIZE: usize = 1200;
+/// Largest operation payload sent without fragmentation
+pub const MAX_PAYLOAD_SIZE: usize = MAX_DATAGRAM_SIZE - HEADER_SIZE;
+
+pub const FRAGMENT_HEADER_SIZE: usize = 5;
+/// Operation payload carried by a single fragment
+pub const MAX_FRAGMENT_SIZE: usize = MAX_PAYLOAD_SIZE - FRAGMENT_HEADER_SIZE;
+pub const MAX_FRAGMENTS: usize = u8::MAX as usize;
+
+const FRAGMENT_TIMEOUT: Duration = Duration::from_secs(2);
+const MAX_REASSEMBLY_BYTES: usize = 1 << 20;
+
+/**
+ * Prefix of every fragment payload: the group shared by all fragments of an
+ * operation, the index of this fragment, the number of fragments in the
+ * group and the opcode of the fragmented operation.
+ */
+pub struct FragmentHeader {
+    pub group: FragmentGroup,
+    pub index: u8,
+    pub count: u8,
+    pub opcode: OpcodeType,
+}
+
+impl FragmentHeader {
+    pub fn read(buf: &[u8]) -> Result<FragmentHeader, CodecError> {
+        if buf.len() < FRAGMENT_HEADER_SIZE {
+            return Err(CodecError::Truncated);
+        }
+
+        let mut data = std::io::Cursor::new(buf);
+        let header = FragmentHeader {
+            group: data.get_u16_le(),
+            index: data.get_u8(),
+            count: data.get_u8(),
+            opcode: data.get_u8(),
+        };
+
+        if header.count < 2 || header.index >= header.count {
+            return Err(CodecError::InvalidFragment);
+        }
+
+        Ok(header)
+    }
+
+    pub fn write(&self, buf: &mut BytesMut) {
+        buf.reserve(FRAGMENT_HEADER_SIZE);
+
+        buf.put_u16_le(self.group);
+        buf.put_u8(self.index);
+        buf.put_u8(self.count);
+        buf.put_u8(self.opcode);
+    }
+}
+
+struct PartialOperation {
+    opcode: OpcodeType,
+    fragments: Vec<Option<BytesMut>>,
+    received: usize,
+    size: usize,
+    started: Instant,
+}
+
+/**
+ * Collects fragments until an operation is complete. Incomplete operations
+ * are dropped once they time out, or oldest first when buffered fragments
+ * exceed the memory cap.
+ */
+pub struct Reassembler {
+    timeout: Duration,
+    max_bytes: usize,
+    buffered: usize,
+    partial: HashMap<FragmentGroup, PartialOperation>,
+}
+
+impl Reassembler {
+    pub fn new(timeout: Duration, max_bytes: usize) -> Reassembler {
+        Reassembler {
+            timeout,
+            max_bytes,
+            buffered: 0,
+            partial: HashMap::new(),
+        }
+    }
+
+    /// Bytes held by incomplete operations
+    pub fn buffered(&self) -> usize {
+        self.buffered
+    }
+
+    /**
+     * Adds a fragment, returning the opcode and payload of the operation
+     * once all of its fragments have arrived.
+     */
+    pub fn insert(
+        &mut self,
+        header: &FragmentHeader,
+        data: &[u8],
+        now: Instant,
+    ) -> Result<Option<(OpcodeType, BytesMut)>, CodecError>
+    {
+        self.expire(now);
+
+        let consistent = match self.partial.get(&header.group) {
+            Some(partial) => {
+                partial.opcode == header.opcode &&
+                    partial.fragments.len() == header.count as usize
+            },
+            None => true,
+        };
+        if !consistent {
+            self.remove(header.group);
+            return Err(CodecError::InvalidFragment);
+        }
+
+        while self.buffered + data.len() > self.max_bytes {
+            if !self.evict_oldest() {
+                return Err(CodecError::InvalidFragment);
+            }
+        }
+
+        let partial = self.partial.entry(header.group)
+            .or_insert_with(|| PartialOperation {
+                opcode: header.opcode,
+                fragments: vec![None; header.count as usize],
+                received: 0,
+                size: 0,
+                started: now,
+            });
+
+        let fragment = &mut partial.fragments[header.index as usize];
+        if fragment.is_some() {
+            return Ok(None);
+        }
+        *fragment = Some(BytesMut::from(data));
+        partial.received += 1;
+        partial.size += data.len();
+        self.buffered += data.len();
+
+        if partial.received < partial.fragments.len() {
+            return Ok(None);
+        }
+
+        let partial = self.remove(header.group).unwrap();
+        let mut payload = BytesMut::with_capacity(partial.size);
+        for fragment in partial.fragments.into_iter().flatten() {
+            payload.unsplit(fragment);
+        }
+
+        Ok(Some((partial.opcode, payload)))
+    }
+
+    fn expire(&mut self, now: Instant) {
+        let timeout = self.timeout;
+        let expired: Vec<FragmentGroup> = self.partial.iter()
+            .filter(|(_, partial)| now.saturating_duration_since(partial.started) >= timeout)
+            .map(|(group, _)| *group)
+            .collect();
+
+        for group in expired {
+            log::debug!("Dropping incomplete fragment group {}", group);
+            self.remove(group);
+        }
+    }
+
+    fn evict_oldest(&mut self) -> bool {
+        let oldest = self.partial.iter()
+            .min_by_key(|(_, partial)| partial.started)
+            .map(|(group, _)| *group);
+
+        match oldest {
+            Some(group) => {
+                log::debug!("Fragment buffer full, dropping group {}", group);
+                self.remove(group);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn remove(&mut self, group: FragmentGroup) -> Option<PartialOperation> {
+        let partial = self.partial.remove(&group)?;
+        self.buffered -= partial.size;
+        Some(partial)
+    }
+}
+
+impl Default for Reassembler {
+    fn default() -> Reassembler {
+        Reassembler::new(FRAGMENT_TIMEOUT, MAX_REASSEMBLY_BYTES)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(group: FragmentGroup, index: u8, count: u8) -> FragmentHeader {
+        FragmentHeader { group, index, count, opcode: 0x10 }
+    }
+
+    #[test]
+    fn test_reassemble_out_of_order() {
+        let mut reassembler = Reassembler::default();
+        let now = Instant::now();
+
+        assert!(reassembler.insert(&header(1, 2, 3), b"ghi", now).unwrap().is_none());
+        assert!(reassembler.insert(&header(1, 0, 3), b"abc", now).unwrap().is_none());
+        assert!(reassembler.insert(&header(1, 0, 3), b"abc", now).unwrap().is_none());
+        assert_eq!(reassembler.buffered(), 6);
+
+        let (opcode, payload) = reassembler.insert(&header(1, 1, 3), b"def", now)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(opcode, 0x10);
+        assert_eq!(&payload[..], &b"abcdefghi"[..]);
+        assert_eq!(reassembler.buffered(), 0);
+    }
+
+    #[test]
+    fn test_incomplete_groups_expire() {
+        let mut reassembler = Reassembler::new(Duration::from_millis(100), 1024);
+        let now = Instant::now();
+
+        reassembler.insert(&header(1, 0, 2), b"abc", now).unwrap();
+        reassembler.insert(&header(2, 0, 2), b"abc", now + Duration::from_millis(200)).unwrap();
+        assert_eq!(reassembler.buffered(), 3);
+
+        let later = now + Duration::from_millis(250);
+        assert!(reassembler.insert(&header(1, 1, 2), b"def", later).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_memory_cap_evicts_oldest() {
+        let mut reassembler = Reassembler::new(FRAGMENT_TIMEOUT, 8);
+        let now = Instant::now();
+
+        reassembler.insert(&header(1, 0, 2), b"abcd", now).unwrap();
+        reassembler.insert(&header(2, 0, 2), b"abcd", now + Duration::from_millis(1)).unwrap();
+        reassembler.insert(&header(3, 0, 2), b"abcd", now + Duration::from_millis(2)).unwrap();
+        assert_eq!(reassembler.buffered(), 8);
+
+        // group 1 was evicted, so its second half starts a new group
+        assert!(reassembler.insert(&header(1, 1, 2), b"efgh", now).unwrap().is_none());
+        assert!(reassembler.insert(&header(3, 1, 2), b"efgh", now).unwrap().is_some());
+    }
+}
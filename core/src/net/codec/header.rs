@@ -1,72 +1,116 @@
-use std::io::{
-    Cursor,
-    Seek,
-    SeekFrom,
-};
+use std::io::Cursor;
 
 use bytes::{Buf, BufMut, BytesMut};
 
 use super::error::CodecError;
 use super::opcode::OpcodeType;
+use super::sequence::{
+    AckBits,
+    SequenceNumber,
+};
 
 type MagicType = [u8; 2];
 const PACKET_MAGIC: MagicType = [0xEC, 0xAA];
 
-pub static HEADER_SIZE: usize = 8;
+pub const HEADER_SIZE: usize = 13;
 pub struct Header {
     pub size: usize,
     pub opcode: OpcodeType,
+    pub sequence: SequenceNumber,
+    pub ack: SequenceNumber,
+    pub ack_bits: AckBits,
 }
 
 impl Header {
     pub fn new(opcode: OpcodeType, size: usize) -> Header {
-        Header { size, opcode }
+        Header {
+            size,
+            opcode,
+            sequence: 0,
+            ack: 0,
+            ack_bits: 0,
+        }
     }
 
-    pub fn find(buf: &mut BytesMut) -> Option<usize> {
-        let mut data = Cursor::new(&buf);
-        while data.remaining() >= PACKET_MAGIC.len() {
-            let position = data.position();
-
-            for byte in &PACKET_MAGIC[..] {
-                if data.get_u8() != *byte {
-                    data.seek(SeekFrom::Start(position + 1))
-                        .unwrap(); // safe, as we just were there
-                    break;
-                }
-            }
-
-            return Some(position as usize);
-        }
+    pub fn with_sequence(
+        mut self,
+        sequence: SequenceNumber,
+        ack: SequenceNumber,
+        ack_bits: AckBits,
+    ) -> Header
+    {
+        self.sequence = sequence;
+        self.ack = ack;
+        self.ack_bits = ack_bits;
+        self
+    }
 
-        None
+    /// Offset of the first packet magic in `buf`
+    pub fn find(buf: &[u8]) -> Option<usize> {
+        buf.windows(PACKET_MAGIC.len())
+            .position(|window| window == &PACKET_MAGIC[..])
     }
 
-    pub fn read(buf: &mut BytesMut) -> Result<Option<Header>, CodecError> {
+    pub fn read(buf: &[u8]) -> Result<Header, CodecError> {
         let data = match buf.get(0..HEADER_SIZE) {
             Some(data) => data,
-            None => return Ok(None),
+            None => return Err(CodecError::Truncated),
         };
 
         let mut data = Cursor::new(data);
         for byte in &PACKET_MAGIC[..] {
             if data.get_u8() != *byte {
-                return Err(CodecError::BadData);
+                return Err(CodecError::BadMagic);
             }
         }
 
         let size = data.get_u16_le();
         let opcode = data.get_u8();
+        let sequence = data.get_u16_le();
+        let ack = data.get_u16_le();
+        let ack_bits = data.get_u32_le();
 
-        Ok(Some(Header::new(opcode, size as usize)))
+        Ok(
+            Header::new(opcode, size as usize)
+                .with_sequence(sequence, ack, ack_bits)
+        )
     }
 
     pub fn write(&self, buf: &mut BytesMut) {
+        debug_assert!(self.size <= u16::MAX as usize, "payload must be fragmented");
         buf.reserve(HEADER_SIZE);
 
         buf.put_slice(&PACKET_MAGIC[..]);
         buf.put_u16_le(self.size as u16);
         buf.put_u8(self.opcode);
-        buf.put_slice(&[0, 0, 0][..]);
+        buf.put_u16_le(self.sequence);
+        buf.put_u16_le(self.ack);
+        buf.put_u32_le(self.ack_bits);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_magic() {
+        assert_eq!(Header::find(&[0xEC, 0xAA][..]), Some(0));
+        assert_eq!(Header::find(&[0x00, 0xEC, 0x00, 0xEC, 0xAA][..]), Some(3));
+        assert_eq!(Header::find(&[0xAA, 0xEC][..]), None);
+        assert_eq!(Header::find(&[0xEC][..]), None);
+        assert_eq!(Header::find(&[][..]), None);
+    }
+
+    #[test]
+    fn test_read_truncated_header() {
+        let mut buf = BytesMut::new();
+        Header::new(0x10, 4).write(&mut buf);
+
+        assert!(Header::read(&buf[..]).is_ok());
+        assert_eq!(
+            Header::read(&buf[..HEADER_SIZE - 1]).err(),
+            Some(CodecError::Truncated)
+        );
     }
 }
\ No newline at end of file
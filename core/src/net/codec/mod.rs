@@ -1,10 +1,20 @@
-mod encdec;
 mod error;
 mod eternalreckoningcodec;
+mod fragment;
+#[doc(hidden)]
+pub mod fuzz;
 mod header;
 mod opcode;
+mod sequence;
 
 pub use self::{
     error::CodecError,
     eternalreckoningcodec::EternalReckoningCodec,
+    fragment::MAX_DATAGRAM_SIZE,
+    opcode::{
+        OpcodeType,
+        DISCONNECT_MESSAGE_OP,
+        FRAGMENT_OP,
+    },
+    sequence::SequenceNumber,
 };
\ No newline at end of file
@@ -0,0 +1,163 @@
+use std::collections::VecDeque;
+
+pub type SequenceNumber = u16;
+pub type AckBits = u32;
+
+const ACK_WINDOW: u32 = 32;
+const MAX_IN_FLIGHT: usize = 1024;
+const MAX_REPORTED: usize = 1024;
+
+/// Compares sequence numbers, accounting for wrap-around
+pub fn sequence_greater_than(lhs: SequenceNumber, rhs: SequenceNumber) -> bool {
+    const HALF: SequenceNumber = SequenceNumber::MAX / 2 + 1;
+
+    ((lhs > rhs) && (lhs - rhs <= HALF)) ||
+        ((lhs < rhs) && (rhs - lhs > HALF))
+}
+
+/**
+ * Tracks the sequence numbers of a single connection.
+ *
+ * Every outgoing packet is stamped with the next local sequence number, the
+ * most recent remote sequence number and a bitfield of the remote packets
+ * received before it: bit 0 stands for `ack` itself, bit n for `ack - n`.
+ */
+pub struct SequenceState {
+    local: SequenceNumber,
+    last_sent: Option<SequenceNumber>,
+    remote: Option<SequenceNumber>,
+    received: AckBits,
+    in_flight: VecDeque<SequenceNumber>,
+    acknowledged: VecDeque<SequenceNumber>,
+    lost: VecDeque<SequenceNumber>,
+}
+
+impl SequenceState {
+    pub fn new() -> SequenceState {
+        SequenceState {
+            local: 0,
+            last_sent: None,
+            remote: None,
+            received: 0,
+            in_flight: VecDeque::new(),
+            acknowledged: VecDeque::new(),
+            lost: VecDeque::new(),
+        }
+    }
+
+    /// Returns (sequence, ack, ack bits) for the next outgoing packet
+    pub fn next_outgoing(&mut self) -> (SequenceNumber, SequenceNumber, AckBits) {
+        let sequence = self.local;
+        self.local = self.local.wrapping_add(1);
+        self.last_sent = Some(sequence);
+
+        if self.in_flight.len() >= MAX_IN_FLIGHT {
+            if let Some(expired) = self.in_flight.pop_front() {
+                Self::report(&mut self.lost, expired);
+            }
+        }
+        self.in_flight.push_back(sequence);
+
+        (sequence, self.remote.unwrap_or(0), self.received)
+    }
+
+    pub fn last_sent(&self) -> Option<SequenceNumber> {
+        self.last_sent
+    }
+
+    /**
+     * Records an incoming packet header. Returns false if the packet is a
+     * duplicate or too old to tell, in which case it should be dropped.
+     */
+    pub fn receive(
+        &mut self,
+        sequence: SequenceNumber,
+        ack: SequenceNumber,
+        ack_bits: AckBits,
+    ) -> bool
+    {
+        let remote = match self.remote {
+            Some(remote) => remote,
+            None => {
+                self.remote = Some(sequence);
+                self.received = 1;
+                self.process_acks(ack, ack_bits);
+                return true;
+            },
+        };
+
+        if sequence_greater_than(sequence, remote) {
+            let shift = sequence.wrapping_sub(remote) as u32;
+            self.received = if shift >= ACK_WINDOW {
+                0
+            } else {
+                self.received << shift
+            };
+            self.received |= 1;
+            self.remote = Some(sequence);
+        } else {
+            let offset = remote.wrapping_sub(sequence) as u32;
+            if offset >= ACK_WINDOW {
+                return false;
+            }
+
+            let bit = 1 << offset;
+            if self.received & bit != 0 {
+                return false;
+            }
+            self.received |= bit;
+        }
+
+        self.process_acks(ack, ack_bits);
+
+        true
+    }
+
+    pub fn take_acknowledged(&mut self) -> Vec<SequenceNumber> {
+        self.acknowledged.drain(..).collect()
+    }
+
+    pub fn take_lost(&mut self) -> Vec<SequenceNumber> {
+        self.lost.drain(..).collect()
+    }
+
+    fn process_acks(&mut self, ack: SequenceNumber, ack_bits: AckBits) {
+        if ack_bits == 0 {
+            // peer has not received anything from us yet
+            return;
+        }
+
+        let mut pending = VecDeque::with_capacity(self.in_flight.len());
+
+        while let Some(sequence) = self.in_flight.pop_front() {
+            if sequence_greater_than(sequence, ack) {
+                pending.push_back(sequence);
+                continue;
+            }
+
+            let offset = ack.wrapping_sub(sequence) as u32;
+            if offset >= ACK_WINDOW {
+                Self::report(&mut self.lost, sequence);
+            } else if ack_bits & (1 << offset) != 0 {
+                Self::report(&mut self.acknowledged, sequence);
+            } else {
+                pending.push_back(sequence);
+            }
+        }
+
+        self.in_flight = pending;
+    }
+
+    fn report(queue: &mut VecDeque<SequenceNumber>, sequence: SequenceNumber) {
+        if queue.len() >= MAX_REPORTED {
+            queue.pop_front();
+        }
+        queue.push_back(sequence);
+    }
+}
+
+impl Default for SequenceState {
+    fn default() -> SequenceState {
+        SequenceState::new()
+    }
+}
@@ -1,4 +1,15 @@
+#[macro_use]
+pub mod schema;
+pub mod capture;
+pub mod clock;
 pub mod codec;
 pub mod operation;
+pub mod quantization;
+pub mod snapshot;
+pub mod stats;
+pub mod transport;
 
-pub use codec::EternalReckoningCodec;
\ No newline at end of file
+pub use codec::EternalReckoningCodec;
+
+/// Bumped whenever the wire format changes incompatibly
+pub const PROTOCOL_VERSION: u16 = 7;
\ No newline at end of file
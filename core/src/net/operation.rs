@@ -1,74 +1,381 @@
 use std::fmt::{
-    Debug,
     Display,
     Formatter,
 };
 
+use bytes::BytesMut;
 use uuid::Uuid;
 
-#[derive(Clone)]
-pub enum Operation {
-    ClSync(ClSync),
-    SvSync(SvSync),
-    ClConnectMessage(ClConnectMessage),
-    SvConnectResponse(SvConnectResponse),
-    SvUpdateWorld(SvUpdateWorld),
-    ClMoveSetPosition(ClMoveSetPosition),
-    DisconnectMessage,
+use super::clock::Timestamp;
+use super::codec::CodecError;
+use super::quantization::{
+    PositionEncoding,
+    MAX_POSITION_PRECISION,
+};
+use super::schema::{
+    Reader,
+    Wire,
+};
+#[cfg(test)]
+use super::schema::Sample;
+use super::snapshot::SnapshotId;
+use crate::simulation::PhysicsConfig;
+
+operations! {
+    #[opcode(0x00, "(client) sync")]
+    pub struct ClSync {
+        pub client_time: Timestamp,
+    }
+
+    #[opcode(0x01, "(server) sync")]
+    /// Answer to a ClSync, echoing its client time
+    pub struct SvSync {
+        pub client_time: Timestamp,
+        pub server_time: Timestamp,
+        pub tick: u64,
+    }
+
+    #[opcode(0x02, "(client) connect message")]
+    pub struct ClConnectMessage {
+        pub protocol_version: u16,
+        pub client_build: String,
+        pub player_name: String,
+    }
+
+    #[opcode(0x03, "(server) connect response")]
+    pub struct SvConnectResponse {
+        pub uuid: Uuid,
+        /// origin of quantized positions
+        pub zone_origin: nalgebra::Point3<f64>,
+        /// settings client prediction must share with the server
+        pub params: SimulationParams,
+    }
+
+    #[opcode(0x04, "(server) connection rejected")]
+    pub struct SvConnectRejected {
+        pub reason: RejectReason,
+        pub message: String,
+    }
+
+    #[opcode(0x10, "(server) world update")]
+    /// World state encoded as a delta against `baseline`, or in full if it is None
+    pub struct SvUpdateWorld {
+        pub snapshot: SnapshotId,
+        pub baseline: Option<SnapshotId>,
+        /// tick of the recipient's latest input the snapshot reflects
+        pub input_tick: Option<u64>,
+        pub updates: Vec<EntityUpdate>,
+        pub removed: Vec<Uuid>,
+    }
+
+    #[opcode(0x11, "(client) snapshot acknowledgement")]
+    pub struct ClSnapshotAck {
+        pub snapshot: SnapshotId,
+    }
+
+    #[opcode(0x12, "(server) spawn entity")]
+    /// Introduces an entity; world updates only refer to spawned entities
+    pub struct SvSpawnEntity {
+        pub uuid: Uuid,
+        pub archetype: Archetype,
+        pub model: String,
+        pub texture: String,
+        pub components: Vec<EntityComponent>,
+    }
+
+    #[opcode(0x13, "(server) despawn entity")]
+    pub struct SvDespawnEntity {
+        pub uuid: Uuid,
+    }
+
+    #[opcode(0x20, "(client) input")]
+    /// Player input sampled on a client tick; the server moves the player
+    pub struct ClInput {
+        pub tick: u64,
+        /// `MoveKeys` bits
+        pub keys: u8,
+        pub jump: bool,
+        /// camera yaw, quantized over the full circle
+        pub yaw: u16,
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RejectReason {
+    /// unrecognized reason code, possibly from a newer server
+    Other,
+    VersionMismatch,
+    ServerFull,
+    Banned,
+    InvalidName,
+}
+
+impl RejectReason {
+    pub fn from_code(code: u8) -> RejectReason {
+        match code {
+            0x01 => RejectReason::VersionMismatch,
+            0x02 => RejectReason::ServerFull,
+            0x03 => RejectReason::Banned,
+            0x04 => RejectReason::InvalidName,
+            _ => RejectReason::Other,
+        }
+    }
+
+    pub fn code(self) -> u8 {
+        match self {
+            RejectReason::Other => 0x00,
+            RejectReason::VersionMismatch => 0x01,
+            RejectReason::ServerFull => 0x02,
+            RejectReason::Banned => 0x03,
+            RejectReason::InvalidName => 0x04,
+        }
+    }
 }
 
-impl Display for Operation {
+impl Display for RejectReason {
     fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
         write!(f, "{}", match self {
-            Operation::ClSync(_) => "(client) sync",
-            Operation::SvSync(_) => "(server) sync",
-            Operation::ClConnectMessage(_) => "(client) connect message",
-            Operation::SvConnectResponse(_) => "(server) connect response",
-            Operation::SvUpdateWorld(_) => "(server) world update",
-            Operation::ClMoveSetPosition(_) => "(client) player movement",
-            Operation::DisconnectMessage => "disconnected",
+            RejectReason::Other => "rejected",
+            RejectReason::VersionMismatch => "protocol version mismatch",
+            RejectReason::ServerFull => "server full",
+            RejectReason::Banned => "banned",
+            RejectReason::InvalidName => "invalid player name",
         })
     }
 }
 
-impl Debug for Operation {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), std::fmt::Error> {
-        Display::fmt(&self, f)
+impl Wire for RejectReason {
+    fn encode(&self, buf: &mut BytesMut) {
+        self.code().encode(buf);
+    }
+
+    fn decode(data: &mut Reader) -> Result<RejectReason, CodecError> {
+        Ok(RejectReason::from_code(u8::decode(data)?))
     }
 }
 
-#[derive(Clone)]
-pub struct ClSync;
+#[cfg(test)]
+impl Sample for RejectReason {
+    fn sample() -> RejectReason {
+        RejectReason::ServerFull
+    }
+}
 
-#[derive(Clone)]
-pub struct SvSync;
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Archetype {
+    /// unrecognized archetype code, possibly from a newer server
+    Other,
+    Player,
+    Prop,
+}
 
-#[derive(Clone)]
-pub struct ClConnectMessage;
+impl Archetype {
+    pub fn from_code(code: u8) -> Archetype {
+        match code {
+            0x01 => Archetype::Player,
+            0x02 => Archetype::Prop,
+            _ => Archetype::Other,
+        }
+    }
 
-#[derive(Clone)]
-pub struct SvConnectResponse {
-    pub uuid: Uuid,
+    pub fn code(self) -> u8 {
+        match self {
+            Archetype::Other => 0x00,
+            Archetype::Player => 0x01,
+            Archetype::Prop => 0x02,
+        }
+    }
 }
 
-#[derive(Clone)]
-pub struct ClMoveSetPosition {
-    pub pos: nalgebra::Point3<f64>,
+impl Wire for Archetype {
+    fn encode(&self, buf: &mut BytesMut) {
+        self.code().encode(buf);
+    }
+
+    fn decode(data: &mut Reader) -> Result<Archetype, CodecError> {
+        Ok(Archetype::from_code(u8::decode(data)?))
+    }
 }
 
-#[derive(Clone)]
-pub struct SvUpdateWorld {
-    pub updates: Vec<EntityUpdate>,
+#[cfg(test)]
+impl Sample for Archetype {
+    fn sample() -> Archetype {
+        Archetype::Player
+    }
 }
 
-#[derive(Clone)]
-pub struct EntityUpdate {
-    pub uuid: Uuid,
-    pub data: Vec<EntityComponent>,
+wire_struct! {
+    pub struct EntityUpdate {
+        pub uuid: Uuid,
+        pub data: Vec<EntityComponent>,
+    }
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq)]
 pub enum EntityComponent {
     Health(u64),
     Position(nalgebra::Point3<f64>),
+    QuantizedPosition(QuantizedPosition),
+    Orientation(Orientation),
+}
+
+type ComponentCodeType = u8;
+
+const HEALTH_COMP: ComponentCodeType = 0x01;
+// 3 x f64
+const POSITION_COMP: ComponentCodeType = 0x02;
+// u8 precision, 3 x i16 fixed-point offset from the zone origin
+const POSITION_FIXED16_COMP: ComponentCodeType = 0x03;
+// u8 precision, 3 x i32 fixed-point offset from the zone origin
+const POSITION_FIXED32_COMP: ComponentCodeType = 0x04;
+// u16 yaw, i16 pitch
+const ORIENTATION_COMP: ComponentCodeType = 0x05;
+
+/// Component code followed by the component data
+impl Wire for EntityComponent {
+    fn encode(&self, buf: &mut BytesMut) {
+        match self {
+            EntityComponent::Health(health) => {
+                HEALTH_COMP.encode(buf);
+                health.encode(buf);
+            },
+            EntityComponent::Position(position) => {
+                POSITION_COMP.encode(buf);
+                position.encode(buf);
+            },
+            EntityComponent::QuantizedPosition(position) => {
+                match position.encoding {
+                    PositionEncoding::Fixed16 => {
+                        POSITION_FIXED16_COMP.encode(buf);
+                        position.precision.encode(buf);
+                        (position.offset.x as i16).encode(buf);
+                        (position.offset.y as i16).encode(buf);
+                        (position.offset.z as i16).encode(buf);
+                    },
+                    _ => {
+                        POSITION_FIXED32_COMP.encode(buf);
+                        position.precision.encode(buf);
+                        position.offset.x.encode(buf);
+                        position.offset.y.encode(buf);
+                        position.offset.z.encode(buf);
+                    },
+                }
+            },
+            EntityComponent::Orientation(orientation) => {
+                ORIENTATION_COMP.encode(buf);
+                orientation.encode(buf);
+            },
+        }
+    }
+
+    fn decode(data: &mut Reader) -> Result<EntityComponent, CodecError> {
+        match ComponentCodeType::decode(data)? {
+            HEALTH_COMP => Ok(EntityComponent::Health(u64::decode(data)?)),
+            POSITION_COMP => Ok(EntityComponent::Position(
+                nalgebra::Point3::<f64>::decode(data)?
+            )),
+            POSITION_FIXED16_COMP => {
+                let precision = decode_precision(data)?;
+                Ok(EntityComponent::QuantizedPosition(QuantizedPosition {
+                    encoding: PositionEncoding::Fixed16,
+                    precision,
+                    offset: nalgebra::Vector3::new(
+                        i16::decode(data)? as i32,
+                        i16::decode(data)? as i32,
+                        i16::decode(data)? as i32,
+                    ),
+                }))
+            },
+            POSITION_FIXED32_COMP => {
+                let precision = decode_precision(data)?;
+                Ok(EntityComponent::QuantizedPosition(QuantizedPosition {
+                    encoding: PositionEncoding::Fixed32,
+                    precision,
+                    offset: nalgebra::Vector3::new(
+                        i32::decode(data)?,
+                        i32::decode(data)?,
+                        i32::decode(data)?,
+                    ),
+                }))
+            },
+            ORIENTATION_COMP => Ok(EntityComponent::Orientation(
+                Orientation::decode(data)?
+            )),
+            code => Err(CodecError::UnknownComponent(code)),
+        }
+    }
+}
+
+fn decode_precision(data: &mut Reader) -> Result<u8, CodecError> {
+    let precision = u8::decode(data)?;
+    if precision > MAX_POSITION_PRECISION {
+        return Err(CodecError::InvalidPrecision(precision));
+    }
+    Ok(precision)
+}
+
+#[cfg(test)]
+impl Sample for EntityComponent {
+    fn sample() -> EntityComponent {
+        EntityComponent::QuantizedPosition(QuantizedPosition {
+            encoding: PositionEncoding::Fixed16,
+            precision: 6,
+            offset: nalgebra::Vector3::new(-64, 0, 512),
+        })
+    }
+}
+
+/// Fixed-point offset from the zone origin, with `precision` fractional bits
+#[derive(Clone, PartialEq)]
+pub struct QuantizedPosition {
+    pub encoding: PositionEncoding,
+    pub precision: u8,
+    pub offset: nalgebra::Vector3<i32>,
+}
+
+wire_struct! {
+    /// Yaw over the full circle and pitch over [-pi/2, pi/2], in 16 bits each
+    #[derive(PartialEq)]
+    pub struct Orientation {
+        pub yaw: u16,
+        pub pitch: i16,
+    }
+}
+
+wire_struct! {
+    /// Movement and physics settings of the server, per second
+    #[derive(Debug, PartialEq)]
+    pub struct SimulationParams {
+        pub movement_speed: f64,
+        pub jump_force: f64,
+        pub physics: PhysicsConfig,
+    }
+}
+
+/// Every setting as an f64, in declaration order
+impl Wire for PhysicsConfig {
+    fn encode(&self, buf: &mut BytesMut) {
+        self.gravity.encode(buf);
+        self.min_collision_depth.encode(buf);
+        self.max_ground_slope.encode(buf);
+        self.horisontal_drag.encode(buf);
+        self.vertical_drag.encode(buf);
+    }
+
+    fn decode(data: &mut Reader) -> Result<PhysicsConfig, CodecError> {
+        Ok(PhysicsConfig {
+            gravity: f64::decode(data)?,
+            min_collision_depth: f64::decode(data)?,
+            max_ground_slope: f64::decode(data)?,
+            horisontal_drag: f64::decode(data)?,
+            vertical_drag: f64::decode(data)?,
+        })
+    }
+}
+
+#[cfg(test)]
+impl Sample for PhysicsConfig {
+    fn sample() -> PhysicsConfig {
+        PhysicsConfig::default()
+    }
 }
\ No newline at end of file
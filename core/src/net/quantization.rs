@@ -0,0 +1,218 @@
+use std::f64::consts::PI;
+
+use serde::{Serialize, Deserialize};
+
+use crate::util::config::{
+    Validate,
+    Validator,
+};
+use super::operation::{
+    EntityComponent,
+    Orientation,
+    QuantizedPosition,
+};
+
+/// Upper bound for fractional bits in fixed-point positions
+pub const MAX_POSITION_PRECISION: u8 = 24;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PositionEncoding {
+    /// three raw f64s
+    Float,
+    /// three i16s, fixed-point relative to the zone origin, clamping
+    /// positions beyond 2^(15 - precision) units of it
+    Fixed16,
+    /// three i32s, fixed-point relative to the zone origin
+    Fixed32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct QuantizationConfig {
+    pub position_encoding: PositionEncoding,
+    /// number of fractional bits in fixed-point positions
+    pub position_precision: u8,
+    pub zone_origin: [f64; 3],
+}
+
+impl Default for QuantizationConfig {
+    fn default() -> QuantizationConfig {
+        QuantizationConfig {
+            // 1/256 unit steps, +-8 million units around the origin
+            position_encoding: PositionEncoding::Fixed32,
+            position_precision: 8,
+            zone_origin: [0.0, 0.0, 0.0],
+        }
+    }
+}
+
+impl Validate for QuantizationConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check(
+            "position-precision",
+            self.position_precision <= MAX_POSITION_PRECISION,
+            &format!("must be at most {}", MAX_POSITION_PRECISION)
+        );
+        validator.check(
+            "zone-origin",
+            self.zone_origin.iter().all(|value| value.is_finite()),
+            "must be three numbers"
+        );
+    }
+}
+
+impl QuantizationConfig {
+    pub fn origin(&self) -> nalgebra::Point3<f64> {
+        nalgebra::Point3::new(
+            self.zone_origin[0],
+            self.zone_origin[1],
+            self.zone_origin[2],
+        )
+    }
+
+    /// Encodes a position with the configured encoding
+    pub fn position(&self, position: &nalgebra::Point3<f64>) -> EntityComponent {
+        match self.position_encoding {
+            PositionEncoding::Float => EntityComponent::Position(*position),
+            encoding => EntityComponent::QuantizedPosition(
+                QuantizedPosition::quantize(
+                    position,
+                    &self.origin(),
+                    encoding,
+                    self.position_precision,
+                )
+            ),
+        }
+    }
+}
+
+impl QuantizedPosition {
+    /**
+     * Converts a position to fixed-point coordinates relative to `origin`,
+     * clamping to the range of the encoding.
+     */
+    pub fn quantize(
+        position: &nalgebra::Point3<f64>,
+        origin: &nalgebra::Point3<f64>,
+        encoding: PositionEncoding,
+        precision: u8,
+    ) -> QuantizedPosition
+    {
+        let (min, max) = match encoding {
+            PositionEncoding::Fixed16 => (i16::MIN as f64, i16::MAX as f64),
+            _ => (i32::MIN as f64, i32::MAX as f64),
+        };
+
+        let precision = precision.min(MAX_POSITION_PRECISION);
+        let scale = (1u64 << precision) as f64;
+        let offset = (position - origin) * scale;
+        let quantize = |value: f64| value.round().clamp(min, max) as i32;
+
+        QuantizedPosition {
+            encoding,
+            precision,
+            offset: nalgebra::Vector3::new(
+                quantize(offset.x),
+                quantize(offset.y),
+                quantize(offset.z),
+            ),
+        }
+    }
+
+    pub fn dequantize(&self, origin: &nalgebra::Point3<f64>)
+        -> nalgebra::Point3<f64>
+    {
+        let scale = (1u64 << self.precision.min(MAX_POSITION_PRECISION)) as f64;
+        origin + nalgebra::Vector3::new(
+            self.offset.x as f64 / scale,
+            self.offset.y as f64 / scale,
+            self.offset.z as f64 / scale,
+        )
+    }
+}
+
+/// Packs a yaw in [-pi, pi] into 16 bits over the full circle
+pub fn quantize_yaw(yaw: f64) -> u16 {
+    let yaw = (yaw + PI).rem_euclid(2.0 * PI) / (2.0 * PI);
+    (yaw * 65536.0).round() as u32 as u16
+}
+
+pub fn dequantize_yaw(yaw: u16) -> f64 {
+    yaw as f64 / 65536.0 * 2.0 * PI - PI
+}
+
+impl Orientation {
+    /// Packs a yaw in [-pi, pi] and a pitch in [-pi/2, pi/2]
+    pub fn from_euler(yaw: f64, pitch: f64) -> Orientation {
+        let pitch = pitch.clamp(-PI / 2.0, PI / 2.0) / (PI / 2.0);
+
+        Orientation {
+            yaw: quantize_yaw(yaw),
+            pitch: (pitch * i16::MAX as f64).round() as i16,
+        }
+    }
+
+    pub fn yaw(&self) -> f64 {
+        dequantize_yaw(self.yaw)
+    }
+
+    pub fn pitch(&self) -> f64 {
+        self.pitch as f64 / i16::MAX as f64 * (PI / 2.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quantize_position() {
+        let origin = nalgebra::Point3::new(-64.0, 0.0, -64.0);
+        let position = nalgebra::Point3::new(-10.25, 3.5, 100.125);
+
+        for encoding in &[PositionEncoding::Fixed16, PositionEncoding::Fixed32] {
+            let quantized = QuantizedPosition::quantize(&position, &origin, *encoding, 6);
+            let restored = quantized.dequantize(&origin);
+
+            assert!((restored - position).norm() < 1.0 / 64.0);
+        }
+    }
+
+    #[test]
+    fn test_default_covers_map() {
+        let config = QuantizationConfig::default();
+        let position = nalgebra::Point3::new(100000.0, -2500.5, 40000.25);
+
+        match config.position(&position) {
+            EntityComponent::QuantizedPosition(quantized) => {
+                assert_eq!(quantized.dequantize(&config.origin()), position);
+            },
+            _ => panic!("expected a quantized position"),
+        }
+    }
+
+    #[test]
+    fn test_quantize_position_clamps() {
+        let origin = nalgebra::Point3::new(0.0, 0.0, 0.0);
+        let position = nalgebra::Point3::new(1000.0, -1000.0, 0.0);
+
+        let quantized = QuantizedPosition::quantize(
+            &position,
+            &origin,
+            PositionEncoding::Fixed16,
+            6
+        );
+
+        assert_eq!(quantized.offset.x, i16::MAX as i32);
+        assert_eq!(quantized.offset.y, i16::MIN as i32);
+    }
+
+    #[test]
+    fn test_orientation() {
+        let orientation = Orientation::from_euler(1.0, -0.5);
+
+        assert!((orientation.yaw() - 1.0).abs() < 0.001);
+        assert!((orientation.pitch() + 0.5).abs() < 0.001);
+    }
+}
@@ -0,0 +1,483 @@
+use std::io::Cursor;
+
+use bytes::{Buf, BufMut, BytesMut};
+use uuid::Uuid;
+
+use super::codec::CodecError;
+
+pub type Reader<'a> = Cursor<&'a [u8]>;
+
+/**
+ * A value with a fixed little-endian wire representation. Decoding is
+ * bounds-checked: running out of data is an error, never a panic.
+ */
+pub trait Wire: Sized {
+    fn encode(&self, buf: &mut BytesMut);
+    fn decode(data: &mut Reader) -> Result<Self, CodecError>;
+}
+
+/// Example values used by the generated round-trip tests
+#[cfg(test)]
+pub trait Sample {
+    fn sample() -> Self;
+}
+
+/// Fails unless `len` more bytes are available
+pub fn require(data: &Reader, len: usize) -> Result<(), CodecError> {
+    if data.remaining() < len {
+        return Err(CodecError::Truncated);
+    }
+    Ok(())
+}
+
+macro_rules! wire_primitive {
+    ($ty:ty, $put:ident, $get:ident, $sample:expr) => {
+        impl Wire for $ty {
+            fn encode(&self, buf: &mut BytesMut) {
+                buf.reserve(std::mem::size_of::<$ty>());
+                buf.$put(*self);
+            }
+
+            fn decode(data: &mut Reader) -> Result<$ty, CodecError> {
+                require(data, std::mem::size_of::<$ty>())?;
+                Ok(data.$get())
+            }
+        }
+
+        #[cfg(test)]
+        impl Sample for $ty {
+            fn sample() -> $ty {
+                $sample
+            }
+        }
+    };
+}
+
+wire_primitive!(u8, put_u8, get_u8, 0xA5);
+wire_primitive!(u16, put_u16_le, get_u16_le, 0xBEEF);
+wire_primitive!(u32, put_u32_le, get_u32_le, 0xDEAD_BEEF);
+wire_primitive!(u64, put_u64_le, get_u64_le, 0x0123_4567_89AB_CDEF);
+wire_primitive!(i16, put_i16_le, get_i16_le, -1234);
+wire_primitive!(i32, put_i32_le, get_i32_le, -123_456);
+wire_primitive!(f64, put_f64_le, get_f64_le, -12.625);
+
+/// u8 0 or 1
+impl Wire for bool {
+    fn encode(&self, buf: &mut BytesMut) {
+        (*self as u8).encode(buf);
+    }
+
+    fn decode(data: &mut Reader) -> Result<bool, CodecError> {
+        match u8::decode(data)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            flag => Err(CodecError::InvalidFlag(flag)),
+        }
+    }
+}
+
+#[cfg(test)]
+impl Sample for bool {
+    fn sample() -> bool {
+        true
+    }
+}
+
+/// u16 byte length followed by UTF-8 data, truncated to fit on a char boundary
+impl Wire for String {
+    fn encode(&self, buf: &mut BytesMut) {
+        let mut len = self.len().min(u16::MAX as usize);
+        while !self.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let bytes = &self.as_bytes()[..len];
+        (bytes.len() as u16).encode(buf);
+        buf.reserve(bytes.len());
+        buf.put_slice(bytes);
+    }
+
+    fn decode(data: &mut Reader) -> Result<String, CodecError> {
+        let len = u16::decode(data)? as usize;
+        require(data, len)?;
+
+        let mut bytes = vec![0; len];
+        data.copy_to_slice(&mut bytes);
+
+        String::from_utf8(bytes).map_err(|_| CodecError::InvalidUtf8)
+    }
+}
+
+#[cfg(test)]
+impl Sample for String {
+    fn sample() -> String {
+        "sample \u{263A}".to_string()
+    }
+}
+
+impl Wire for Uuid {
+    fn encode(&self, buf: &mut BytesMut) {
+        buf.reserve(16);
+        buf.put_slice(self.as_bytes());
+    }
+
+    fn decode(data: &mut Reader) -> Result<Uuid, CodecError> {
+        require(data, 16)?;
+
+        let mut bytes = [0; 16];
+        data.copy_to_slice(&mut bytes);
+
+        Ok(Uuid::from_bytes(bytes))
+    }
+}
+
+#[cfg(test)]
+impl Sample for Uuid {
+    fn sample() -> Uuid {
+        Uuid::from_u128(0xd171_4871_dbbd_4e65_a966_c6e5_7c49_ba47)
+    }
+}
+
+impl Wire for nalgebra::Point3<f64> {
+    fn encode(&self, buf: &mut BytesMut) {
+        self.x.encode(buf);
+        self.y.encode(buf);
+        self.z.encode(buf);
+    }
+
+    fn decode(data: &mut Reader) -> Result<nalgebra::Point3<f64>, CodecError> {
+        Ok(nalgebra::Point3::new(
+            f64::decode(data)?,
+            f64::decode(data)?,
+            f64::decode(data)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+impl Sample for nalgebra::Point3<f64> {
+    fn sample() -> nalgebra::Point3<f64> {
+        nalgebra::Point3::new(1.5, -2.25, 1024.0)
+    }
+}
+
+/// u8 presence flag, followed by the value if present
+impl<T: Wire> Wire for Option<T> {
+    fn encode(&self, buf: &mut BytesMut) {
+        match self {
+            Some(value) => {
+                1u8.encode(buf);
+                value.encode(buf);
+            },
+            None => 0u8.encode(buf),
+        }
+    }
+
+    fn decode(data: &mut Reader) -> Result<Option<T>, CodecError> {
+        match u8::decode(data)? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode(data)?)),
+            flag => Err(CodecError::InvalidFlag(flag)),
+        }
+    }
+}
+
+#[cfg(test)]
+impl<T: Sample> Sample for Option<T> {
+    fn sample() -> Option<T> {
+        Some(T::sample())
+    }
+}
+
+/// u32 element count followed by the elements
+impl<T: Wire> Wire for Vec<T> {
+    fn encode(&self, buf: &mut BytesMut) {
+        (self.len() as u32).encode(buf);
+        for value in self {
+            value.encode(buf);
+        }
+    }
+
+    fn decode(data: &mut Reader) -> Result<Vec<T>, CodecError> {
+        let count = u32::decode(data)? as usize;
+
+        // every element takes at least a byte, so this bounds the allocation
+        require(data, count)?;
+
+        let mut values = Vec::with_capacity(count);
+        for _ in 0..count {
+            values.push(T::decode(data)?);
+        }
+        Ok(values)
+    }
+}
+
+#[cfg(test)]
+impl<T: Sample> Sample for Vec<T> {
+    fn sample() -> Vec<T> {
+        vec![T::sample(), T::sample()]
+    }
+}
+
+/**
+ * Declares a struct whose wire representation is its fields in order.
+ */
+macro_rules! wire_struct {
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident;
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        pub struct $name;
+
+        impl $crate::net::schema::Wire for $name {
+            fn encode(&self, _buf: &mut bytes::BytesMut) {}
+
+            fn decode(_data: &mut $crate::net::schema::Reader)
+                -> Result<$name, $crate::net::codec::CodecError>
+            {
+                Ok($name)
+            }
+        }
+
+        #[cfg(test)]
+        impl $crate::net::schema::Sample for $name {
+            fn sample() -> $name {
+                $name
+            }
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        pub struct $name:ident {
+            $(
+                $(#[$field_meta:meta])*
+                pub $field:ident: $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Clone)]
+        pub struct $name {
+            $(
+                $(#[$field_meta])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl $crate::net::schema::Wire for $name {
+            fn encode(&self, buf: &mut bytes::BytesMut) {
+                $(
+                    $crate::net::schema::Wire::encode(&self.$field, buf);
+                )*
+            }
+
+            fn decode(data: &mut $crate::net::schema::Reader)
+                -> Result<$name, $crate::net::codec::CodecError>
+            {
+                Ok($name {
+                    $(
+                        $field: <$ty as $crate::net::schema::Wire>::decode(data)?,
+                    )*
+                })
+            }
+        }
+
+        #[cfg(test)]
+        impl $crate::net::schema::Sample for $name {
+            fn sample() -> $name {
+                $name {
+                    $(
+                        $field: <$ty as $crate::net::schema::Sample>::sample(),
+                    )*
+                }
+            }
+        }
+    };
+}
+
+/**
+ * Declares every operation of the protocol. Each `#[opcode(code, name)]`
+ * struct becomes a wire struct and a variant of `Operation` carrying it;
+ * the opcode dispatch, display names and a round-trip test per operation
+ * are generated from the list. `DisconnectMessage` has no body and is
+ * always present.
+ */
+macro_rules! operations {
+    (
+        $(
+            #[opcode($opcode:expr, $display:expr)]
+            $(#[$meta:meta])*
+            pub struct $name:ident $body:tt
+        )*
+    ) => {
+        $(
+            wire_struct! {
+                $(#[$meta])*
+                pub struct $name $body
+            }
+
+            impl $name {
+                pub const OPCODE: $crate::net::codec::OpcodeType = $opcode;
+            }
+        )*
+
+        #[derive(Clone)]
+        pub enum Operation {
+            $(
+                $name($name),
+            )*
+            DisconnectMessage,
+        }
+
+        impl Operation {
+            pub fn opcode(&self) -> $crate::net::codec::OpcodeType {
+                match self {
+                    $(
+                        Operation::$name(_) => $name::OPCODE,
+                    )*
+                    Operation::DisconnectMessage => {
+                        $crate::net::codec::DISCONNECT_MESSAGE_OP
+                    },
+                }
+            }
+
+            pub fn encode_payload(&self, buf: &mut bytes::BytesMut) {
+                match self {
+                    $(
+                        Operation::$name(data) => {
+                            $crate::net::schema::Wire::encode(data, buf)
+                        },
+                    )*
+                    Operation::DisconnectMessage => (),
+                }
+            }
+
+            /**
+             * Decodes an operation from its complete payload. Trailing
+             * bytes are an error.
+             */
+            pub fn decode_payload(
+                opcode: $crate::net::codec::OpcodeType,
+                payload: &[u8],
+            ) -> Result<Operation, $crate::net::codec::CodecError>
+            {
+                let mut data = std::io::Cursor::new(payload);
+
+                let op = match opcode {
+                    $(
+                        opcode if opcode == $name::OPCODE => Operation::$name(
+                            <$name as $crate::net::schema::Wire>::decode(&mut data)?
+                        ),
+                    )*
+                    $crate::net::codec::DISCONNECT_MESSAGE_OP => {
+                        Operation::DisconnectMessage
+                    },
+                    _ => {
+                        return Err($crate::net::codec::CodecError::InvalidOpcode(opcode));
+                    },
+                };
+
+                let decoded = data.position() as usize;
+                if decoded != payload.len() {
+                    return Err($crate::net::codec::CodecError::SizeMismatch {
+                        declared: payload.len(),
+                        decoded,
+                    });
+                }
+
+                Ok(op)
+            }
+        }
+
+        impl std::fmt::Display for Operation {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+                write!(f, "{}", match self {
+                    $(
+                        Operation::$name(_) => $display,
+                    )*
+                    Operation::DisconnectMessage => "disconnected",
+                })
+            }
+        }
+
+        impl std::fmt::Debug for Operation {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
+                std::fmt::Display::fmt(&self, f)
+            }
+        }
+
+        #[cfg(test)]
+        mod round_trip {
+            use super::*;
+
+            #[allow(dead_code)]
+            fn round_trip(op: Operation) {
+                let mut encoded = bytes::BytesMut::new();
+                op.encode_payload(&mut encoded);
+
+                let decoded = Operation::decode_payload(op.opcode(), &encoded[..])
+                    .unwrap();
+                assert_eq!(decoded.opcode(), op.opcode());
+
+                let mut reencoded = bytes::BytesMut::new();
+                decoded.encode_payload(&mut reencoded);
+                assert_eq!(&encoded[..], &reencoded[..]);
+
+                if !encoded.is_empty() {
+                    let truncated = &encoded[..encoded.len() - 1];
+                    assert!(Operation::decode_payload(op.opcode(), truncated).is_err());
+                }
+
+                encoded.extend_from_slice(&[0]);
+                assert!(Operation::decode_payload(op.opcode(), &encoded[..]).is_err());
+            }
+
+            $(
+                #[allow(non_snake_case)]
+                mod $name {
+                    #[test]
+                    fn test_round_trip() {
+                        super::round_trip(super::super::Operation::$name(
+                            <super::super::$name as $crate::net::schema::Sample>::sample()
+                        ));
+                    }
+                }
+            )*
+
+            #[test]
+            fn test_unique_opcodes() {
+                let mut opcodes = vec![
+                    $(
+                        super::$name::OPCODE,
+                    )*
+                    $crate::net::codec::DISCONNECT_MESSAGE_OP,
+                    $crate::net::codec::FRAGMENT_OP,
+                ];
+                let count = opcodes.len();
+                opcodes.sort();
+                opcodes.dedup();
+                assert_eq!(opcodes.len(), count);
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_string_truncated_on_char_boundary() {
+        // 4-byte chars, so the u16 limit falls inside one
+        let long = "\u{1F600}".repeat(u16::MAX as usize / 4 + 1);
+
+        let mut buf = BytesMut::new();
+        long.encode(&mut buf);
+        let decoded = String::decode(&mut Cursor::new(&buf[..])).unwrap();
+
+        assert_eq!(decoded.len(), u16::MAX as usize - 3);
+        assert!(long.starts_with(&decoded));
+    }
+}
\ No newline at end of file
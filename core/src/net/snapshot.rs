@@ -0,0 +1,209 @@
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+use std::mem::discriminant;
+
+use uuid::Uuid;
+
+use super::operation::{
+    EntityComponent,
+    EntityUpdate,
+};
+
+pub type SnapshotId = u32;
+
+/// Number of snapshots kept for use as delta baselines
+pub const SNAPSHOT_HISTORY: usize = 32;
+
+/// Full replicated state of the world, as seen by a single client
+#[derive(Clone, Default)]
+pub struct Snapshot {
+    entities: HashMap<Uuid, Vec<EntityComponent>>,
+}
+
+impl Snapshot {
+    pub fn new() -> Snapshot {
+        Snapshot { entities: HashMap::new() }
+    }
+
+    pub fn insert(&mut self, uuid: Uuid, components: Vec<EntityComponent>) {
+        self.entities.insert(uuid, components);
+    }
+
+    pub fn get(&self, uuid: &Uuid) -> Option<&Vec<EntityComponent>> {
+        self.entities.get(uuid)
+    }
+
+    pub fn entities(&self) -> impl Iterator<Item = (&Uuid, &Vec<EntityComponent>)> {
+        self.entities.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+
+    /**
+     * Returns the entity updates and removals needed to turn `baseline` into
+     * this snapshot. Unchanged entities and components are omitted; without a
+     * baseline, every entity is sent in full.
+     */
+    pub fn delta_from(&self, baseline: Option<&Snapshot>)
+        -> (Vec<EntityUpdate>, Vec<Uuid>)
+    {
+        let mut updates = Vec::new();
+
+        for (uuid, components) in &self.entities {
+            let previous = baseline.and_then(|baseline| baseline.get(uuid));
+
+            let data: Vec<EntityComponent> = match previous {
+                Some(previous) => {
+                    components.iter()
+                        .filter(|component| !previous.contains(component))
+                        .cloned()
+                        .collect()
+                },
+                None => components.clone(),
+            };
+
+            if previous.is_some() && data.is_empty() {
+                continue;
+            }
+
+            updates.push(EntityUpdate { uuid: *uuid, data });
+        }
+
+        let removed = match baseline {
+            Some(baseline) => {
+                baseline.entities.keys()
+                    .filter(|uuid| !self.entities.contains_key(uuid))
+                    .cloned()
+                    .collect()
+            },
+            None => Vec::new(),
+        };
+
+        (updates, removed)
+    }
+
+    /// Rebuilds a full snapshot from a baseline and a delta against it
+    pub fn from_delta(
+        baseline: Option<&Snapshot>,
+        updates: &[EntityUpdate],
+        removed: &[Uuid],
+    ) -> Snapshot
+    {
+        let mut snapshot = match baseline {
+            Some(baseline) => baseline.clone(),
+            None => Snapshot::new(),
+        };
+
+        for uuid in removed {
+            snapshot.entities.remove(uuid);
+        }
+
+        for update in updates {
+            let components = snapshot.entities.entry(update.uuid)
+                .or_insert_with(Vec::new);
+
+            for component in &update.data {
+                let kind = discriminant(component);
+                match components.iter_mut().find(|c| discriminant(*c) == kind) {
+                    Some(existing) => *existing = component.clone(),
+                    None => components.push(component.clone()),
+                }
+            }
+        }
+
+        snapshot
+    }
+}
+
+/// Fixed-size history of recent snapshots, oldest first
+pub struct SnapshotRing {
+    capacity: usize,
+    snapshots: VecDeque<(SnapshotId, Snapshot)>,
+}
+
+impl SnapshotRing {
+    pub fn new(capacity: usize) -> SnapshotRing {
+        SnapshotRing {
+            capacity,
+            snapshots: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn push(&mut self, id: SnapshotId, snapshot: Snapshot) {
+        if self.snapshots.len() >= self.capacity {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back((id, snapshot));
+    }
+
+    pub fn get(&self, id: SnapshotId) -> Option<&Snapshot> {
+        self.snapshots.iter()
+            .find(|(snapshot_id, _)| *snapshot_id == id)
+            .map(|(_, snapshot)| snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uuid(byte: u8) -> Uuid {
+        Uuid::from_slice(&[byte; 16][..]).unwrap()
+    }
+
+    #[test]
+    fn test_delta_round_trip() {
+        let position = |x| EntityComponent::Position(
+            nalgebra::Point3::<f64>::new(x, 0.0, 0.0)
+        );
+
+        let mut baseline = Snapshot::new();
+        baseline.insert(uuid(1), vec![EntityComponent::Health(100), position(1.0)]);
+        baseline.insert(uuid(2), vec![EntityComponent::Health(100), position(2.0)]);
+        baseline.insert(uuid(3), vec![EntityComponent::Health(100)]);
+
+        let mut current = Snapshot::new();
+        current.insert(uuid(1), vec![EntityComponent::Health(100), position(1.5)]);
+        current.insert(uuid(2), vec![EntityComponent::Health(100), position(2.0)]);
+        current.insert(uuid(4), vec![EntityComponent::Health(50)]);
+
+        let (updates, removed) = current.delta_from(Some(&baseline));
+
+        assert_eq!(removed, vec![uuid(3)]);
+        assert_eq!(updates.len(), 2);
+        for update in &updates {
+            if update.uuid == uuid(1) {
+                assert!(update.data == vec![position(1.5)]);
+            } else {
+                assert_eq!(update.uuid, uuid(4));
+                assert!(update.data == vec![EntityComponent::Health(50)]);
+            }
+        }
+
+        let rebuilt = Snapshot::from_delta(Some(&baseline), &updates, &removed);
+        assert_eq!(rebuilt.len(), current.len());
+        for (uuid, components) in current.entities() {
+            assert!(rebuilt.get(uuid) == Some(components));
+        }
+    }
+
+    #[test]
+    fn test_snapshot_ring_capacity() {
+        let mut ring = SnapshotRing::new(2);
+        ring.push(1, Snapshot::new());
+        ring.push(2, Snapshot::new());
+        ring.push(3, Snapshot::new());
+
+        assert!(ring.get(1).is_none());
+        assert!(ring.get(2).is_some());
+        assert!(ring.get(3).is_some());
+    }
+}
@@ -0,0 +1,90 @@
+use std::collections::BTreeMap;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use super::codec::OpcodeType;
+
+/// Traffic counters for a single connection
+#[derive(Clone, Debug, Default)]
+pub struct ConnectionStats {
+    /// datagrams received
+    pub packets_in: u64,
+    /// datagrams sent
+    pub packets_out: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+    /// malformed packets and fragmented operations that were dropped
+    pub decode_errors: u64,
+    /// operations received, by opcode
+    pub opcodes_in: BTreeMap<OpcodeType, u64>,
+    /// operations sent, by opcode
+    pub opcodes_out: BTreeMap<OpcodeType, u64>,
+    /// when the last datagram arrived
+    pub last_seen: Option<Instant>,
+}
+
+impl ConnectionStats {
+    pub fn new() -> ConnectionStats {
+        ConnectionStats::default()
+    }
+
+    pub fn record_inbound(&mut self, bytes: usize, now: Instant) {
+        self.packets_in += 1;
+        self.bytes_in += bytes as u64;
+        self.last_seen = Some(now);
+    }
+
+    pub fn record_outbound(&mut self, bytes: usize) {
+        self.packets_out += 1;
+        self.bytes_out += bytes as u64;
+    }
+
+    pub fn record_operation_in(&mut self, opcode: OpcodeType) {
+        *self.opcodes_in.entry(opcode).or_insert(0) += 1;
+    }
+
+    pub fn record_operation_out(&mut self, opcode: OpcodeType) {
+        *self.opcodes_out.entry(opcode).or_insert(0) += 1;
+    }
+
+    /// Bytes per second received and sent since an earlier copy of the counters
+    pub fn bandwidth_since(&self, earlier: &ConnectionStats, elapsed: Duration) -> (f64, f64) {
+        let seconds = elapsed.as_secs_f64();
+        if seconds <= 0.0 {
+            return (0.0, 0.0);
+        }
+
+        (
+            self.bytes_in.saturating_sub(earlier.bytes_in) as f64 / seconds,
+            self.bytes_out.saturating_sub(earlier.bytes_out) as f64 / seconds,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bandwidth() {
+        let now = Instant::now();
+        let mut stats = ConnectionStats::new();
+        stats.record_inbound(100, now);
+        stats.record_operation_in(0x02);
+        stats.record_operation_in(0x02);
+        let earlier = stats.clone();
+
+        stats.record_inbound(300, now);
+        stats.record_outbound(1000);
+        stats.record_outbound(1000);
+
+        assert_eq!(stats.packets_in, 2);
+        assert_eq!(stats.opcodes_in.get(&0x02), Some(&2));
+        assert_eq!(
+            stats.bandwidth_since(&earlier, Duration::from_secs(2)),
+            (150.0, 1000.0)
+        );
+    }
+}
\ No newline at end of file
@@ -0,0 +1,79 @@
+use std::str::FromStr;
+
+use failure::{
+    format_err,
+    Error,
+};
+
+/**
+ * Where to reach a server: `udp://host:port` or a bare `host:port` for
+ * UDP, `ws://host[:port][/path]` for WebSocket.
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerAddress {
+    Udp(String),
+    WebSocket {
+        /// `host:port`, also sent as the `Host` header
+        host: String,
+        path: String,
+    },
+}
+
+impl FromStr for ServerAddress {
+    type Err = Error;
+
+    fn from_str(address: &str) -> Result<ServerAddress, Error> {
+        let (scheme, rest) = match address.find("://") {
+            Some(index) => (&address[..index], &address[index + 3..]),
+            None => ("udp", address),
+        };
+
+        match &scheme.to_ascii_lowercase()[..] {
+            "udp" => Ok(ServerAddress::Udp(rest.trim_end_matches('/').to_string())),
+            "ws" => {
+                let (host, path) = match rest.find('/') {
+                    Some(index) => (&rest[..index], &rest[index..]),
+                    None => (rest, "/"),
+                };
+
+                // a colon after any IPv6 brackets is the port
+                let has_port = match (host.rfind(':'), host.rfind(']')) {
+                    (Some(colon), Some(bracket)) => colon > bracket,
+                    (Some(_), None) => true,
+                    (None, _) => false,
+                };
+                let host = if has_port {
+                    host.to_string()
+                } else {
+                    format!("{}:80", host)
+                };
+
+                Ok(ServerAddress::WebSocket { host, path: path.to_string() })
+            },
+            "wss" => Err(format_err!("secure WebSocket connections are not supported")),
+            scheme => Err(format_err!("unknown transport: {}", scheme)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_address() {
+        let parse = |address: &str| address.parse::<ServerAddress>().ok();
+        let websocket = |host: &str, path: &str| Some(ServerAddress::WebSocket {
+            host: host.to_string(),
+            path: path.to_string(),
+        });
+
+        assert_eq!(parse("localhost:6142"), Some(ServerAddress::Udp("localhost:6142".to_string())));
+        assert_eq!(parse("udp://localhost:6142"), Some(ServerAddress::Udp("localhost:6142".to_string())));
+        assert_eq!(parse("ws://localhost:6143"), websocket("localhost:6143", "/"));
+        assert_eq!(parse("WS://example.com/game"), websocket("example.com:80", "/game"));
+        assert_eq!(parse("ws://[::1]/"), websocket("[::1]:80", "/"));
+        assert_eq!(parse("wss://example.com"), None);
+        assert_eq!(parse("tcp://example.com:1"), None);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,392 @@
+use std::collections::VecDeque;
+use std::net::SocketAddr;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use bytes::{
+    Bytes,
+    BytesMut,
+};
+use failure::{
+    format_err,
+    Error,
+};
+use futures::{
+    Async,
+    AsyncSink,
+    Future,
+    Poll,
+    Sink,
+    StartSend,
+    Stream,
+};
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng,
+};
+use serde::{Serialize, Deserialize};
+use tokio::timer::Delay;
+
+use crate::util::config::{
+    Validate,
+    Validator,
+};
+
+/// Impairments applied to datagrams in each direction
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct NetworkConditions {
+    pub enabled: bool,
+    /// delay added to every datagram, in milliseconds
+    pub latency_ms: u64,
+    /// random extra delay of up to this many milliseconds
+    pub jitter_ms: u64,
+    /// probability of dropping a datagram
+    pub loss: f64,
+    /// probability of delivering a datagram twice
+    pub duplication: f64,
+    /// probability of holding a datagram back behind later ones
+    pub reordering: f64,
+    /// extra delay of held back datagrams, in milliseconds
+    pub reordering_delay_ms: u64,
+    /// makes runs reproducible; a random seed is logged if unset
+    pub seed: Option<u64>,
+}
+
+impl Default for NetworkConditions {
+    fn default() -> NetworkConditions {
+        NetworkConditions {
+            enabled: false,
+            latency_ms: 0,
+            jitter_ms: 0,
+            loss: 0.0,
+            duplication: 0.0,
+            reordering: 0.0,
+            reordering_delay_ms: 50,
+            seed: None,
+        }
+    }
+}
+
+impl Validate for NetworkConditions {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check("loss", (0.0..=1.0).contains(&self.loss), "must be between 0 and 1");
+        validator.check(
+            "duplication",
+            (0.0..=1.0).contains(&self.duplication),
+            "must be between 0 and 1"
+        );
+        validator.check(
+            "reordering",
+            (0.0..=1.0).contains(&self.reordering),
+            "must be between 0 and 1"
+        );
+    }
+}
+
+fn probability(value: f64) -> f64 {
+    if value.is_nan() {
+        return 0.0;
+    }
+    value.clamp(0.0, 1.0)
+}
+
+struct Delayed<T> {
+    due: Instant,
+    item: T,
+}
+
+/**
+ * Holds items back according to the network conditions. Jitter alone never
+ * reorders items; only those picked for reordering overtake later ones.
+ */
+pub struct Impairment<T> {
+    conditions: NetworkConditions,
+    queue: VecDeque<Delayed<T>>,
+    last_due: Option<Instant>,
+}
+
+impl<T: Clone> Impairment<T> {
+    pub fn new(conditions: &NetworkConditions) -> Impairment<T> {
+        Impairment {
+            conditions: conditions.clone(),
+            queue: VecDeque::new(),
+            last_due: None,
+        }
+    }
+
+    pub fn push<R: Rng>(&mut self, item: T, now: Instant, rng: &mut R) {
+        if rng.gen_bool(probability(self.conditions.loss)) {
+            return;
+        }
+
+        if rng.gen_bool(probability(self.conditions.duplication)) {
+            self.delay(item.clone(), now, rng);
+        }
+        self.delay(item, now, rng);
+    }
+
+    fn delay<R: Rng>(&mut self, item: T, now: Instant, rng: &mut R) {
+        let delay = self.conditions.latency_ms + rng.gen_range(0, self.conditions.jitter_ms + 1);
+        let mut due = now + Duration::from_millis(delay);
+
+        if rng.gen_bool(probability(self.conditions.reordering)) {
+            due += Duration::from_millis(self.conditions.reordering_delay_ms);
+        } else {
+            if let Some(last_due) = self.last_due {
+                due = due.max(last_due);
+            }
+            self.last_due = Some(due);
+        }
+
+        let index = self.queue.iter()
+            .position(|delayed| delayed.due > due)
+            .unwrap_or(self.queue.len());
+        self.queue.insert(index, Delayed { due, item });
+    }
+
+    /// Next item whose delay has passed
+    pub fn pop(&mut self, now: Instant) -> Option<T> {
+        match self.queue.front() {
+            Some(delayed) if delayed.due <= now => {
+                self.queue.pop_front().map(|delayed| delayed.item)
+            },
+            _ => None,
+        }
+    }
+
+    pub fn next_due(&self) -> Option<Instant> {
+        self.queue.front().map(|delayed| delayed.due)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+/**
+ * Datagram socket wrapper simulating a poor connection, passing datagrams
+ * through untouched unless the conditions are enabled. Delayed datagrams
+ * are released while the stream is polled, in both directions.
+ */
+pub struct ConditionedSocket<S> {
+    socket: S,
+    enabled: bool,
+    rng: StdRng,
+    inbound: Impairment<(BytesMut, SocketAddr)>,
+    outbound: Impairment<(Bytes, SocketAddr)>,
+    pending: Option<(Bytes, SocketAddr)>,
+    timer: Option<Delay>,
+}
+
+impl<S> ConditionedSocket<S>
+where
+    S: Stream<Item = (BytesMut, SocketAddr)>,
+    S: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<S as Stream>::Error> + From<<S as Sink>::SinkError>,
+{
+    pub fn new(socket: S, conditions: &NetworkConditions) -> ConditionedSocket<S> {
+        let seed = conditions.seed.unwrap_or_else(rand::random);
+
+        if conditions.enabled {
+            log::warn!(
+                "Simulating network conditions: {} ms latency, {} ms jitter, \
+                {} loss, {} duplication, {} reordering (seed {})",
+                conditions.latency_ms,
+                conditions.jitter_ms,
+                conditions.loss,
+                conditions.duplication,
+                conditions.reordering,
+                seed
+            );
+        }
+
+        ConditionedSocket {
+            socket,
+            enabled: conditions.enabled,
+            rng: StdRng::seed_from_u64(seed),
+            inbound: Impairment::new(conditions),
+            outbound: Impairment::new(conditions),
+            pending: None,
+            timer: None,
+        }
+    }
+
+    /// Sends every outbound datagram whose delay has passed
+    fn release(&mut self, now: Instant) -> Poll<(), Error> {
+        loop {
+            let datagram = match self.pending.take() {
+                Some(datagram) => datagram,
+                None => match self.outbound.pop(now) {
+                    Some(datagram) => datagram,
+                    None => break,
+                },
+            };
+
+            if let AsyncSink::NotReady(datagram) = self.socket.start_send(datagram)? {
+                self.pending = Some(datagram);
+                futures::try_ready!(self.socket.poll_complete());
+            }
+        }
+
+        Ok(self.socket.poll_complete()?)
+    }
+
+    /// Schedules a wakeup for the next delayed datagram, in either direction
+    fn wake_at_next_due(&mut self) -> Result<bool, Error> {
+        let due = match (self.inbound.next_due(), self.outbound.next_due()) {
+            (Some(inbound), Some(outbound)) => inbound.min(outbound),
+            (Some(due), None) | (None, Some(due)) => due,
+            (None, None) => return Ok(false),
+        };
+
+        let timer = self.timer.get_or_insert_with(|| Delay::new(due));
+        timer.reset(due);
+
+        let ready = timer.poll()
+            .map_err(|err| format_err!("Network conditions timer failed: {}", err))?
+            .is_ready();
+        Ok(ready)
+    }
+}
+
+impl<S> Stream for ConditionedSocket<S>
+where
+    S: Stream<Item = (BytesMut, SocketAddr)>,
+    S: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<S as Stream>::Error> + From<<S as Sink>::SinkError>,
+{
+    type Item = (BytesMut, SocketAddr);
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<(BytesMut, SocketAddr)>, Error> {
+        if !self.enabled {
+            return Ok(self.socket.poll()?);
+        }
+
+        loop {
+            let now = Instant::now();
+
+            while let Async::Ready(datagram) = self.socket.poll()? {
+                match datagram {
+                    Some(datagram) => self.inbound.push(datagram, now, &mut self.rng),
+                    None => return Ok(Async::Ready(None)),
+                }
+            }
+
+            self.release(now)?;
+
+            if let Some(datagram) = self.inbound.pop(now) {
+                return Ok(Async::Ready(Some(datagram)));
+            }
+
+            if !self.wake_at_next_due()? {
+                return Ok(Async::NotReady);
+            }
+        }
+    }
+}
+
+impl<S> Sink for ConditionedSocket<S>
+where
+    S: Stream<Item = (BytesMut, SocketAddr)>,
+    S: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<S as Stream>::Error> + From<<S as Sink>::SinkError>,
+{
+    type SinkItem = (Bytes, SocketAddr);
+    type SinkError = Error;
+
+    fn start_send(&mut self, datagram: (Bytes, SocketAddr))
+        -> StartSend<(Bytes, SocketAddr), Error>
+    {
+        if !self.enabled {
+            return Ok(self.socket.start_send(datagram)?);
+        }
+
+        self.outbound.push(datagram, Instant::now(), &mut self.rng);
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Error> {
+        if !self.enabled {
+            return Ok(self.socket.poll_complete()?);
+        }
+
+        self.release(Instant::now())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conditions() -> NetworkConditions {
+        NetworkConditions {
+            enabled: true,
+            latency_ms: 100,
+            jitter_ms: 40,
+            seed: Some(7),
+            ..NetworkConditions::default()
+        }
+    }
+
+    fn drain(impairment: &mut Impairment<u32>, now: Instant) -> Vec<u32> {
+        let mut items = Vec::new();
+        while let Some(item) = impairment.pop(now) {
+            items.push(item);
+        }
+        items
+    }
+
+    #[test]
+    fn test_latency_keeps_order() {
+        let mut impairment = Impairment::new(&conditions());
+        let mut rng = StdRng::seed_from_u64(7);
+        let now = Instant::now();
+
+        for i in 0..50 {
+            impairment.push(i, now + Duration::from_millis(i as u64), &mut rng);
+        }
+
+        assert!(drain(&mut impairment, now + Duration::from_millis(99)).is_empty());
+        let mut received = drain(&mut impairment, now + Duration::from_millis(120));
+        received.extend(drain(&mut impairment, now + Duration::from_millis(200)));
+        assert_eq!(received, (0..50).collect::<Vec<_>>());
+        assert!(impairment.is_empty());
+    }
+
+    #[test]
+    fn test_seeded_impairments_repeat() {
+        let conditions = NetworkConditions {
+            loss: 0.2,
+            duplication: 0.2,
+            reordering: 0.2,
+            ..conditions()
+        };
+        let now = Instant::now();
+        let later = now + Duration::from_secs(1);
+
+        let run = |seed| {
+            let mut impairment = Impairment::new(&conditions);
+            let mut rng = StdRng::seed_from_u64(seed);
+            for i in 0..200 {
+                impairment.push(i, now, &mut rng);
+            }
+            drain(&mut impairment, later)
+        };
+
+        let received = run(7);
+        assert_eq!(received, run(7));
+        assert_ne!(received, run(8));
+
+        let mut sorted = received.clone();
+        sorted.sort();
+        assert_ne!(received, sorted, "expected reordering");
+        sorted.dedup();
+        assert!(sorted.len() < 200, "expected loss");
+        assert!(received.len() > sorted.len(), "expected duplicates");
+    }
+}
\ No newline at end of file
@@ -0,0 +1,202 @@
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+use std::net::SocketAddr;
+use std::time::Instant;
+
+use bytes::{
+    Bytes,
+    BytesMut,
+};
+use failure::Error;
+use futures::{
+    Async,
+    AsyncSink,
+    Poll,
+    Sink,
+    StartSend,
+    Stream,
+};
+use tokio::codec::{
+    BytesCodec,
+    Decoder,
+};
+use tokio::net::UdpFramed;
+
+use super::Transport;
+use crate::net::capture::Capture;
+use crate::net::codec::EternalReckoningCodec;
+use crate::net::operation::Operation;
+use crate::net::stats::ConnectionStats;
+
+pub type UdpTransport = DatagramTransport<UdpFramed<BytesCodec>>;
+
+/**
+ * Transport over any datagram socket, keeping a codec for every peer so
+ * each connection has its own sequence numbers and fragment reassembly,
+ * along with its traffic counters. State for a peer is only kept once a
+ * datagram from it decodes, so stray traffic can't grow the maps.
+ */
+pub struct DatagramTransport<S> {
+    socket: S,
+    codecs: HashMap<SocketAddr, EternalReckoningCodec>,
+    stats: HashMap<SocketAddr, ConnectionStats>,
+    capture: Option<Capture>,
+    incoming: VecDeque<(Operation, SocketAddr)>,
+    outgoing: VecDeque<(Bytes, SocketAddr)>,
+}
+
+impl<S> DatagramTransport<S>
+where
+    S: Stream<Item = (BytesMut, SocketAddr)>,
+    S: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<S as Stream>::Error> + From<<S as Sink>::SinkError>,
+{
+    pub fn new(socket: S) -> DatagramTransport<S> {
+        DatagramTransport {
+            socket,
+            codecs: HashMap::new(),
+            stats: HashMap::new(),
+            capture: None,
+            incoming: VecDeque::new(),
+            outgoing: VecDeque::new(),
+        }
+    }
+
+    /// Records the packets exchanged with every peer to `capture`
+    pub fn with_capture(mut self, capture: Option<Capture>) -> DatagramTransport<S> {
+        self.capture = capture;
+        self
+    }
+
+    fn new_codec(capture: &Option<Capture>, peer: SocketAddr) -> EternalReckoningCodec {
+        match capture {
+            Some(capture) => EternalReckoningCodec::new().with_capture(capture.clone(), peer),
+            None => EternalReckoningCodec::new(),
+        }
+    }
+
+    fn codec(&mut self, peer: SocketAddr) -> &mut EternalReckoningCodec {
+        let capture = &self.capture;
+        self.codecs.entry(peer)
+            .or_insert_with(|| Self::new_codec(capture, peer))
+    }
+
+    fn receive(&mut self, peer: SocketAddr, mut buf: BytesMut) -> Result<(), Error> {
+        let size = buf.len();
+        let mut unknown = None;
+        let codec = match self.codecs.get_mut(&peer) {
+            Some(codec) => codec,
+            None => unknown.get_or_insert(Self::new_codec(&self.capture, peer)),
+        };
+        let mut ops = Vec::new();
+
+        // an empty datagram still decodes, to a disconnect
+        loop {
+            match codec.decode(&mut buf)? {
+                Some(op) => ops.push((op, peer)),
+                None => break,
+            }
+            if buf.is_empty() {
+                break;
+            }
+        }
+        let decode_errors = codec.decode_errors();
+
+        if let Some(codec) = unknown {
+            if ops.is_empty() {
+                return Ok(());
+            }
+            self.codecs.insert(peer, codec);
+        }
+
+        let stats = self.stats.entry(peer).or_default();
+        stats.record_inbound(size, Instant::now());
+        stats.decode_errors = decode_errors;
+        for (op, _) in &ops {
+            stats.record_operation_in(op.opcode());
+        }
+
+        self.incoming.extend(ops);
+        Ok(())
+    }
+}
+
+impl<S> Stream for DatagramTransport<S>
+where
+    S: Stream<Item = (BytesMut, SocketAddr)>,
+    S: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<S as Stream>::Error> + From<<S as Sink>::SinkError>,
+{
+    type Item = (Operation, SocketAddr);
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<(Operation, SocketAddr)>, Error> {
+        loop {
+            if let Some(incoming) = self.incoming.pop_front() {
+                return Ok(Async::Ready(Some(incoming)));
+            }
+
+            match futures::try_ready!(self.socket.poll()) {
+                Some((buf, peer)) => self.receive(peer, buf)?,
+                None => return Ok(Async::Ready(None)),
+            }
+        }
+    }
+}
+
+impl<S> Sink for DatagramTransport<S>
+where
+    S: Stream<Item = (BytesMut, SocketAddr)>,
+    S: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<S as Stream>::Error> + From<<S as Sink>::SinkError>,
+{
+    type SinkItem = (Operation, SocketAddr);
+    type SinkError = Error;
+
+    fn start_send(&mut self, (op, peer): (Operation, SocketAddr))
+        -> StartSend<(Operation, SocketAddr), Error>
+    {
+        let opcode = op.opcode();
+        for datagram in self.codec(peer).encode_datagrams(op)? {
+            self.outgoing.push_back((datagram.freeze(), peer));
+        }
+        self.stats.entry(peer).or_default().record_operation_out(opcode);
+
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Error> {
+        while let Some(datagram) = self.outgoing.pop_front() {
+            let (size, peer) = (datagram.0.len(), datagram.1);
+            match self.socket.start_send(datagram)? {
+                AsyncSink::Ready => {
+                    self.stats.entry(peer).or_default().record_outbound(size);
+                },
+                AsyncSink::NotReady(datagram) => {
+                    self.outgoing.push_front(datagram);
+                    futures::try_ready!(self.socket.poll_complete());
+                },
+            }
+        }
+
+        Ok(self.socket.poll_complete()?)
+    }
+}
+
+impl<S> Transport for DatagramTransport<S>
+where
+    S: Stream<Item = (BytesMut, SocketAddr)>,
+    S: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<S as Stream>::Error> + From<<S as Sink>::SinkError>,
+{
+    fn forget(&mut self, peer: &SocketAddr) {
+        self.codecs.remove(peer);
+        self.stats.remove(peer);
+    }
+
+    fn stats(&self, peer: &SocketAddr) -> Option<ConnectionStats> {
+        self.stats.get(peer).cloned()
+    }
+}
\ No newline at end of file
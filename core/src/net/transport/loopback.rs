@@ -0,0 +1,194 @@
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+use std::net::SocketAddr;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use bytes::{
+    Bytes,
+    BytesMut,
+};
+use futures::{
+    task::{
+        self,
+        Task,
+    },
+    Async,
+    AsyncSink,
+    Poll,
+    Sink,
+    StartSend,
+    Stream,
+};
+
+use super::DatagramTransport;
+
+pub type LoopbackTransport = DatagramTransport<LoopbackSocket>;
+
+#[derive(Default)]
+struct Endpoint {
+    queue: VecDeque<(BytesMut, SocketAddr)>,
+    task: Option<Task>,
+}
+
+/**
+ * In-memory datagram network. Datagrams are delivered to bound addresses
+ * immediately and in order, and silently dropped for unbound ones.
+ */
+#[derive(Clone, Default)]
+pub struct LoopbackNetwork {
+    endpoints: Arc<Mutex<HashMap<SocketAddr, Endpoint>>>,
+}
+
+impl LoopbackNetwork {
+    pub fn new() -> LoopbackNetwork {
+        LoopbackNetwork::default()
+    }
+
+    pub fn bind(&self, addr: SocketAddr) -> LoopbackSocket {
+        self.endpoints.lock().unwrap().insert(addr, Endpoint::default());
+
+        LoopbackSocket { addr, network: self.clone() }
+    }
+
+    pub fn transport(&self, addr: SocketAddr) -> LoopbackTransport {
+        DatagramTransport::new(self.bind(addr))
+    }
+}
+
+/// Endpoint of a `LoopbackNetwork`, unbound when dropped
+pub struct LoopbackSocket {
+    addr: SocketAddr,
+    network: LoopbackNetwork,
+}
+
+impl LoopbackSocket {
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Stream for LoopbackSocket {
+    type Item = (BytesMut, SocketAddr);
+    type Error = std::io::Error;
+
+    fn poll(&mut self) -> Poll<Option<(BytesMut, SocketAddr)>, std::io::Error> {
+        let mut endpoints = self.network.endpoints.lock().unwrap();
+        let endpoint = endpoints.entry(self.addr).or_default();
+
+        match endpoint.queue.pop_front() {
+            Some(datagram) => Ok(Async::Ready(Some(datagram))),
+            None => {
+                endpoint.task = Some(task::current());
+                Ok(Async::NotReady)
+            },
+        }
+    }
+}
+
+impl Sink for LoopbackSocket {
+    type SinkItem = (Bytes, SocketAddr);
+    type SinkError = std::io::Error;
+
+    fn start_send(&mut self, (datagram, peer): (Bytes, SocketAddr))
+        -> StartSend<(Bytes, SocketAddr), std::io::Error>
+    {
+        let mut endpoints = self.network.endpoints.lock().unwrap();
+        if let Some(endpoint) = endpoints.get_mut(&peer) {
+            endpoint.queue.push_back((BytesMut::from(&datagram[..]), self.addr));
+            if let Some(task) = endpoint.task.take() {
+                task.notify();
+            }
+        }
+
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), std::io::Error> {
+        Ok(Async::Ready(()))
+    }
+}
+
+impl Drop for LoopbackSocket {
+    fn drop(&mut self) {
+        if let Ok(mut endpoints) = self.network.endpoints.lock() {
+            endpoints.remove(&self.addr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::net::operation::{
+        self,
+        Operation,
+    };
+    use crate::net::transport::Transport;
+
+    #[test]
+    fn test_loopback_exchange() {
+        let network = LoopbackNetwork::new();
+        let server_addr = "10.0.0.1:6142".parse().unwrap();
+        let client_addr = "10.0.0.2:50000".parse().unwrap();
+        let mut server = network.transport(server_addr);
+        let mut client = network.transport(client_addr);
+
+        futures::future::lazy(move || {
+            assert!(server.poll().unwrap().is_not_ready());
+
+            // large enough to be fragmented
+            let update = Operation::SvUpdateWorld(operation::SvUpdateWorld {
+                snapshot: 1,
+                baseline: None,
+                input_tick: None,
+                updates: (0..100).map(|_| operation::EntityUpdate {
+                    uuid: Uuid::nil(),
+                    data: vec![operation::EntityComponent::Health(100)],
+                }).collect(),
+                removed: Vec::new(),
+            });
+
+            client.start_send((Operation::ClSync(operation::ClSync { client_time: 1 }), server_addr)).unwrap();
+            client.start_send((update, server_addr)).unwrap();
+            client.poll_complete().unwrap();
+
+            match server.poll().unwrap() {
+                Async::Ready(Some((Operation::ClSync(data), peer))) => {
+                    assert_eq!(data.client_time, 1);
+                    assert_eq!(peer, client_addr);
+                },
+                _ => panic!("expected a sync"),
+            }
+            match server.poll().unwrap() {
+                Async::Ready(Some((Operation::SvUpdateWorld(data), _))) => {
+                    assert_eq!(data.updates.len(), 100);
+                },
+                _ => panic!("expected a world update"),
+            }
+            assert!(server.poll().unwrap().is_not_ready());
+
+            // undecodable datagrams from unknown peers leave no state behind
+            let stranger_addr = "10.0.0.3:50000".parse().unwrap();
+            let mut stranger = network.bind(stranger_addr);
+            stranger.start_send((Bytes::from(&b"junk"[..]), server_addr)).unwrap();
+            assert!(server.poll().unwrap().is_not_ready());
+            assert!(server.stats(&stranger_addr).is_none());
+            assert!(server.stats(&client_addr).is_some());
+
+            // datagrams to unbound addresses are lost
+            drop(client);
+            server.start_send((Operation::DisconnectMessage, client_addr)).unwrap();
+            server.poll_complete().unwrap();
+
+            Ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,185 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+
+use bytes::{
+    Bytes,
+    BytesMut,
+};
+use failure::Error;
+use futures::{
+    Async,
+    Poll,
+    Sink,
+    StartSend,
+    Stream,
+};
+
+/**
+ * Two datagram sockets behind one, such as UDP alongside WebSocket.
+ * Datagrams are sent back through the socket their peer was last heard
+ * on, the first socket for peers never heard from. The second socket is
+ * optional.
+ */
+pub struct MergedSocket<A, B> {
+    first: A,
+    second: Option<B>,
+    second_peers: HashSet<SocketAddr>,
+    second_turn: bool,
+}
+
+impl<A, B> MergedSocket<A, B>
+where
+    A: Stream<Item = (BytesMut, SocketAddr)>,
+    A: Sink<SinkItem = (Bytes, SocketAddr)>,
+    B: Stream<Item = (BytesMut, SocketAddr)>,
+    B: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<A as Stream>::Error> + From<<A as Sink>::SinkError>,
+    Error: From<<B as Stream>::Error> + From<<B as Sink>::SinkError>,
+{
+    pub fn new(first: A, second: Option<B>) -> MergedSocket<A, B> {
+        MergedSocket {
+            first,
+            second,
+            second_peers: HashSet::new(),
+            second_turn: false,
+        }
+    }
+
+    fn poll_first(&mut self) -> Poll<Option<(BytesMut, SocketAddr)>, Error> {
+        let datagram = futures::try_ready!(self.first.poll());
+        if let Some((_, peer)) = &datagram {
+            self.second_peers.remove(peer);
+        }
+        Ok(Async::Ready(datagram))
+    }
+
+    fn poll_second(&mut self) -> Poll<Option<(BytesMut, SocketAddr)>, Error> {
+        let second = match self.second.as_mut() {
+            Some(second) => second,
+            None => return Ok(Async::NotReady),
+        };
+
+        match futures::try_ready!(second.poll()) {
+            Some((datagram, peer)) => {
+                // an empty datagram is the connection closing
+                if datagram.is_empty() {
+                    self.second_peers.remove(&peer);
+                } else {
+                    self.second_peers.insert(peer);
+                }
+                Ok(Async::Ready(Some((datagram, peer))))
+            },
+            None => {
+                log::warn!("Secondary socket closed");
+                self.second = None;
+                self.second_peers.clear();
+                Ok(Async::NotReady)
+            },
+        }
+    }
+}
+
+impl<A, B> Stream for MergedSocket<A, B>
+where
+    A: Stream<Item = (BytesMut, SocketAddr)>,
+    A: Sink<SinkItem = (Bytes, SocketAddr)>,
+    B: Stream<Item = (BytesMut, SocketAddr)>,
+    B: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<A as Stream>::Error> + From<<A as Sink>::SinkError>,
+    Error: From<<B as Stream>::Error> + From<<B as Sink>::SinkError>,
+{
+    type Item = (BytesMut, SocketAddr);
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<(BytesMut, SocketAddr)>, Error> {
+        // alternate so a busy socket cannot starve the other
+        self.second_turn = !self.second_turn;
+
+        if self.second_turn {
+            if let Async::Ready(datagram) = self.poll_second()? {
+                return Ok(Async::Ready(datagram));
+            }
+            self.poll_first()
+        } else {
+            if let Async::Ready(datagram) = self.poll_first()? {
+                return Ok(Async::Ready(datagram));
+            }
+            self.poll_second()
+        }
+    }
+}
+
+impl<A, B> Sink for MergedSocket<A, B>
+where
+    A: Stream<Item = (BytesMut, SocketAddr)>,
+    A: Sink<SinkItem = (Bytes, SocketAddr)>,
+    B: Stream<Item = (BytesMut, SocketAddr)>,
+    B: Sink<SinkItem = (Bytes, SocketAddr)>,
+    Error: From<<A as Stream>::Error> + From<<A as Sink>::SinkError>,
+    Error: From<<B as Stream>::Error> + From<<B as Sink>::SinkError>,
+{
+    type SinkItem = (Bytes, SocketAddr);
+    type SinkError = Error;
+
+    fn start_send(&mut self, datagram: (Bytes, SocketAddr))
+        -> StartSend<(Bytes, SocketAddr), Error>
+    {
+        if self.second_peers.contains(&datagram.1) {
+            if let Some(second) = self.second.as_mut() {
+                return Ok(second.start_send(datagram)?);
+            }
+        }
+        Ok(self.first.start_send(datagram)?)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Error> {
+        let first = self.first.poll_complete()?;
+        let second = match self.second.as_mut() {
+            Some(second) => second.poll_complete()?,
+            None => Async::Ready(()),
+        };
+
+        if first.is_ready() && second.is_ready() {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::Future;
+
+    use super::*;
+    use crate::net::transport::LoopbackNetwork;
+
+    #[test]
+    fn test_replies_follow_the_peer() {
+        let udp = LoopbackNetwork::new();
+        let websocket = LoopbackNetwork::new();
+        let server_addr: SocketAddr = "10.0.0.1:6142".parse().unwrap();
+        let peer: SocketAddr = "10.0.0.2:50000".parse().unwrap();
+
+        let mut server = MergedSocket::new(udp.bind(server_addr), Some(websocket.bind(server_addr)));
+        let mut udp_peer = udp.bind(peer);
+        let mut websocket_peer = websocket.bind(peer);
+
+        futures::future::lazy(move || {
+            let reply = (Bytes::from(&b"reply"[..]), peer);
+
+            websocket_peer.start_send((Bytes::from(&b"hello"[..]), server_addr)).unwrap();
+            assert!(server.poll().unwrap().is_ready());
+            server.start_send(reply.clone()).unwrap();
+            assert!(websocket_peer.poll().unwrap().is_ready());
+
+            udp_peer.start_send((Bytes::from(&b"hello"[..]), server_addr)).unwrap();
+            assert!(server.poll().unwrap().is_ready());
+            server.start_send(reply).unwrap();
+            assert!(udp_peer.poll().unwrap().is_ready());
+            assert!(websocket_peer.poll().unwrap().is_not_ready());
+
+            Ok::<(), ()>(())
+        }).wait().unwrap();
+    }
+}
\ No newline at end of file
@@ -0,0 +1,55 @@
+use std::net::SocketAddr;
+
+use failure::Error;
+use futures::{
+    Sink,
+    Stream,
+};
+
+use super::operation::Operation;
+use super::stats::ConnectionStats;
+
+mod address;
+mod conditions;
+mod datagram;
+mod loopback;
+mod merged;
+mod websocket;
+
+pub use address::ServerAddress;
+pub use conditions::{
+    ConditionedSocket,
+    Impairment,
+    NetworkConditions,
+};
+pub use datagram::{
+    DatagramTransport,
+    UdpTransport,
+};
+pub use loopback::{
+    LoopbackNetwork,
+    LoopbackSocket,
+    LoopbackTransport,
+};
+pub use merged::MergedSocket;
+pub use websocket::{
+    connect_websocket,
+    WebSocketConnection,
+    WebSocketListener,
+};
+
+/**
+ * Moves operations between this endpoint and its peers. Incoming operations
+ * are yielded with the peer that sent them; outgoing operations are sent to
+ * the peer they are paired with.
+ */
+pub trait Transport:
+    Stream<Item = (Operation, SocketAddr), Error = Error> +
+    Sink<SinkItem = (Operation, SocketAddr), SinkError = Error>
+{
+    /// Drops the connection state kept for a peer
+    fn forget(&mut self, peer: &SocketAddr);
+
+    /// Traffic counters of a peer, until it is forgotten
+    fn stats(&self, peer: &SocketAddr) -> Option<ConnectionStats>;
+}
\ No newline at end of file
@@ -0,0 +1,371 @@
+use std::collections::{
+    HashMap,
+    VecDeque,
+};
+use std::net::SocketAddr;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use bytes::{
+    Bytes,
+    BytesMut,
+};
+use failure::Error;
+use futures::{
+    Async,
+    AsyncSink,
+    Future,
+    Poll,
+    Sink,
+    StartSend,
+    Stream,
+};
+use tokio::net::{
+    TcpListener,
+    TcpStream,
+};
+use tokio::timer::{
+    Interval,
+    Timeout,
+};
+use tokio_tungstenite::{
+    tungstenite::{
+        handshake::server::NoCallback,
+        protocol::WebSocketConfig,
+        Message,
+    },
+    AcceptAsync,
+    WebSocketStream,
+};
+use url::Url;
+
+/// Largest message accepted, including messages split over several frames
+pub const MAX_MESSAGE_SIZE: usize = 1 << 16;
+
+/// Binary messages queued for a connection before further datagrams are dropped
+const MAX_SEND_QUEUE: usize = 256;
+
+/// Time a client has to complete the opening handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Connections that send nothing for this long are closed
+const IDLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Connections accepted at once, including those still handshaking
+const MAX_CONNECTIONS: usize = 256;
+
+fn config() -> Option<WebSocketConfig> {
+    Some(WebSocketConfig {
+        max_send_queue: Some(MAX_SEND_QUEUE),
+        max_message_size: Some(MAX_MESSAGE_SIZE),
+        max_frame_size: Some(MAX_MESSAGE_SIZE),
+    })
+}
+
+/**
+ * One WebSocket connection as a datagram socket: every binary message
+ * carries one datagram. An empty datagram is yielded when the connection
+ * closes or goes idle, which decodes to a disconnect, and the stream ends
+ * after it. Pings and closes are answered by tungstenite, ahead of any
+ * queued datagrams.
+ */
+pub struct WebSocketConnection {
+    stream: WebSocketStream<TcpStream>,
+    peer: SocketAddr,
+    last_received: Instant,
+    closed: bool,
+}
+
+impl WebSocketConnection {
+    fn new(stream: WebSocketStream<TcpStream>, peer: SocketAddr) -> WebSocketConnection {
+        WebSocketConnection {
+            stream,
+            peer,
+            last_received: Instant::now(),
+            closed: false,
+        }
+    }
+
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.peer
+    }
+
+    fn close(&mut self) -> Poll<Option<BytesMut>, Error> {
+        if self.closed {
+            return Ok(Async::Ready(None));
+        }
+
+        self.closed = true;
+        Ok(Async::Ready(Some(BytesMut::new())))
+    }
+
+    fn poll_datagram(&mut self) -> Poll<Option<BytesMut>, Error> {
+        if self.closed {
+            return Ok(Async::Ready(None));
+        }
+
+        loop {
+            let message = match self.stream.poll() {
+                Ok(Async::Ready(Some(message))) => message,
+                Ok(Async::Ready(None)) => return self.close(),
+                Ok(Async::NotReady) => {
+                    if self.last_received.elapsed() >= IDLE_TIMEOUT {
+                        log::debug!("Closing idle WebSocket connection {}", self.peer);
+                        return self.close();
+                    }
+                    return Ok(Async::NotReady);
+                },
+                Err(err) => {
+                    log::debug!("Closing WebSocket connection {}: {}", self.peer, err);
+                    return self.close();
+                },
+            };
+
+            self.last_received = Instant::now();
+            match message {
+                Message::Binary(data) => return Ok(Async::Ready(Some(BytesMut::from(data)))),
+                Message::Text(text) => {
+                    log::debug!("Skipping {} byte text message", text.len());
+                },
+                Message::Ping(_) | Message::Pong(_) | Message::Close(_) => (),
+            }
+        }
+    }
+}
+
+impl Stream for WebSocketConnection {
+    type Item = (BytesMut, SocketAddr);
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<(BytesMut, SocketAddr)>, Error> {
+        let peer = self.peer;
+        Ok(futures::try_ready!(self.poll_datagram())
+            .map(|datagram| (datagram, peer))
+            .into())
+    }
+}
+
+impl Sink for WebSocketConnection {
+    type SinkItem = (Bytes, SocketAddr);
+    type SinkError = Error;
+
+    fn start_send(&mut self, (datagram, _peer): (Bytes, SocketAddr))
+        -> StartSend<(Bytes, SocketAddr), Error>
+    {
+        if !self.closed {
+            // like UDP, datagrams that don't fit are lost
+            if let AsyncSink::NotReady(_) = self.stream.start_send(Message::Binary(datagram.to_vec()))? {
+                log::debug!("WebSocket connection {} busy, dropping datagram", self.peer);
+            }
+        }
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Error> {
+        if self.closed {
+            return Ok(Async::Ready(()));
+        }
+        Ok(self.stream.poll_complete()?)
+    }
+}
+
+/// Opens a WebSocket connection to `path` on `host`, resolved to `addr`
+pub fn connect_websocket(addr: SocketAddr, host: String, path: String)
+    -> impl Future<Item = WebSocketConnection, Error = Error>
+{
+    futures::future::result(Url::parse(&format!("ws://{}{}", host, path)))
+        .from_err()
+        .and_then(move |url| {
+            TcpStream::connect(&addr)
+                .from_err()
+                .and_then(move |stream| {
+                    tokio_tungstenite::client_async_with_config(url, stream, config())
+                        .from_err()
+                })
+        })
+        .map(move |(stream, _response)| WebSocketConnection::new(stream, addr))
+}
+
+/**
+ * Accepts WebSocket connections and presents them as a single datagram
+ * socket, with each connection's remote address as its peer. Clients that
+ * don't finish the handshake in time are dropped, and connections beyond
+ * the cap are refused.
+ */
+pub struct WebSocketListener {
+    listener: TcpListener,
+    handshakes: Vec<(SocketAddr, Timeout<AcceptAsync<TcpStream, NoCallback>>)>,
+    connections: HashMap<SocketAddr, WebSocketConnection>,
+    idle_check: Interval,
+    incoming: VecDeque<(BytesMut, SocketAddr)>,
+}
+
+impl WebSocketListener {
+    pub fn bind(addr: &SocketAddr) -> Result<WebSocketListener, Error> {
+        Ok(WebSocketListener {
+            listener: TcpListener::bind(addr)?,
+            handshakes: Vec::new(),
+            connections: HashMap::new(),
+            idle_check: Interval::new_interval(IDLE_TIMEOUT / 2),
+            incoming: VecDeque::new(),
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr, Error> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    fn accept(&mut self) {
+        loop {
+            match self.listener.poll_accept() {
+                Ok(Async::Ready((stream, peer))) => {
+                    if self.handshakes.len() + self.connections.len() >= MAX_CONNECTIONS {
+                        log::warn!("Refusing WebSocket connection from {}: too many connections", peer);
+                        continue;
+                    }
+
+                    let handshake = tokio_tungstenite::accept_async_with_config(stream, config());
+                    self.handshakes.push((peer, Timeout::new(handshake, HANDSHAKE_TIMEOUT)));
+                },
+                Ok(Async::NotReady) => break,
+                Err(err) => {
+                    log::warn!("Failed to accept WebSocket connection: {}", err);
+                    break;
+                },
+            }
+        }
+
+        let connections = &mut self.connections;
+        self.handshakes.retain_mut(|(peer, handshake)| match handshake.poll() {
+            Ok(Async::Ready(stream)) => {
+                log::info!("WebSocket connection from {}", peer);
+                connections.insert(*peer, WebSocketConnection::new(stream, *peer));
+                false
+            },
+            Ok(Async::NotReady) => true,
+            Err(err) => {
+                log::debug!("WebSocket handshake with {} failed: {}", peer, err);
+                false
+            },
+        });
+    }
+}
+
+impl Stream for WebSocketListener {
+    type Item = (BytesMut, SocketAddr);
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<(BytesMut, SocketAddr)>, Error> {
+        if let Some(datagram) = self.incoming.pop_front() {
+            return Ok(Async::Ready(Some(datagram)));
+        }
+
+        // wakes the task now and then, so idle connections are noticed
+        while let Async::Ready(Some(_)) = self.idle_check.poll()? {}
+
+        self.accept();
+
+        let mut closed = Vec::new();
+        for (peer, connection) in self.connections.iter_mut() {
+            loop {
+                match connection.poll_datagram() {
+                    Ok(Async::Ready(Some(datagram))) => {
+                        self.incoming.push_back((datagram, *peer));
+                    },
+                    Ok(Async::NotReady) => break,
+                    Ok(Async::Ready(None)) | Err(_) => {
+                        closed.push(*peer);
+                        break;
+                    },
+                }
+            }
+        }
+        for peer in closed {
+            self.connections.remove(&peer);
+        }
+
+        match self.incoming.pop_front() {
+            Some(datagram) => Ok(Async::Ready(Some(datagram))),
+            None => Ok(Async::NotReady),
+        }
+    }
+}
+
+impl Sink for WebSocketListener {
+    type SinkItem = (Bytes, SocketAddr);
+    type SinkError = Error;
+
+    fn start_send(&mut self, (datagram, peer): (Bytes, SocketAddr))
+        -> StartSend<(Bytes, SocketAddr), Error>
+    {
+        // like UDP, datagrams to peers that are gone are lost
+        if let Some(connection) = self.connections.get_mut(&peer) {
+            if let Err(err) = connection.start_send((datagram, peer)) {
+                log::debug!("Failed to send to WebSocket connection {}: {}", peer, err);
+            }
+        }
+        Ok(AsyncSink::Ready)
+    }
+
+    fn poll_complete(&mut self) -> Poll<(), Error> {
+        let mut ready = true;
+        for connection in self.connections.values_mut() {
+            match connection.poll_complete() {
+                Ok(Async::Ready(())) => (),
+                Ok(Async::NotReady) => ready = false,
+                // the connection is dropped once reading from it fails too
+                Err(err) => {
+                    log::debug!("Failed to flush WebSocket connection {}: {}", connection.peer, err);
+                },
+            }
+        }
+
+        if ready {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::operation::{
+        self,
+        Operation,
+    };
+    use crate::net::transport::DatagramTransport;
+
+    #[test]
+    fn test_echo_over_tcp() {
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+        let echoed = runtime.block_on(futures::future::lazy(|| {
+            let listener = WebSocketListener::bind(&"127.0.0.1:0".parse().unwrap())?;
+            let addr = listener.local_addr()?;
+
+            let (sink, stream) = DatagramTransport::new(listener).split();
+            tokio::spawn(sink.send_all(stream).map(|_| ()).map_err(|_| ()));
+
+            let sync = Operation::ClSync(operation::ClSync { client_time: 42 });
+            Ok::<_, Error>(
+                connect_websocket(addr, addr.to_string(), "/".to_string())
+                    .and_then(move |connection| {
+                        DatagramTransport::new(connection).send((sync, addr))
+                    })
+                    .and_then(|transport| transport.into_future().map_err(|(err, _)| err))
+                    .map(|(echoed, _)| echoed)
+            )
+        }).flatten()).unwrap();
+
+        match echoed {
+            Some((Operation::ClSync(data), _)) => assert_eq!(data.client_time, 42),
+            _ => panic!("expected the sync to be echoed"),
+        }
+
+        runtime.shutdown_now().wait().unwrap();
+    }
+}
\ No newline at end of file
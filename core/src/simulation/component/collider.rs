@@ -1,6 +1,6 @@
 use specs::prelude::*;
 
-use crate::display::terrain::HeightMap;
+use crate::simulation::HeightMap;
 
 pub struct Collider {
     pub collider: ColliderType,
@@ -14,6 +14,7 @@ pub struct Collision {
 }
 
 pub enum ColliderType {
+    /// infinite plane through the entity's position, facing along the normal
     Plane(nalgebra::Unit<nalgebra::Vector3<f64>>),
     Sphere(f64),
     HeightMap(HeightMap),
@@ -0,0 +1,26 @@
+use specs::prelude::*;
+
+use crate::simulation::worldsnapshot::{
+    EntityMap,
+    SnapshotComponent,
+    SnapshotError,
+};
+
+pub struct Health(pub u64);
+
+impl Component for Health {
+    type Storage = VecStorage<Self>;
+}
+
+impl SnapshotComponent for Health {
+    const KEY: &'static str = "health";
+    type Data = u64;
+
+    fn save(&self, _: &EntityMap) -> u64 {
+        self.0
+    }
+
+    fn restore(data: u64, _: &EntityMap) -> Result<Health, SnapshotError> {
+        Ok(Health(data))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,28 @@
+use specs::prelude::*;
+
+use crate::simulation::worldsnapshot::{
+    EntityMap,
+    SnapshotComponent,
+    SnapshotError,
+};
+
+pub struct Jump {
+    pub force: f64,
+}
+
+impl Component for Jump {
+    type Storage = VecStorage<Self>;
+}
+
+impl SnapshotComponent for Jump {
+    const KEY: &'static str = "jump";
+    type Data = f64;
+
+    fn save(&self, _: &EntityMap) -> f64 {
+        self.force
+    }
+
+    fn restore(data: f64, _: &EntityMap) -> Result<Jump, SnapshotError> {
+        Ok(Jump { force: data })
+    }
+}
\ No newline at end of file
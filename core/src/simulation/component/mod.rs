@@ -0,0 +1,17 @@
+pub mod collider;
+mod health;
+mod jump;
+mod movement;
+mod name;
+mod playerinput;
+mod position;
+mod velocity;
+
+pub use collider::Collider;
+pub use health::Health;
+pub use jump::Jump;
+pub use movement::Movement;
+pub use name::Name;
+pub use playerinput::PlayerInput;
+pub use position::Position;
+pub use velocity::Velocity;
\ No newline at end of file
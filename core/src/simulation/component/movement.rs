@@ -0,0 +1,43 @@
+use specs::prelude::*;
+
+use crate::simulation::worldsnapshot::{
+    EntityMap,
+    SnapshotComponent,
+    SnapshotError,
+};
+
+pub struct Movement {
+    /// distance covered per tick
+    pub speed: f64,
+    pub on_ground: bool,
+}
+
+impl Component for Movement {
+    type Storage = VecStorage<Self>;
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct MovementData {
+    pub speed: f64,
+    pub on_ground: bool,
+}
+
+impl SnapshotComponent for Movement {
+    const KEY: &'static str = "movement";
+    type Data = MovementData;
+
+    fn save(&self, _: &EntityMap) -> MovementData {
+        MovementData {
+            speed: self.speed,
+            on_ground: self.on_ground,
+        }
+    }
+
+    fn restore(data: MovementData, _: &EntityMap) -> Result<Movement, SnapshotError> {
+        Ok(Movement {
+            speed: data.speed,
+            on_ground: data.on_ground,
+        })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,26 @@
+use specs::prelude::*;
+
+use crate::simulation::worldsnapshot::{
+    EntityMap,
+    SnapshotComponent,
+    SnapshotError,
+};
+
+pub struct Name(pub String);
+
+impl Component for Name {
+    type Storage = VecStorage<Self>;
+}
+
+impl SnapshotComponent for Name {
+    const KEY: &'static str = "name";
+    type Data = String;
+
+    fn save(&self, _: &EntityMap) -> String {
+        self.0.clone()
+    }
+
+    fn restore(data: String, _: &EntityMap) -> Result<Name, SnapshotError> {
+        Ok(Name(data))
+    }
+}
\ No newline at end of file
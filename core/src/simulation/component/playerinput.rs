@@ -0,0 +1,17 @@
+use specs::prelude::*;
+
+use crate::simulation::movement::MoveKeys;
+
+/// Input a player is currently holding, applied every tick
+#[derive(Default)]
+pub struct PlayerInput {
+    /// client tick the input was sampled on, if it came over the network
+    pub tick: Option<u64>,
+    pub keys: MoveKeys,
+    pub jump: bool,
+    pub yaw: f64,
+}
+
+impl Component for PlayerInput {
+    type Storage = VecStorage<Self>;
+}
\ No newline at end of file
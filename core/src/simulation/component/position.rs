@@ -0,0 +1,26 @@
+use specs::prelude::*;
+
+use crate::simulation::worldsnapshot::{
+    EntityMap,
+    SnapshotComponent,
+    SnapshotError,
+};
+
+pub struct Position(pub nalgebra::Point3<f64>);
+
+impl Component for Position {
+    type Storage = VecStorage<Self>;
+}
+
+impl SnapshotComponent for Position {
+    const KEY: &'static str = "position";
+    type Data = [f64; 3];
+
+    fn save(&self, _: &EntityMap) -> [f64; 3] {
+        [self.0.x, self.0.y, self.0.z]
+    }
+
+    fn restore(data: [f64; 3], _: &EntityMap) -> Result<Position, SnapshotError> {
+        Ok(Position(nalgebra::Point3::from(data)))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,27 @@
+use specs::prelude::*;
+
+use crate::simulation::worldsnapshot::{
+    EntityMap,
+    SnapshotComponent,
+    SnapshotError,
+};
+
+/// Change in position per tick
+pub struct Velocity(pub nalgebra::Vector3<f64>);
+
+impl Component for Velocity {
+    type Storage = VecStorage<Self>;
+}
+
+impl SnapshotComponent for Velocity {
+    const KEY: &'static str = "velocity";
+    type Data = [f64; 3];
+
+    fn save(&self, _: &EntityMap) -> [f64; 3] {
+        [self.0.x, self.0.y, self.0.z]
+    }
+
+    fn restore(data: [f64; 3], _: &EntityMap) -> Result<Velocity, SnapshotError> {
+        Ok(Velocity(nalgebra::Vector3::from(data)))
+    }
+}
\ No newline at end of file
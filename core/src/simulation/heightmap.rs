@@ -0,0 +1,66 @@
+use failure::{
+    format_err,
+    Error,
+};
+
+/// Square grid of terrain heights, one unit apart
+pub struct HeightMap {
+    pub size: usize,
+    scale: f32,
+    data: Vec<f32>,
+}
+
+impl HeightMap {
+    pub fn new(data: Vec<f32>, size: usize, scale: f32) -> HeightMap {
+        assert_eq!(data.len(), size*size);
+        HeightMap { size, scale, data }
+    }
+
+    /// Reads the heights from the brightness of a square BMP image
+    pub fn from_bmp(path: &str, scale: f32) -> Result<HeightMap, Error> {
+        let mut reader = std::io::BufReader::new(
+            std::fs::File::open(path)
+                .map_err(|_| format_err!("cannot load heightmap: {}", path))?
+        );
+        let img = bmp::from_reader(&mut reader)?;
+
+        if img.get_width() != img.get_height() {
+            return Err(format_err!("heightmap is not square"));
+        }
+
+        let size = img.get_width() as usize;
+        let mut data = Vec::<f32>::with_capacity(size * size);
+        for (x, y) in img.coordinates() {
+            let pixel = img.get_pixel(x, y);
+            let value = (pixel.r as f32 + pixel.g as f32 + pixel.b as f32) / (3.0*255.0);
+            data.push(value);
+        }
+
+        Ok(HeightMap::new(data, size, scale))
+    }
+
+    pub fn len(&self) -> usize {
+        self.size * self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    pub fn scale(&self) -> f32 {
+        self.scale
+    }
+
+    /// Unscaled heights, row by row
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<f32> {
+        if x >= self.size || y >= self.size {
+            return None;
+        }
+
+        Some(*self.data.get(x + y * self.size).unwrap() * self.scale)
+    }
+}
\ No newline at end of file
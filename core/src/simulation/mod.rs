@@ -1,5 +1,33 @@
+pub mod component;
+mod heightmap;
+pub mod movement;
+mod physicsconfig;
+pub mod profiler;
+mod simulatedclock;
 mod simulation;
+pub mod system;
+mod terrain;
+mod ticklength;
+mod ticknumber;
 mod ticktime;
+mod timestep;
+pub mod worldsnapshot;
 
+pub use heightmap::HeightMap;
+pub use physicsconfig::PhysicsConfig;
+pub use profiler::{
+    Profiler,
+    ProfilerConfig,
+};
+pub use simulatedclock::SimulatedClock;
 pub use simulation::Simulation;
-pub use ticktime::TickTime;
\ No newline at end of file
+pub use terrain::TerrainConfig;
+pub use ticklength::TickLength;
+pub use ticknumber::TickNumber;
+pub use ticktime::TickTime;
+pub use timestep::FixedTimestep;
+pub use worldsnapshot::{
+    SnapshotComponent,
+    WorldSnapshot,
+    WorldSnapshotter,
+};
\ No newline at end of file
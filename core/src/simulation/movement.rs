@@ -0,0 +1,77 @@
+//! Movement rules shared by client prediction and the authoritative server.
+
+/// Movement keys held by a player, as carried by `ClInput`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MoveKeys(pub u8);
+
+impl MoveKeys {
+    pub const FORWARD: u8 = 0x01;
+    pub const BACKWARD: u8 = 0x02;
+    pub const LEFT: u8 = 0x04;
+    pub const RIGHT: u8 = 0x08;
+
+    pub fn set(&mut self, key: u8, held: bool) {
+        if held {
+            self.0 |= key;
+        } else {
+            self.0 &= !key;
+        }
+    }
+
+    pub fn held(self, key: u8) -> bool {
+        self.0 & key != 0
+    }
+}
+
+/// Unit direction of travel for the held keys when facing `yaw`
+pub fn movement_direction(keys: MoveKeys, yaw: f64)
+    -> Option<nalgebra::Vector3<f64>>
+{
+    let mut movement = nalgebra::Vector3::<f64>::new(0.0, 0.0, 0.0);
+    if keys.held(MoveKeys::FORWARD) {
+        movement -= nalgebra::Vector3::z();
+    }
+    if keys.held(MoveKeys::BACKWARD) {
+        movement += nalgebra::Vector3::z();
+    }
+    if keys.held(MoveKeys::LEFT) {
+        movement -= nalgebra::Vector3::x();
+    }
+    if keys.held(MoveKeys::RIGHT) {
+        movement += nalgebra::Vector3::x();
+    }
+
+    let movement = movement.try_normalize(0.001)?;
+
+    let rotation = nalgebra::Rotation3::from_axis_angle(
+        &nalgebra::Vector3::<f64>::y_axis(),
+        yaw
+    );
+
+    Some(rotation.transform_vector(&movement))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_movement_direction() {
+        let mut keys = MoveKeys::default();
+        assert!(movement_direction(keys, 0.0).is_none());
+
+        keys.set(MoveKeys::FORWARD, true);
+        keys.set(MoveKeys::BACKWARD, true);
+        assert!(movement_direction(keys, 0.0).is_none());
+
+        keys.set(MoveKeys::BACKWARD, false);
+        keys.set(MoveKeys::RIGHT, true);
+        let direction = movement_direction(keys, 0.0).unwrap();
+        assert!((direction.norm() - 1.0).abs() < 1e-9);
+        assert!(direction.x > 0.0 && direction.z < 0.0);
+
+        // a half turn reverses the direction
+        let turned = movement_direction(keys, std::f64::consts::PI).unwrap();
+        assert!((turned + direction).norm() < 1e-9);
+    }
+}
\ No newline at end of file
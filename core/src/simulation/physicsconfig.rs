@@ -0,0 +1,67 @@
+use crate::util::config::{
+    Validate,
+    Validator,
+};
+use super::TickLength;
+
+/**
+ * Physics tuning. The physics systems read it from the world every tick,
+ * with rates already scaled to ticks, so it can be replaced while running.
+ */
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct PhysicsConfig {
+    pub gravity: f64,
+    pub min_collision_depth: f64,
+    pub max_ground_slope: f64,
+    pub horisontal_drag: f64,
+    pub vertical_drag: f64,
+}
+
+impl Default for PhysicsConfig {
+    fn default() -> PhysicsConfig {
+        PhysicsConfig {
+            gravity: 0.48,
+            min_collision_depth: 0.001,
+            max_ground_slope: 0.2,
+            horisontal_drag: 0.25,
+            vertical_drag: 0.0,
+        }
+    }
+}
+
+impl PhysicsConfig {
+    /// Converts the per-second rates into per-tick ones
+    pub fn scaled(&self, tick_length: &TickLength) -> PhysicsConfig {
+        PhysicsConfig {
+            gravity: tick_length.scale_to(self.gravity),
+            ..self.clone()
+        }
+    }
+}
+
+impl Validate for PhysicsConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check("gravity", self.gravity.is_finite(), "must be a number");
+        validator.check(
+            "min-collision-depth",
+            self.min_collision_depth > 0.0,
+            "must be greater than 0"
+        );
+        validator.check(
+            "max-ground-slope",
+            (0.0..=1.0).contains(&self.max_ground_slope),
+            "must be between 0 and 1"
+        );
+        validator.check(
+            "horisontal-drag",
+            (0.0..=1.0).contains(&self.horisontal_drag),
+            "must be between 0 and 1"
+        );
+        validator.check(
+            "vertical-drag",
+            (0.0..=1.0).contains(&self.vertical_drag),
+            "must be between 0 and 1"
+        );
+    }
+}
\ No newline at end of file
@@ -0,0 +1,393 @@
+use std::collections::{
+    BTreeMap,
+    VecDeque,
+};
+use std::io::Write;
+use std::sync::{
+    atomic::{
+        AtomicU64,
+        Ordering,
+    },
+    Arc,
+    Mutex,
+};
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use specs::{
+    shred::RunningTime,
+    System,
+    World,
+};
+
+use crate::util::config::{
+    Validate,
+    Validator,
+};
+use super::TickNumber;
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct ProfilerConfig {
+    pub enabled: bool,
+    /// number of recent ticks that percentiles and traces cover
+    pub window_ticks: usize,
+    /// how often timings are logged, 0 to only warn about slow ticks
+    pub report_interval_ms: u64,
+    /// Chrome trace-event file rewritten with the recent ticks at every report
+    pub trace_file: Option<String>,
+}
+
+impl Default for ProfilerConfig {
+    fn default() -> ProfilerConfig {
+        ProfilerConfig {
+            enabled: true,
+            window_ticks: 300,
+            report_interval_ms: 60000,
+            trace_file: None,
+        }
+    }
+}
+
+impl Validate for ProfilerConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check("window-ticks", self.window_ticks > 0, "must be greater than 0");
+    }
+}
+
+/// Percentiles of one timing over the profiler's window
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimingSummary {
+    pub name: &'static str,
+    pub samples: usize,
+    pub p50: Duration,
+    pub p95: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl TimingSummary {
+    fn from_samples(name: &'static str, samples: &VecDeque<Duration>) -> TimingSummary {
+        let mut sorted: Vec<Duration> = samples.iter().cloned().collect();
+        sorted.sort();
+
+        let percentile = |p: usize| match sorted.len() {
+            0 => Duration::from_secs(0),
+            len => sorted[((len - 1) * p + 50) / 100],
+        };
+
+        TimingSummary {
+            name,
+            samples: sorted.len(),
+            p50: percentile(50),
+            p95: percentile(95),
+            p99: percentile(99),
+            max: sorted.last().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+impl std::fmt::Display for TimingSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} p50 {:?} p95 {:?} p99 {:?} max {:?}",
+            self.name,
+            self.p50,
+            self.p95,
+            self.p99,
+            self.max
+        )
+    }
+}
+
+struct TraceEvent {
+    name: &'static str,
+    thread: u64,
+    start: Duration,
+    duration: Duration,
+}
+
+struct ProfilerState {
+    window: usize,
+    budget: Duration,
+    report_interval: Option<Duration>,
+    trace_file: Option<String>,
+    epoch: Instant,
+    last_report: Instant,
+    ticks: VecDeque<Duration>,
+    systems: BTreeMap<&'static str, VecDeque<Duration>>,
+    /// system timings of the tick in progress
+    current: Vec<TraceEvent>,
+    trace: VecDeque<Vec<TraceEvent>>,
+}
+
+impl ProfilerState {
+    fn record(samples: &mut VecDeque<Duration>, window: usize, duration: Duration) {
+        if samples.len() == window {
+            samples.pop_front();
+        }
+        samples.push_back(duration);
+    }
+
+    fn warn_over_budget(&self, tick: TickNumber, duration: Duration) {
+        let mut systems: Vec<&TraceEvent> = self.current.iter().collect();
+        systems.sort_by_key(|event| std::cmp::Reverse(event.duration));
+
+        let breakdown: Vec<String> = systems.iter()
+            .map(|event| format!("{} {:?}", event.name, event.duration))
+            .collect();
+
+        log::warn!(
+            "Tick {} exceeded its budget: took {:?}, {:?} allowed ({})",
+            tick.0,
+            duration,
+            self.budget,
+            breakdown.join(", ")
+        );
+    }
+}
+
+/**
+ * Records how long each tick and each wrapped system take, keeping the
+ * timings of a rolling window of ticks. Clones share the same records, so
+ * systems running on other threads can report into it.
+ */
+#[derive(Clone)]
+pub struct Profiler {
+    enabled: bool,
+    state: Arc<Mutex<ProfilerState>>,
+}
+
+impl Profiler {
+    pub fn new(config: &ProfilerConfig, budget: Duration) -> Profiler {
+        let now = Instant::now();
+
+        Profiler {
+            enabled: config.enabled,
+            state: Arc::new(Mutex::new(ProfilerState {
+                window: config.window_ticks.max(1),
+                budget,
+                report_interval: match config.report_interval_ms {
+                    0 => None,
+                    ms => Some(Duration::from_millis(ms)),
+                },
+                trace_file: config.trace_file.clone(),
+                epoch: now,
+                last_report: now,
+                ticks: VecDeque::new(),
+                systems: BTreeMap::new(),
+                current: Vec::new(),
+                trace: VecDeque::new(),
+            })),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Wraps `system` so that its run time is recorded under `name`
+    pub fn timed<S>(&self, name: &'static str, system: S) -> Timed<S> {
+        Timed {
+            name,
+            system,
+            profiler: self.clone(),
+        }
+    }
+
+    pub fn record_system(&self, name: &'static str, started: Instant, duration: Duration) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let window = state.window;
+        let samples = state.systems.entry(name).or_default();
+        ProfilerState::record(samples, window, duration);
+
+        let start = started.saturating_duration_since(state.epoch);
+        state.current.push(TraceEvent {
+            name,
+            thread: thread_id(),
+            start,
+            duration,
+        });
+    }
+
+    /**
+     * Closes the tick in progress, warning if it took longer than the
+     * budget and logging a report when one is due.
+     */
+    pub fn record_tick(&self, tick: TickNumber, started: Instant, duration: Duration) {
+        if !self.enabled {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let window = state.window;
+        ProfilerState::record(&mut state.ticks, window, duration);
+
+        if duration > state.budget {
+            state.warn_over_budget(tick, duration);
+        }
+
+        let start = started.saturating_duration_since(state.epoch);
+        let mut events = std::mem::take(&mut state.current);
+        events.push(TraceEvent {
+            name: "tick",
+            thread: thread_id(),
+            start,
+            duration,
+        });
+        if state.trace.len() == window {
+            state.trace.pop_front();
+        }
+        state.trace.push_back(events);
+
+        if let Some(interval) = state.report_interval {
+            let now = Instant::now();
+            if now.duration_since(state.last_report) >= interval {
+                state.last_report = now;
+                drop(state);
+                self.report();
+            }
+        }
+    }
+
+    /// Tick timings first, then each system's by name
+    pub fn summary(&self) -> Vec<TimingSummary> {
+        let state = self.state.lock().unwrap();
+
+        std::iter::once(TimingSummary::from_samples("tick", &state.ticks))
+            .chain(state.systems.iter()
+                .map(|(name, samples)| TimingSummary::from_samples(name, samples)))
+            .collect()
+    }
+
+    /// Logs the summary, and rewrites the trace file if there is one
+    pub fn report(&self) {
+        let summary: Vec<String> = self.summary().iter()
+            .map(ToString::to_string)
+            .collect();
+        log::info!("Tick timings: {}", summary.join("; "));
+
+        let trace_file = self.state.lock().unwrap().trace_file.clone();
+        if let Some(path) = trace_file {
+            std::fs::File::create(&path)
+                .and_then(|file| self.write_chrome_trace(std::io::BufWriter::new(file)))
+                .unwrap_or_else(|err| {
+                    log::error!("Failed to write trace file {}: {}", path, err);
+                });
+        }
+    }
+
+    /**
+     * Writes the recent ticks in the Chrome trace-event format, which
+     * chrome://tracing and Perfetto can open.
+     */
+    pub fn write_chrome_trace<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let state = self.state.lock().unwrap();
+
+        write!(writer, "{{\"displayTimeUnit\":\"ms\",\"traceEvents\":[")?;
+        let events = state.trace.iter().flat_map(|tick| tick.iter());
+        for (index, event) in events.enumerate() {
+            if index > 0 {
+                write!(writer, ",")?;
+            }
+            write!(
+                writer,
+                "{{\"name\":\"{}\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{},\"dur\":{}}}",
+                event.name.escape_default(),
+                event.thread,
+                event.start.as_micros(),
+                event.duration.as_micros()
+            )?;
+        }
+        write!(writer, "]}}")?;
+
+        writer.flush()
+    }
+}
+
+/// Small stable id for the current thread, for trace events
+fn thread_id() -> u64 {
+    static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+    thread_local! {
+        static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+    }
+
+    THREAD_ID.with(|id| *id)
+}
+
+/// A system whose run time is recorded by a `Profiler`
+pub struct Timed<S> {
+    name: &'static str,
+    system: S,
+    profiler: Profiler,
+}
+
+impl<'a, S: System<'a>> System<'a> for Timed<S> {
+    type SystemData = S::SystemData;
+
+    fn run(&mut self, data: Self::SystemData) {
+        let started = Instant::now();
+        self.system.run(data);
+        self.profiler.record_system(self.name, started, started.elapsed());
+    }
+
+    fn running_time(&self) -> RunningTime {
+        self.system.running_time()
+    }
+
+    fn setup(&mut self, world: &mut World) {
+        self.system.setup(world);
+    }
+
+    fn dispose(self, world: &mut World) {
+        self.system.dispose(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_and_trace() {
+        let config = ProfilerConfig {
+            window_ticks: 100,
+            report_interval_ms: 0,
+            ..Default::default()
+        };
+        let profiler = Profiler::new(&config, Duration::from_millis(100));
+
+        // 150 ticks, of which only the last 100 count
+        let start = Instant::now();
+        for tick in 1..=150 {
+            let duration = Duration::from_millis(tick);
+            profiler.record_system("movement", start, duration / 2);
+            profiler.record_tick(TickNumber(tick), start, duration);
+        }
+
+        let summary = profiler.summary();
+        assert_eq!(summary[0].name, "tick");
+        assert_eq!(summary[0].samples, 100);
+        assert_eq!(summary[0].p50, Duration::from_millis(101));
+        assert_eq!(summary[0].p95, Duration::from_millis(145));
+        assert_eq!(summary[0].max, Duration::from_millis(150));
+        assert_eq!(summary[1].name, "movement");
+        assert_eq!(summary[1].max, Duration::from_millis(75));
+
+        let mut trace = Vec::new();
+        profiler.write_chrome_trace(&mut trace).unwrap();
+        let trace = String::from_utf8(trace).unwrap();
+        assert!(trace.starts_with("{\"displayTimeUnit\":\"ms\",\"traceEvents\":[{"));
+        assert!(trace.ends_with("}]}"));
+        assert_eq!(trace.matches("\"name\":\"movement\"").count(), 100);
+        assert_eq!(trace.matches("\"name\":\"tick\"").count(), 100);
+        assert!(trace.contains("\"dur\":150000}"));
+    }
+}
\ No newline at end of file
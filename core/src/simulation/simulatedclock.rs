@@ -0,0 +1,71 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+
+/**
+ * Clock for stepping a simulation without real time passing: every tick
+ * happens exactly one tick length after the previous one, measured from
+ * an arbitrary epoch.
+ */
+#[derive(Clone, Debug)]
+pub struct SimulatedClock {
+    epoch: Instant,
+    tick_length: Duration,
+    elapsed: Duration,
+}
+
+impl SimulatedClock {
+    pub fn new(tick_length: Duration) -> SimulatedClock {
+        SimulatedClock {
+            epoch: Instant::now(),
+            tick_length,
+            elapsed: Duration::from_secs(0),
+        }
+    }
+
+    pub fn tick_length(&self) -> Duration {
+        self.tick_length
+    }
+
+    /// Simulated time since the epoch
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn now(&self) -> Instant {
+        self.epoch + self.elapsed
+    }
+
+    /// Moves the clock on by one tick, returning the new time
+    pub fn tick(&mut self) -> Instant {
+        self.advance(self.tick_length)
+    }
+
+    /// Moves the clock on by an arbitrary amount, such as a stall
+    pub fn advance(&mut self, duration: Duration) -> Instant {
+        self.elapsed += duration;
+        self.now()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ticks_are_evenly_spaced() {
+        let tick = Duration::from_millis(16);
+        let mut clock = SimulatedClock::new(tick);
+        let start = clock.now();
+
+        let first = clock.tick();
+        let second = clock.tick();
+        assert_eq!(first - start, tick);
+        assert_eq!(second - first, tick);
+
+        let stalled = clock.advance(Duration::from_secs(1));
+        assert_eq!(stalled - second, Duration::from_secs(1));
+        assert_eq!(clock.elapsed(), tick * 2 + Duration::from_secs(1));
+    }
+}
\ No newline at end of file
@@ -6,11 +6,25 @@ use specs::{
     WorldExt,
 };
 
-use super::TickTime;
+use crate::util::shutdown::Shutdown;
+
+use super::{
+    FixedTimestep,
+    Profiler,
+    SimulatedClock,
+    TickNumber,
+    TickTime,
+};
+
+/// Ticks run back to back after an overrun, unless configured otherwise
+const DEFAULT_MAX_CATCH_UP: u32 = 5;
 
 pub struct Simulation<'a, 'b, T: std::marker::Send + Sync> {
     dispatcher: Dispatcher<'a, 'b>,
     world: World,
+    max_catch_up: u32,
+    profiler: Option<Profiler>,
+    shutdown: Shutdown,
     _events: std::marker::PhantomData<T>,
 }
 
@@ -20,16 +34,57 @@ impl<'a, 'b, T: 'static + std::marker::Send + Sync> Simulation<'a, 'b, T> {
     {
         world.insert::<Vec<T>>(Vec::new());
         world.insert(TickTime::default());
+        world.insert(TickNumber::default());
 
         dispatcher.setup(&mut world);
 
         Simulation {
             dispatcher,
             world,
+            max_catch_up: DEFAULT_MAX_CATCH_UP,
+            profiler: None,
+            shutdown: Shutdown::new(),
             _events: std::marker::PhantomData,
         }
     }
 
+    /// Limits how many late ticks `run` simulates back to back
+    pub fn with_max_catch_up(mut self, ticks: u32) -> Simulation<'a, 'b, T> {
+        self.max_catch_up = ticks;
+        self
+    }
+
+    /**
+     * Records tick times into `profiler`, which then also warns about slow
+     * ticks. Systems report their own times when wrapped with
+     * `Profiler::timed`.
+     */
+    pub fn with_profiler(mut self, profiler: Profiler) -> Simulation<'a, 'b, T> {
+        self.profiler = Some(profiler).filter(Profiler::is_enabled);
+        self
+    }
+
+    /// Makes `run` return once shutdown is requested
+    pub fn with_shutdown(mut self, shutdown: Shutdown) -> Simulation<'a, 'b, T> {
+        self.shutdown = shutdown;
+        self
+    }
+
+    /// Number of the most recently simulated tick, 0 before the first
+    pub fn tick(&self) -> TickNumber {
+        *self.world.read_resource::<TickNumber>()
+    }
+
+    /// Read access to the world between ticks, such as for assertions
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Write access to the world between ticks, such as for setting up a scenario
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
     pub fn push_event(&mut self, event: T) {
         let mut queue = self.world.write_resource::<Vec<T>>();
         (*queue).push(event);
@@ -47,13 +102,42 @@ impl<'a, 'b, T: 'static + std::marker::Send + Sync> Simulation<'a, 'b, T> {
 
     pub fn next_tick(&mut self, tick_time: std::time::Instant) {
         self.set_tick_time(tick_time);
+        self.world.write_resource::<TickNumber>().0 += 1;
 
+        let started = Instant::now();
         self.dispatcher.dispatch(&mut self.world);
         self.world.maintain();
 
+        if let Some(ref profiler) = self.profiler {
+            profiler.record_tick(self.tick(), started, started.elapsed());
+        }
+
         self.clear_events();
     }
 
+    /**
+     * Runs one tick with the given events, at the time of the next tick of
+     * `clock`. Stepping never waits for real time to pass.
+     */
+    pub fn step<I>(&mut self, clock: &mut SimulatedClock, events: I)
+    where
+        I: IntoIterator<Item = T>,
+    {
+        for event in events {
+            self.push_event(event);
+        }
+
+        let tick_time = clock.tick();
+        self.next_tick(tick_time);
+    }
+
+    /// Runs exactly `ticks` ticks without events
+    pub fn step_ticks(&mut self, clock: &mut SimulatedClock, ticks: u64) {
+        for _ in 0..ticks {
+            self.step(clock, Vec::new());
+        }
+    }
+
     pub fn run<F>(
         &mut self,
         mut receiver: F,
@@ -63,9 +147,15 @@ impl<'a, 'b, T: 'static + std::marker::Send + Sync> Simulation<'a, 'b, T> {
         F: FnMut() -> Result<Option<T>, ()>,
         F: 'static,
     {
-        let mut next_frame = Instant::now();
+        let mut timestep = FixedTimestep::new(tick_length, self.max_catch_up, Instant::now());
 
         loop {
+            if self.shutdown.is_requested() {
+                log::info!("Simulation stopping after tick {}", self.tick().0);
+                return Ok(());
+            }
+
+            let next_frame = timestep.next_tick();
             while Instant::now() < next_frame {
                 std::thread::sleep(next_frame - Instant::now());
             }
@@ -78,9 +168,36 @@ impl<'a, 'b, T: 'static + std::marker::Send + Sync> Simulation<'a, 'b, T> {
                 }
             }
 
+            let started = Instant::now();
             self.next_tick(next_frame);
+            let duration = started.elapsed();
+
+            // a profiler reports overruns itself, with a breakdown by system
+            if duration > tick_length && self.profiler.is_none() {
+                log::warn!(
+                    "Tick {} overran: took {:?}, {:?} allowed",
+                    self.tick().0,
+                    duration,
+                    tick_length
+                );
+            }
 
-            next_frame = next_frame + tick_length;
+            let skipped = timestep.advance(Instant::now());
+            if skipped > 0 {
+                log::warn!(
+                    "Simulation fell behind, skipping {} ticks after tick {}",
+                    skipped,
+                    self.tick().0
+                );
+            }
         }
     }
+
+    /**
+     * Stops the systems, letting them release what they hold and say
+     * goodbye to whoever they talk to.
+     */
+    pub fn dispose(mut self) {
+        self.dispatcher.dispose(&mut self.world);
+    }
 }
\ No newline at end of file
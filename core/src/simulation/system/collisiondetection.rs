@@ -11,21 +11,26 @@ use crate::simulation::{
         Position,
     },
 };
-use crate::display::terrain::HeightMap;
+use crate::simulation::HeightMap;
 
+#[derive(Default)]
 pub struct CollisionDetection {
+    /// taken from the `PhysicsConfig` resource every run
     min_collision_depth: f64,
 }
 
 impl<'a> System<'a> for CollisionDetection {
     type SystemData = (
         Entities<'a>,
+        ReadExpect<'a, PhysicsConfig>,
         ReadStorage<'a, Position>,
         WriteStorage<'a, Collider>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entities, positions, mut colliders) = data;
+        let (entities, config, positions, mut colliders) = data;
+
+        self.min_collision_depth = config.min_collision_depth;
 
         for collider in (&mut colliders).join() {
             collider.collisions.clear();
@@ -43,20 +48,17 @@ impl<'a> System<'a> for CollisionDetection {
                     continue;
                 }
 
-                match self.check_collision(
-                    &pos,
+                if let Some((depth, normal)) = self.check_collision(
+                    pos,
                     &collider.collider,
-                    &target_pos,
+                    target_pos,
                     &target_collider.collider
                 )
                 {
-                    Some((depth, normal)) => {
-                        collisions.push((
-                            Collision { with: target, depth, normal },
-                            Collision { with: ent, depth: -depth, normal }
-                        ));
-                    },
-                    None => (),
+                    collisions.push((
+                        Collision { with: target, depth, normal },
+                        Collision { with: ent, depth: -depth, normal }
+                    ));
                 }
             }
         }
@@ -70,10 +72,6 @@ impl<'a> System<'a> for CollisionDetection {
 }
 
 impl CollisionDetection {
-    pub fn new(config: &PhysicsConfig) -> CollisionDetection {
-        CollisionDetection { min_collision_depth: config.min_collision_depth }
-    }
-
     fn check_collision(
         &self,
         t1_pos: &Position, t1_collider: &ColliderType,
@@ -84,13 +82,13 @@ impl CollisionDetection {
             ColliderType::Sphere(t1) => {
                 match t2_collider {
                     ColliderType::Sphere(t2) => {
-                        self.sphere_to_sphere(&t1_pos, *t1, &t2_pos, *t2)
+                        self.sphere_to_sphere(t1_pos, *t1, t2_pos, *t2)
                     },
                     ColliderType::Plane(t2) => {
-                        self.sphere_to_plane(&t1_pos, *t1, &t2_pos, t2)
+                        self.sphere_to_plane(t1_pos, *t1, t2_pos, t2)
                     },
                     ColliderType::HeightMap(t2) => {
-                        self.sphere_to_heightmap(&t1_pos, *t1, &t2_pos, t2)
+                        self.sphere_to_heightmap(t1_pos, *t1, t2_pos, t2)
                     }
                 }
             },
@@ -156,10 +154,12 @@ impl CollisionDetection {
     ) -> Option<(nalgebra::Vector3<f64>, nalgebra::Unit<nalgebra::Vector3<f64>>)>
     {
         // TODO: change to generic AABB check
+        // the last row and column only close off the quads before them
+        let extent = t2_data.size.saturating_sub(1) as f64;
         if t1_pos.0.x <= t2_pos.0.x ||
-            t1_pos.0.x >= t2_pos.0.x + t2_data.size as f64 ||
+            t1_pos.0.x >= t2_pos.0.x + extent ||
             t1_pos.0.z <= t2_pos.0.z ||
-            t1_pos.0.z >= t2_pos.0.z + t2_data.size as f64
+            t1_pos.0.z >= t2_pos.0.z + extent
         {
             return None
         }
@@ -178,22 +178,21 @@ impl CollisionDetection {
         let quad_y = map_offs.y - grid_y as f64;
 
         // get the normal for the relevant height map triangle
-        let normal: _;
-        if quad_x + quad_y <= 0.5 {
+        let normal = if quad_x + quad_y <= 0.5 {
             // cross(quad.1 - quad.0, quad.2 - quad.0)
-            normal = nalgebra::Unit::new_normalize(
+            nalgebra::Unit::new_normalize(
                 nalgebra::Vector3::<f64>::new(1.0, quad.1 - quad.0, 0.0).cross(
                     &nalgebra::Vector3::<f64>::new(0.0, quad.2 - quad.0, 1.0)
                 )
-            );
+            )
         } else {
             // cross(quad.3 - quad.1, quad.2 - quad.1)
-            normal = nalgebra::Unit::new_normalize(
+            nalgebra::Unit::new_normalize(
                 nalgebra::Vector3::<f64>::new(0.0, quad.3 - quad.1, 1.0).cross(
                     &nalgebra::Vector3::<f64>::new(-1.0, quad.2 - quad.1, 1.0)
                 )
-            );
-        }
+            )
+        };
 
         let quad_pos = nalgebra::Point3::new(
             t2_pos.0.x + grid_x as f64,
@@ -10,25 +10,13 @@ use crate::simulation::{
     },
 };
 
-pub struct CollisionResolver {
-    min_ground_y: f64,
-}
-
-impl CollisionResolver {
-    /**
-     * 'max_ground_slope' gives the maximum slope in percentage notation
-     * upon which something is considered to be "on ground"
-     */
-    pub fn new(config: &PhysicsConfig) -> CollisionResolver {
-        CollisionResolver {
-            min_ground_y: 1.0 - config.max_ground_slope,
-        }
-    }
-}
+/// Pushes colliding entities apart and tracks which ones stand on ground
+pub struct CollisionResolver;
 
 impl<'a> System<'a> for CollisionResolver {
     type SystemData = (
         Entities<'a>,
+        ReadExpect<'a, PhysicsConfig>,
         ReadStorage<'a, Collider>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Velocity>,
@@ -36,7 +24,11 @@ impl<'a> System<'a> for CollisionResolver {
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (entity, colliders, mut positions, mut velocities, mut movement) = data;
+        let (entity, config, colliders, mut positions, mut velocities, mut movement) = data;
+
+        // 'max_ground_slope' gives the maximum slope in percentage notation
+        // upon which something is considered to be "on ground"
+        let min_ground_y = 1.0 - config.max_ground_slope;
 
         for (ent, collider, pos, vel)
             in (&entity, &colliders, &mut positions, &mut velocities).join()
@@ -47,7 +39,7 @@ impl<'a> System<'a> for CollisionResolver {
                 pos.0 -= collision.depth;
                 vel.0 -= vel.0.dot(collision.normal.as_ref()) * collision.normal.as_ref();
 
-                if collision.normal.as_ref().y >= self.min_ground_y {
+                if collision.normal.as_ref().y >= min_ground_y {
                     on_ground = true;
                 }
             }
@@ -0,0 +1,9 @@
+mod collisiondetection;
+mod collisionresolver;
+mod physics;
+mod playermovement;
+
+pub use collisiondetection::CollisionDetection;
+pub use collisionresolver::CollisionResolver;
+pub use physics::Physics;
+pub use playermovement::PlayerMovement;
\ No newline at end of file
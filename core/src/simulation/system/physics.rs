@@ -9,39 +9,31 @@ use crate::simulation::{
     },
 };
 
-pub struct Physics {
-    gravity: nalgebra::Vector3::<f64>,
-    horisontal_drag_coeff: f64,
-    vertical_drag_coeff: f64,
-}
-
-impl Physics {
-    pub fn new(config: &PhysicsConfig) -> Physics {
-        Physics {
-            gravity: nalgebra::Vector3::new(0.0, config.gravity, 0.0),
-            horisontal_drag_coeff: 1.0 - config.horisontal_drag,
-            vertical_drag_coeff: 1.0 - config.vertical_drag,
-        }
-    }
-}
+/// Moves entities by their velocity, applying drag and gravity
+pub struct Physics;
 
 impl<'a> System<'a> for Physics {
     type SystemData = (
         Entities<'a>,
+        ReadExpect<'a, PhysicsConfig>,
         WriteStorage<'a, Position>,
         WriteStorage<'a, Velocity>,
         ReadStorage<'a, Movement>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
-        let (ent, mut pos, mut vel, mov) = data;
+        let (ent, config, mut pos, mut vel, mov) = data;
+
+        let gravity = nalgebra::Vector3::new(0.0, config.gravity, 0.0);
+        let horisontal_drag_coeff = 1.0 - config.horisontal_drag;
+        let vertical_drag_coeff = 1.0 - config.vertical_drag;
 
         for (ent, pos, vel) in (&ent, &mut pos, &mut vel).join() {
             pos.0 += vel.0;
 
-            vel.0.x *= self.horisontal_drag_coeff;
-            vel.0.y *= self.vertical_drag_coeff;
-            vel.0.z *= self.horisontal_drag_coeff;
+            vel.0.x *= horisontal_drag_coeff;
+            vel.0.y *= vertical_drag_coeff;
+            vel.0.z *= horisontal_drag_coeff;
 
             if let Some(mov) = mov.get(ent) {
                 if mov.on_ground {
@@ -50,7 +42,7 @@ impl<'a> System<'a> for Physics {
                 }
             }
 
-            vel.0 += self.gravity;
+            vel.0 += gravity;
         }
     }
 }
\ No newline at end of file
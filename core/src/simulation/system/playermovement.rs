@@ -0,0 +1,44 @@
+use specs::prelude::*;
+
+use crate::simulation::{
+    component::{
+        Jump,
+        Movement,
+        PlayerInput,
+        Position,
+        Velocity,
+    },
+    movement::movement_direction,
+};
+
+/**
+ * Moves entities by their held input. Walking shifts the position directly,
+ * while jumping adds to the velocity and so is left to `Physics`.
+ */
+pub struct PlayerMovement;
+
+impl<'a> System<'a> for PlayerMovement {
+    type SystemData = (
+        ReadStorage<'a, PlayerInput>,
+        ReadStorage<'a, Movement>,
+        ReadStorage<'a, Jump>,
+        WriteStorage<'a, Position>,
+        WriteStorage<'a, Velocity>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (input, mov, jump, mut pos, mut vel) = data;
+
+        for (input, mov, jump, vel) in (&input, &mov, &jump, &mut vel).join() {
+            if input.jump && mov.on_ground {
+                vel.0.y -= jump.force;
+            }
+        }
+
+        for (input, mov, pos) in (&input, &mov, &mut pos).join() {
+            if let Some(movement) = movement_direction(input.keys, input.yaw) {
+                pos.0 += movement * mov.speed;
+            }
+        }
+    }
+}
\ No newline at end of file
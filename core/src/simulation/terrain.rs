@@ -0,0 +1,55 @@
+use failure::Error;
+
+use crate::util::config::{
+    Validate,
+    Validator,
+};
+
+use super::HeightMap;
+
+/**
+ * The world's ground, loaded from a heightmap image. The client and the
+ * server collide against the same terrain, so their settings must match.
+ */
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct TerrainConfig {
+    /// square greyscale BMP, brighter pixels higher
+    pub heightmap: String,
+    /// height difference between black and white pixels
+    pub scale: f32,
+    /// position of the heightmap's first corner
+    pub origin: [f64; 3],
+}
+
+impl Default for TerrainConfig {
+    fn default() -> TerrainConfig {
+        TerrainConfig {
+            heightmap: "assets/terrain.bmp".to_string(),
+            scale: 25.0,
+            origin: [-64.0, 5.0, -64.0],
+        }
+    }
+}
+
+impl TerrainConfig {
+    pub fn load(&self) -> Result<HeightMap, Error> {
+        HeightMap::from_bmp(&self.heightmap, self.scale)
+    }
+
+    pub fn origin(&self) -> nalgebra::Point3<f64> {
+        nalgebra::Point3::from(self.origin)
+    }
+}
+
+impl Validate for TerrainConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check("heightmap", !self.heightmap.is_empty(), "must not be empty");
+        validator.check("scale", self.scale.is_finite(), "must be a number");
+        validator.check(
+            "origin",
+            self.origin.iter().all(|value| value.is_finite()),
+            "must be three numbers"
+        );
+    }
+}
\ No newline at end of file
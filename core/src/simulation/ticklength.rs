@@ -0,0 +1,10 @@
+/// Simulated time covered by one tick
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TickLength(pub std::time::Duration);
+
+impl TickLength {
+    /// Converts a per-second rate into a per-tick one
+    pub fn scale_to(&self, value: f64) -> f64 {
+        value * self.0.as_secs_f64()
+    }
+}
\ No newline at end of file
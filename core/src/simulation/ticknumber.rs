@@ -0,0 +1,3 @@
+/// Number of the tick being simulated, counting from 1
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TickNumber(pub u64);
\ No newline at end of file
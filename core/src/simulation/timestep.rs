@@ -0,0 +1,73 @@
+use std::time::{
+    Duration,
+    Instant,
+};
+
+/**
+ * Schedule of a fixed-timestep loop. A late tick is followed by catch-up
+ * ticks back to back, but never more than `max_catch_up`; ticks beyond that
+ * are skipped and the schedule restarts from the present.
+ */
+pub struct FixedTimestep {
+    tick_length: Duration,
+    max_catch_up: u32,
+    next_tick: Instant,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_length: Duration, max_catch_up: u32, start: Instant) -> FixedTimestep {
+        FixedTimestep {
+            tick_length,
+            max_catch_up,
+            next_tick: start,
+        }
+    }
+
+    pub fn tick_length(&self) -> Duration {
+        self.tick_length
+    }
+
+    /// Scheduled time of the next tick
+    pub fn next_tick(&self) -> Instant {
+        self.next_tick
+    }
+
+    /// Moves on to the next tick, returning how many ticks were skipped
+    pub fn advance(&mut self, now: Instant) -> u64 {
+        self.next_tick += self.tick_length;
+
+        let behind = now.saturating_duration_since(self.next_tick).as_nanos() /
+            self.tick_length.as_nanos().max(1);
+        if behind <= self.max_catch_up as u128 {
+            return 0;
+        }
+
+        let skipped = behind as u64 - self.max_catch_up as u64;
+        self.next_tick += self.tick_length * skipped as u32;
+        skipped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catch_up_limit() {
+        let tick = Duration::from_millis(10);
+        let start = Instant::now();
+        let mut timestep = FixedTimestep::new(tick, 2, start);
+
+        assert_eq!(timestep.advance(start + Duration::from_millis(5)), 0);
+        assert_eq!(timestep.next_tick(), start + tick);
+
+        // finishing at 35ms leaves the tick due at 20ms one tick behind
+        assert_eq!(timestep.advance(start + Duration::from_millis(35)), 0);
+        assert_eq!(timestep.next_tick(), start + tick * 2);
+
+        // after a stall, ticks from 30ms to 130ms are due: the next one and
+        // two catch-up ticks run, the other 8 are skipped
+        assert_eq!(timestep.advance(start + Duration::from_millis(135)), 8);
+        assert_eq!(timestep.next_tick(), start + tick * 11);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,463 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+use std::marker::PhantomData;
+
+use failure_derive::Fail;
+use serde::{
+    de::DeserializeOwned,
+    Deserialize,
+    Serialize,
+};
+use specs::{
+    Builder,
+    Component,
+    Entity,
+    Join,
+    World,
+    WorldExt,
+};
+
+use super::TickNumber;
+
+/// Version written to every snapshot; older or newer files are refused
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Fail)]
+pub enum SnapshotError {
+    #[fail(display = "unable to access snapshot file {}: {}", path, cause)]
+    Io {
+        #[cause] cause: std::io::Error,
+        path: String,
+    },
+    #[fail(display = "malformed snapshot: {}", _0)]
+    Malformed(#[cause] toml::de::Error),
+    #[fail(display = "unable to write snapshot: {}", _0)]
+    Unserializable(#[cause] toml::ser::Error),
+    #[fail(display = "unsupported snapshot version {}", _0)]
+    UnsupportedVersion(u32),
+    #[fail(display = "unknown component {} in snapshot", _0)]
+    UnknownComponent(String),
+    #[fail(display = "invalid {} component: {}", key, message)]
+    InvalidComponent {
+        key: String,
+        message: String,
+    },
+    #[fail(display = "entity {} is saved more than once", _0)]
+    DuplicateEntity(u64),
+    #[fail(display = "reference to entity {}, which is not in the snapshot", _0)]
+    UnknownEntity(u64),
+}
+
+/**
+ * A component that can be written to a world snapshot. Components save a
+ * plain data form, rather than themselves, so that they can rewrite entity
+ * references into snapshot ids and back.
+ */
+pub trait SnapshotComponent: Component + Sized {
+    /// Name of the component in snapshot files
+    const KEY: &'static str;
+    type Data: Serialize + DeserializeOwned;
+
+    fn save(&self, entities: &EntityMap) -> Self::Data;
+    fn restore(data: Self::Data, entities: &EntityMap)
+        -> Result<Self, SnapshotError>;
+}
+
+/**
+ * Translation between live entities and their ids in a snapshot. Ids are
+ * only assigned to entities that have at least one snapshot component.
+ */
+#[derive(Default)]
+pub struct EntityMap {
+    ids: HashMap<Entity, u64>,
+    entities: HashMap<u64, Entity>,
+}
+
+impl EntityMap {
+    fn insert(&mut self, entity: Entity, id: u64) {
+        self.ids.insert(entity, id);
+        self.entities.insert(id, entity);
+    }
+
+    /// Snapshot id of a live entity, if it is saved
+    pub fn id(&self, entity: Entity) -> Option<u64> {
+        self.ids.get(&entity).cloned()
+    }
+
+    /// Live entity for a snapshot id
+    pub fn entity(&self, id: u64) -> Result<Entity, SnapshotError> {
+        self.entities.get(&id)
+            .cloned()
+            .ok_or(SnapshotError::UnknownEntity(id))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct SavedEntity {
+    pub id: u64,
+    #[serde(serialize_with = "serialize_components")]
+    pub components: toml::value::Table,
+}
+
+fn serialize_components<S>(components: &toml::value::Table, serializer: S)
+    -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    // toml::Value writes plain values ahead of tables, as TOML requires
+    toml::Value::Table(components.clone()).serialize(serializer)
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub struct WorldSnapshot {
+    pub version: u32,
+    pub tick: u64,
+    pub entities: Vec<SavedEntity>,
+}
+
+impl std::str::FromStr for WorldSnapshot {
+    type Err = SnapshotError;
+
+    fn from_str(src: &str) -> Result<WorldSnapshot, SnapshotError> {
+        // check the version first, since other fields may have changed
+        let value: toml::Value = toml::from_str(src)
+            .map_err(SnapshotError::Malformed)?;
+        let version = value.get("version")
+            .and_then(toml::Value::as_integer)
+            .unwrap_or(0);
+        if version != SNAPSHOT_VERSION as i64 {
+            return Err(SnapshotError::UnsupportedVersion(version as u32));
+        }
+
+        value.try_into().map_err(SnapshotError::Malformed)
+    }
+}
+
+impl WorldSnapshot {
+    pub fn to_string(&self) -> Result<String, SnapshotError> {
+        toml::to_string(self).map_err(SnapshotError::Unserializable)
+    }
+
+    pub fn read(path: &str) -> Result<WorldSnapshot, SnapshotError> {
+        let src = std::fs::read_to_string(path)
+            .map_err(|cause| SnapshotError::Io {
+                cause,
+                path: path.to_string(),
+            })?;
+
+        src.parse()
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), SnapshotError> {
+        std::fs::write(path, self.to_string()?)
+            .map_err(|cause| SnapshotError::Io {
+                cause,
+                path: path.to_string(),
+            })
+    }
+}
+
+trait Registration: Send + Sync {
+    fn key(&self) -> &'static str;
+    fn register(&self, world: &mut World);
+    fn has(&self, world: &World, entity: Entity) -> bool;
+    fn save(&self, world: &World, entity: Entity, entities: &EntityMap)
+        -> Result<Option<toml::Value>, SnapshotError>;
+    fn restore(
+        &self,
+        world: &World,
+        entity: Entity,
+        value: toml::Value,
+        entities: &EntityMap,
+    ) -> Result<(), SnapshotError>;
+}
+
+struct ComponentRegistration<C>(PhantomData<fn() -> C>);
+
+impl<C> Registration for ComponentRegistration<C>
+where
+    C: SnapshotComponent,
+    C::Storage: Default,
+{
+    fn key(&self) -> &'static str {
+        C::KEY
+    }
+
+    fn register(&self, world: &mut World) {
+        world.register::<C>();
+    }
+
+    fn has(&self, world: &World, entity: Entity) -> bool {
+        world.read_storage::<C>().contains(entity)
+    }
+
+    fn save(&self, world: &World, entity: Entity, entities: &EntityMap)
+        -> Result<Option<toml::Value>, SnapshotError>
+    {
+        match world.read_storage::<C>().get(entity) {
+            Some(component) => {
+                toml::Value::try_from(component.save(entities))
+                    .map(Some)
+                    .map_err(SnapshotError::Unserializable)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn restore(
+        &self,
+        world: &World,
+        entity: Entity,
+        value: toml::Value,
+        entities: &EntityMap,
+    ) -> Result<(), SnapshotError> {
+        let data: C::Data = value.try_into()
+            .map_err(|err| SnapshotError::InvalidComponent {
+                key: C::KEY.to_string(),
+                message: err.to_string(),
+            })?;
+
+        world.write_storage::<C>()
+            .insert(entity, C::restore(data, entities)?)
+            .map(|_| ())
+            .map_err(|err| SnapshotError::InvalidComponent {
+                key: C::KEY.to_string(),
+                message: err.to_string(),
+            })
+    }
+}
+
+/**
+ * Saves and restores the registered components of a world. Components
+ * that are not registered, such as connection state, are left out of
+ * snapshots, as are entities without any registered components.
+ */
+#[derive(Default)]
+pub struct WorldSnapshotter {
+    components: Vec<Box<dyn Registration>>,
+}
+
+impl WorldSnapshotter {
+    pub fn new() -> WorldSnapshotter {
+        Default::default()
+    }
+
+    pub fn with<C>(mut self) -> WorldSnapshotter
+    where
+        C: SnapshotComponent,
+        C::Storage: Default,
+    {
+        assert!(
+            self.components.iter().all(|component| component.key() != C::KEY),
+            "snapshot component {} registered twice",
+            C::KEY
+        );
+
+        self.components.push(Box::new(ComponentRegistration::<C>(PhantomData)));
+        self
+    }
+
+    pub fn save(&self, world: &World) -> Result<WorldSnapshot, SnapshotError> {
+        let mut entities = EntityMap::default();
+        let mut saved = Vec::new();
+        for entity in world.entities().join() {
+            if self.components.iter().any(|component| component.has(world, entity)) {
+                entities.insert(entity, saved.len() as u64);
+                saved.push(entity);
+            }
+        }
+
+        let mut snapshot = WorldSnapshot {
+            version: SNAPSHOT_VERSION,
+            tick: world.try_fetch::<TickNumber>().map_or(0, |tick| tick.0),
+            entities: Vec::with_capacity(saved.len()),
+        };
+
+        for (id, entity) in saved.into_iter().enumerate() {
+            let mut components = toml::value::Table::new();
+            for component in &self.components {
+                if let Some(value) = component.save(world, entity, &entities)? {
+                    components.insert(component.key().to_string(), value);
+                }
+            }
+
+            snapshot.entities.push(SavedEntity {
+                id: id as u64,
+                components,
+            });
+        }
+
+        Ok(snapshot)
+    }
+
+    /**
+     * Creates the snapshot's entities in `world`, which should be fresh.
+     * Either every entity is restored, or, on error, none are.
+     */
+    pub fn restore(&self, snapshot: &WorldSnapshot, world: &mut World)
+        -> Result<(), SnapshotError>
+    {
+        for component in &self.components {
+            component.register(world);
+        }
+
+        let mut ids = HashSet::new();
+        for saved in &snapshot.entities {
+            if !ids.insert(saved.id) {
+                return Err(SnapshotError::DuplicateEntity(saved.id));
+            }
+        }
+
+        let mut entities = EntityMap::default();
+        for saved in &snapshot.entities {
+            entities.insert(world.create_entity().build(), saved.id);
+        }
+
+        let restored = self.restore_components(snapshot, world, &entities);
+        if restored.is_err() {
+            let created: Vec<Entity> = entities.ids.keys().cloned().collect();
+            world.delete_entities(&created)
+                .unwrap_or_else(|err| log::error!("Failed to undo restore: {}", err));
+        }
+        world.maintain();
+        restored?;
+
+        world.insert(TickNumber(snapshot.tick));
+        Ok(())
+    }
+
+    fn restore_components(
+        &self,
+        snapshot: &WorldSnapshot,
+        world: &World,
+        entities: &EntityMap,
+    ) -> Result<(), SnapshotError> {
+        for saved in &snapshot.entities {
+            let entity = entities.entity(saved.id)?;
+            for (key, value) in &saved.components {
+                let component = self.components.iter()
+                    .find(|component| component.key() == key)
+                    .ok_or_else(|| SnapshotError::UnknownComponent(key.clone()))?;
+
+                component.restore(world, entity, value.clone(), entities)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use specs::VecStorage;
+
+    use super::*;
+
+    struct Position([f64; 3]);
+
+    impl Component for Position {
+        type Storage = VecStorage<Self>;
+    }
+
+    impl SnapshotComponent for Position {
+        const KEY: &'static str = "position";
+        type Data = [f64; 3];
+
+        fn save(&self, _: &EntityMap) -> [f64; 3] {
+            self.0
+        }
+
+        fn restore(data: [f64; 3], _: &EntityMap) -> Result<Position, SnapshotError> {
+            Ok(Position(data))
+        }
+    }
+
+    struct Following(Entity);
+
+    impl Component for Following {
+        type Storage = VecStorage<Self>;
+    }
+
+    impl SnapshotComponent for Following {
+        const KEY: &'static str = "following";
+        type Data = u64;
+
+        fn save(&self, entities: &EntityMap) -> u64 {
+            entities.id(self.0).unwrap()
+        }
+
+        fn restore(data: u64, entities: &EntityMap) -> Result<Following, SnapshotError> {
+            Ok(Following(entities.entity(data)?))
+        }
+    }
+
+    fn snapshotter() -> WorldSnapshotter {
+        WorldSnapshotter::new()
+            .with::<Position>()
+            .with::<Following>()
+    }
+
+    #[test]
+    fn test_round_trip_remaps_entities() {
+        let mut world = World::new();
+        world.register::<Position>();
+        world.register::<Following>();
+        world.insert(TickNumber(42));
+
+        // leave a gap in the entity indices, so restored entities differ
+        let unsaved = world.create_entity().build();
+        let leader = world.create_entity()
+            .with(Position([1.0, 2.0, 3.0]))
+            .build();
+        world.create_entity()
+            .with(Position([4.0, 5.0, 6.0]))
+            .with(Following(leader))
+            .build();
+        world.delete_entity(unsaved).unwrap();
+        world.maintain();
+
+        let text = snapshotter().save(&world).unwrap().to_string().unwrap();
+        let snapshot: WorldSnapshot = text.parse().unwrap();
+        assert_eq!(snapshot.tick, 42);
+        assert_eq!(snapshot.entities.len(), 2);
+
+        let mut restored = World::new();
+        snapshotter().restore(&snapshot, &mut restored).unwrap();
+        assert_eq!(restored.read_resource::<TickNumber>().0, 42);
+
+        let positions = restored.read_storage::<Position>();
+        let following = restored.read_storage::<Following>();
+        let (follower, leader) = (&positions, &following).join()
+            .map(|(pos, following)| (pos.0, positions.get(following.0).unwrap().0))
+            .next()
+            .unwrap();
+        assert_eq!(follower, [4.0, 5.0, 6.0]);
+        assert_eq!(leader, [1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn test_invalid_snapshots_are_refused() {
+        let snapshot = "version = 2\ntick = 0\nentities = []";
+        match snapshot.parse::<WorldSnapshot>() {
+            Err(SnapshotError::UnsupportedVersion(2)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+
+        let snapshot: WorldSnapshot = (
+            "version = 1\ntick = 0\n\
+            [[entities]]\nid = 0\n[entities.components]\nposition = [0.0, 0.0, 0.0]\n\
+            [[entities]]\nid = 1\n[entities.components]\nfollowing = 7"
+        ).parse().unwrap();
+
+        let mut world = World::new();
+        match snapshotter().restore(&snapshot, &mut world) {
+            Err(SnapshotError::UnknownEntity(7)) => (),
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(world.entities().join().count(), 0);
+    }
+}
\ No newline at end of file
@@ -1,5 +1,11 @@
+use std::collections::BTreeMap;
+use std::ffi::OsString;
 use std::fs::File;
-use std::io::Read;
+use std::io::{
+    Read,
+    Write,
+};
+use std::marker::PhantomData;
 
 use toml;
 use failure_derive::Fail;
@@ -10,15 +16,134 @@ pub enum ConfigurationError {
     InvalidArguments,
     #[fail(display = "malformed configuration: {}", _0)]
     MalformedData(#[cause] toml::de::Error),
+    #[fail(display = "malformed configuration file {}: {}", path, cause)]
+    MalformedFile {
+        #[cause] cause: toml::de::Error,
+        path: String,
+    },
     #[fail(display = "unable to access configuration file: {}", path)]
     IoError {
         #[cause] cause: std::io::Error,
         path: String,
     },
+    #[fail(display = "invalid value for {} from {}: {}", key, origin, message)]
+    InvalidOverride {
+        key: String,
+        origin: ConfigSource,
+        message: String,
+    },
+    #[fail(display = "environment variable {} is not valid unicode", name)]
+    NonUnicodeVariable {
+        name: String,
+    },
+    #[fail(display = "invalid configuration: {}", _0)]
+    InvalidValues(InvalidValues),
+}
+
+/// Prefix of the environment variables that override configuration values
+pub const ENV_PREFIX: &str = "ER_";
+/// Command-line flag that replaces the configuration file path
+pub const CONFIG_PATH_FLAG: &str = "--config";
+
+/// Where a configuration value was last set
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigSource {
+    Default,
+    File(String),
+    Environment(String),
+    CommandLine(String),
+}
+
+impl std::fmt::Display for ConfigSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ConfigSource::Default => write!(f, "defaults"),
+            ConfigSource::File(path) => write!(f, "file {}", path),
+            ConfigSource::Environment(name) => write!(f, "environment variable {}", name),
+            ConfigSource::CommandLine(arg) => write!(f, "command line {}", arg),
+        }
+    }
+}
+
+/// A configuration value rejected by validation
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidValue {
+    pub key: String,
+    pub message: String,
+    pub origin: Option<ConfigSource>,
+}
+
+impl std::fmt::Display for InvalidValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {}", self.key, self.message)?;
+        if let Some(ref origin) = self.origin {
+            write!(f, " (from {})", origin)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct InvalidValues(pub Vec<InvalidValue>);
+
+impl std::fmt::Display for InvalidValues {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (index, value) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", value)?;
+        }
+        Ok(())
+    }
+}
+
+/// Configuration that can check its own values
+pub trait Validate {
+    fn validate(&self, validator: &mut Validator);
+}
+
+/// Collects the problems found while validating a configuration
+#[derive(Default)]
+pub struct Validator {
+    /// dotted path of the table being validated
+    key: String,
+    errors: Vec<InvalidValue>,
+}
+
+impl Validator {
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    /// Rejects `field` with `message` unless `valid` holds
+    pub fn check(&mut self, field: &str, valid: bool, message: &str) {
+        if !valid {
+            self.errors.push(InvalidValue {
+                key: child_key(&self.key, field),
+                message: message.to_string(),
+                origin: None,
+            });
+        }
+    }
+
+    /// Validates the table in `field`
+    pub fn nested<V: Validate>(&mut self, field: &str, value: &V) {
+        let key = child_key(&self.key, field);
+        let parent = std::mem::replace(&mut self.key, key);
+        value.validate(self);
+        self.key = parent;
+    }
+
+    pub fn into_errors(self) -> Vec<InvalidValue> {
+        self.errors
+    }
 }
 
 pub struct Config<T> {
     pub data: T,
+    /// source of every value, keyed by its dotted path
+    sources: BTreeMap<String, ConfigSource>,
 }
 
 impl<T> Config<T>
@@ -31,7 +156,7 @@ where
         let config: T = toml::from_str(src)
             .map_err(|e| ConfigurationError::MalformedData(e))?;
 
-        Ok(Config { data: config })
+        Ok(Config { data: config, sources: BTreeMap::new() })
     }
 
     pub fn from_file(path: &String)
@@ -53,18 +178,644 @@ where
         Config::from_str(&buffer)
     }
 
+    /// Writes the defaults to a new file, refusing to replace an existing one
     pub fn write_default(path: &String)
         -> Result<Config<T>, ConfigurationError>
     {
         let config: T = T::default();
 
         let buffer = toml::to_string(&config).unwrap();
-        std::fs::write(path, &buffer[..])
+        std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+            .and_then(|mut file| file.write_all(buffer.as_bytes()))
             .map_err(|e| ConfigurationError::IoError {
-                cause:e ,
+                cause: e,
                 path: path.clone(),
             })?;
-        
-        Ok(Config { data: config })
+
+        Ok(Config { data: config, sources: BTreeMap::new() })
+    }
+
+    /// Where the value at the dotted `key` path was set
+    pub fn source(&self, key: &str) -> Option<&ConfigSource> {
+        self.sources.get(key)
+    }
+
+    /// Checks the values, naming where each rejected one was set
+    pub fn validate(&self) -> Result<(), ConfigurationError>
+    where
+        T: Validate,
+    {
+        let mut validator = Validator::new();
+        self.data.validate(&mut validator);
+
+        let mut errors = validator.into_errors();
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        for error in &mut errors {
+            error.origin = self.sources.get(&error.key).cloned();
+        }
+
+        Err(ConfigurationError::InvalidValues(InvalidValues(errors)))
+    }
+
+    /**
+     * Logs every value along with its source, overrides at info level.
+     * Keys the configuration doesn't have are warned about.
+     */
+    pub fn log_sources(&self) {
+        let values = match toml::Value::try_from(&self.data) {
+            Ok(values) => values,
+            Err(err) => {
+                log::error!("Failed to list configuration values: {}", err);
+                return;
+            },
+        };
+
+        for (key, source) in &self.sources {
+            let value = match lookup(&values, key) {
+                Some(value) => value.to_string(),
+                None => {
+                    log::warn!("Ignoring unknown configuration key {} (from {})", key, source);
+                    continue;
+                },
+            };
+
+            match source {
+                ConfigSource::Default | ConfigSource::File(_) => {
+                    log::debug!("Configuration {} = {} (from {})", key, value, source);
+                },
+                _ => log::info!("Configuration {} = {} (from {})", key, value, source),
+            }
+        }
+    }
+}
+
+/// Where a configuration is loaded from, so that it can be loaded again
+#[derive(Clone, Debug)]
+pub struct ConfigLoader {
+    path: String,
+    /// command-line arguments, without the program
+    args: Vec<String>,
+}
+
+impl ConfigLoader {
+    /// A `--config` flag among `args` takes precedence over `default_path`
+    pub fn new(default_path: Option<String>, args: Vec<String>) -> Option<ConfigLoader> {
+        let path = config_path(&args).or(default_path)?;
+
+        Some(ConfigLoader { path, args })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Layers the file, environment and arguments, generating a missing file
+    pub fn load<T>(&self) -> Result<Config<T>, ConfigurationError>
+    where
+        T: serde::ser::Serialize + serde::de::DeserializeOwned + Default + Validate,
+    {
+        ConfigBuilder::new()
+            .with_file_or_default(&self.path)?
+            .with_env(env_vars(std::env::vars_os())?)?
+            .with_args(&self.args)?
+            .build()
+    }
+
+    /// Like `load`, but fails instead of generating a missing file
+    pub fn reload<T>(&self) -> Result<Config<T>, ConfigurationError>
+    where
+        T: serde::ser::Serialize + serde::de::DeserializeOwned + Default + Validate,
+    {
+        ConfigBuilder::new()
+            .with_file(&self.path)?
+            .with_env(env_vars(std::env::vars_os())?)?
+            .with_args(&self.args)?
+            .build()
+    }
+}
+
+/**
+ * The `ER_*` variables among `vars`. Other variables are skipped whatever
+ * they hold, while an `ER_*` variable that is not valid unicode is an error.
+ */
+fn env_vars<I>(vars: I) -> Result<Vec<(String, String)>, ConfigurationError>
+where
+    I: IntoIterator<Item = (OsString, OsString)>,
+{
+    let mut prefixed = Vec::new();
+    for (name, value) in vars {
+        if !name.to_string_lossy().starts_with(ENV_PREFIX) {
+            continue;
+        }
+
+        match (name.into_string(), value.into_string()) {
+            (Ok(name), Ok(value)) => prefixed.push((name, value)),
+            (Ok(name), Err(_)) => return Err(ConfigurationError::NonUnicodeVariable { name }),
+            (Err(name), _) => return Err(ConfigurationError::NonUnicodeVariable {
+                name: name.to_string_lossy().into_owned(),
+            }),
+        }
+    }
+
+    Ok(prefixed)
+}
+
+/// Path given with `--config=<path>`, if any
+pub fn config_path<S: AsRef<str>>(args: &[S]) -> Option<String> {
+    args.iter()
+        .filter_map(|arg| flag_value(arg.as_ref()))
+        .filter(|(key, _)| *key == &CONFIG_PATH_FLAG[2..])
+        .map(|(_, value)| value.to_string())
+        .last()
+}
+
+/// Splits `--key=value` into its key and value
+fn flag_value(arg: &str) -> Option<(&str, &str)> {
+    if !arg.starts_with("--") {
+        return None;
+    }
+
+    let mut parts = arg[2..].splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(key), Some(value)) => Some((key, value)),
+        _ => None,
+    }
+}
+
+/**
+ * Builds a configuration in layers. Each layer overrides the values set
+ * by earlier ones: the built-in defaults come first, then usually the
+ * configuration file, `ER_*` environment variables and `--key.path=value`
+ * command-line flags.
+ */
+pub struct ConfigBuilder<T> {
+    values: toml::Value,
+    sources: BTreeMap<String, ConfigSource>,
+    config: PhantomData<T>,
+}
+
+impl<T> ConfigBuilder<T>
+where
+    T: serde::ser::Serialize + serde::de::DeserializeOwned + std::default::Default,
+{
+    pub fn new() -> ConfigBuilder<T> {
+        let values = toml::Value::try_from(T::default()).unwrap();
+
+        let mut sources = BTreeMap::new();
+        record_sources(&mut sources, "", &values, &ConfigSource::Default);
+
+        ConfigBuilder {
+            values,
+            sources,
+            config: PhantomData,
+        }
+    }
+
+    pub fn with_file(self, path: &str)
+        -> Result<ConfigBuilder<T>, ConfigurationError>
+    {
+        let mut buffer = String::new();
+
+        File::open(path)
+            .and_then(|mut file| file.read_to_string(&mut buffer))
+            .map_err(|e| ConfigurationError::IoError {
+                cause: e,
+                path: path.to_string(),
+            })?;
+
+        self.with_str(&buffer, ConfigSource::File(path.to_string()))
+            .map_err(|e| match e {
+                ConfigurationError::MalformedData(cause) => {
+                    ConfigurationError::MalformedFile { cause, path: path.to_string() }
+                },
+                e => e,
+            })
+    }
+
+    /**
+     * Like `with_file`, but first writes out the defaults if there is no
+     * file at `path`. An existing file is never replaced, even if it cannot
+     * be read.
+     */
+    pub fn with_file_or_default(self, path: &str)
+        -> Result<ConfigBuilder<T>, ConfigurationError>
+    {
+        if !std::path::Path::new(path).exists() {
+            Config::<T>::write_default(&path.to_string())?;
+        }
+
+        self.with_file(path)
+    }
+
+    pub fn with_str(mut self, src: &str, source: ConfigSource)
+        -> Result<ConfigBuilder<T>, ConfigurationError>
+    {
+        let values: toml::Value = toml::from_str(src)
+            .map_err(ConfigurationError::MalformedData)?;
+
+        merge(&mut self.values, values, "", &mut self.sources, &source);
+
+        Ok(self)
+    }
+
+    /**
+     * Applies the `ER_*` variables among `vars`. Nested keys are separated
+     * by double underscores, so `ER_SERVER__BIND_ADDRESS` sets
+     * `server.bind-address`.
+     */
+    pub fn with_env<I>(mut self, vars: I)
+        -> Result<ConfigBuilder<T>, ConfigurationError>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        for (name, value) in vars {
+            if !name.starts_with(ENV_PREFIX) {
+                continue;
+            }
+
+            let key: Vec<String> = name[ENV_PREFIX.len()..]
+                .split("__")
+                .map(|part| part.to_lowercase().replace('_', "-"))
+                .collect();
+
+            let source = ConfigSource::Environment(name.clone());
+            self.set(&key.join("."), &value, source)?;
+        }
+
+        Ok(self)
+    }
+
+    /// Applies `--key.path=value` flags, other than `--config`
+    pub fn with_args<S: AsRef<str>>(mut self, args: &[S])
+        -> Result<ConfigBuilder<T>, ConfigurationError>
+    {
+        for arg in args {
+            let arg = arg.as_ref();
+            let (key, value) = flag_value(arg)
+                .ok_or(ConfigurationError::InvalidArguments)?;
+
+            if key == &CONFIG_PATH_FLAG[2..] {
+                continue;
+            }
+
+            self.set(key, value, ConfigSource::CommandLine(arg.to_string()))?;
+        }
+
+        Ok(self)
+    }
+
+    /**
+     * Deserializes and validates the layered values. Keys the configuration
+     * doesn't have are refused when they come from the environment or the
+     * command line; files may predate a key being dropped, so their unknown
+     * keys are only warned about by `Config::log_sources`.
+     */
+    pub fn build(self) -> Result<Config<T>, ConfigurationError>
+    where
+        T: Validate,
+    {
+        let data: T = self.values.try_into()
+            .map_err(ConfigurationError::MalformedData)?;
+
+        // values serde ignored don't survive a round trip
+        if let Ok(known) = toml::Value::try_from(&data) {
+            for (key, source) in &self.sources {
+                if lookup(&known, key).is_some() {
+                    continue;
+                }
+
+                if let ConfigSource::Environment(_) | ConfigSource::CommandLine(_) = source {
+                    return Err(ConfigurationError::InvalidOverride {
+                        key: key.clone(),
+                        origin: source.clone(),
+                        message: "unknown key".to_string(),
+                    });
+                }
+            }
+        }
+
+        let config = Config { data, sources: self.sources };
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    /**
+     * Sets one value from its text. Values replacing strings stay strings;
+     * anything else is read as a TOML value, falling back to a string.
+     */
+    fn set(&mut self, key: &str, text: &str, source: ConfigSource)
+        -> Result<(), ConfigurationError>
+    {
+        let invalid = |message: &str| ConfigurationError::InvalidOverride {
+            key: key.to_string(),
+            origin: source.clone(),
+            message: message.to_string(),
+        };
+
+        let path: Vec<&str> = key.split('.').collect();
+        if path.iter().any(|name| name.is_empty()) {
+            return Err(invalid("empty key"));
+        }
+        let (name, parents) = path.split_last().unwrap();
+
+        let mut table = &mut self.values;
+        for parent in parents {
+            table = table.as_table_mut()
+                .ok_or_else(|| invalid("not a table"))?
+                .entry(parent.to_string())
+                .or_insert_with(|| toml::Value::Table(toml::value::Table::new()));
+        }
+        let table = table.as_table_mut()
+            .ok_or_else(|| invalid("not a table"))?;
+
+        let value = match table.get(*name) {
+            Some(toml::Value::String(_)) => toml::Value::String(text.to_string()),
+            _ => parse_value(text),
+        };
+
+        let prefix = format!("{}.", key);
+        self.sources.retain(|path, _| path != key && !path.starts_with(&prefix));
+        record_sources(&mut self.sources, key, &value, &source);
+
+        table.insert(name.to_string(), value);
+
+        Ok(())
+    }
+}
+
+impl<T> Default for ConfigBuilder<T>
+where
+    T: serde::ser::Serialize + serde::de::DeserializeOwned + std::default::Default,
+{
+    fn default() -> ConfigBuilder<T> {
+        ConfigBuilder::new()
+    }
+}
+
+fn parse_value(text: &str) -> toml::Value {
+    toml::from_str::<toml::value::Table>(&format!("value = {}", text))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(text.to_string()))
+}
+
+/// Value at the dotted `key` path
+fn lookup<'a>(values: &'a toml::Value, key: &str) -> Option<&'a toml::Value> {
+    key.split('.').try_fold(values, |value, name| value.get(name))
+}
+
+fn child_key(key: &str, name: &str) -> String {
+    match key {
+        "" => name.to_string(),
+        key => format!("{}.{}", key, name),
+    }
+}
+
+/// Records `source` for every value in `value`, tables being walked into
+fn record_sources(
+    sources: &mut BTreeMap<String, ConfigSource>,
+    key: &str,
+    value: &toml::Value,
+    source: &ConfigSource,
+) {
+    match value {
+        toml::Value::Table(table) => {
+            for (name, value) in table {
+                record_sources(sources, &child_key(key, name), value, source);
+            }
+        },
+        _ => {
+            sources.insert(key.to_string(), source.clone());
+        },
+    }
+}
+
+/// Overrides the values in `base` with those in `layer`, merging tables
+fn merge(
+    base: &mut toml::Value,
+    layer: toml::Value,
+    key: &str,
+    sources: &mut BTreeMap<String, ConfigSource>,
+    source: &ConfigSource,
+) {
+    match (base, layer) {
+        (toml::Value::Table(base), toml::Value::Table(layer)) => {
+            for (name, value) in layer {
+                let key = child_key(key, &name);
+                match base.get_mut(&name) {
+                    Some(existing) => merge(existing, value, &key, sources, source),
+                    None => {
+                        record_sources(sources, &key, &value, source);
+                        base.insert(name, value);
+                    },
+                }
+            }
+        },
+        (base, layer) => {
+            let prefix = format!("{}.", key);
+            sources.retain(|path, _| path != key && !path.starts_with(&prefix));
+            record_sources(sources, key, &layer, source);
+            *base = layer;
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(default, rename_all = "kebab-case")]
+    struct TestConfig {
+        name: String,
+        server: TestServerConfig,
+    }
+
+    impl Default for TestConfig {
+        fn default() -> TestConfig {
+            TestConfig {
+                name: "default".to_string(),
+                server: TestServerConfig::default(),
+            }
+        }
+    }
+
+    impl Validate for TestConfig {
+        fn validate(&self, validator: &mut Validator) {
+            validator.check("name", !self.name.is_empty(), "must not be empty");
+            validator.nested("server", &self.server);
+        }
+    }
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(default, rename_all = "kebab-case")]
+    struct TestServerConfig {
+        tick_rate: u64,
+        bind_address: String,
+        verbose: bool,
+    }
+
+    impl Default for TestServerConfig {
+        fn default() -> TestServerConfig {
+            TestServerConfig {
+                tick_rate: 30,
+                bind_address: "127.0.0.1:6142".to_string(),
+                verbose: false,
+            }
+        }
+    }
+
+    impl Validate for TestServerConfig {
+        fn validate(&self, validator: &mut Validator) {
+            validator.check("tick-rate", self.tick_rate > 0, "must be greater than 0");
+        }
+    }
+
+    #[test]
+    fn test_layered_config() {
+        let file = "name = \"from file\"\n[server]\ntick-rate = 60\nverbose = true\n";
+        let vars = vec![
+            ("ER_SERVER__TICK_RATE".to_string(), "20".to_string()),
+            ("ER_SERVER__BIND_ADDRESS".to_string(), "0.0.0.0:7000".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ];
+        let args = ["--config=other.toml", "--server.bind-address=0.0.0.0:7001", "--name=10"];
+
+        let config = ConfigBuilder::<TestConfig>::new()
+            .with_str(file, ConfigSource::File("test.toml".to_string())).unwrap()
+            .with_env(vars).unwrap()
+            .with_args(&args).unwrap()
+            .build().unwrap();
+
+        // strings stay strings even when they look like numbers
+        assert_eq!(config.data.name, "10");
+        assert_eq!(config.data.server.tick_rate, 20);
+        assert_eq!(config.data.server.bind_address, "0.0.0.0:7001");
+        assert!(config.data.server.verbose);
+
+        assert_eq!(
+            config.source("name"),
+            Some(&ConfigSource::CommandLine("--name=10".to_string()))
+        );
+        assert_eq!(
+            config.source("server.tick-rate"),
+            Some(&ConfigSource::Environment("ER_SERVER__TICK_RATE".to_string()))
+        );
+        assert_eq!(
+            config.source("server.verbose"),
+            Some(&ConfigSource::File("test.toml".to_string()))
+        );
+        assert_eq!(config_path(&args), Some("other.toml".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_overrides() {
+        let result = ConfigBuilder::<TestConfig>::new()
+            .with_args(&["--server.tick-rate=fast"]).unwrap()
+            .build();
+        assert!(matches!(result, Err(ConfigurationError::MalformedData(_))));
+
+        let result = ConfigBuilder::<TestConfig>::new()
+            .with_args(&["--name.first=value"]);
+        assert!(matches!(
+            result,
+            Err(ConfigurationError::InvalidOverride { ref key, .. }) if key == "name.first"
+        ));
+
+        let result = ConfigBuilder::<TestConfig>::new()
+            .with_args(&["tick-rate"]);
+        assert!(matches!(result, Err(ConfigurationError::InvalidArguments)));
+
+        // misspelled keys are refused, except in files
+        let result = ConfigBuilder::<TestConfig>::new()
+            .with_args(&["--server.tick-rat=20"]).unwrap()
+            .build();
+        assert!(matches!(
+            result,
+            Err(ConfigurationError::InvalidOverride { ref key, .. }) if key == "server.tick-rat"
+        ));
+
+        let vars = vec![("ER_SERVER__TICKRATE".to_string(), "20".to_string())];
+        let result = ConfigBuilder::<TestConfig>::new()
+            .with_env(vars).unwrap()
+            .build();
+        assert!(matches!(
+            result,
+            Err(ConfigurationError::InvalidOverride { ref key, .. }) if key == "server.tickrate"
+        ));
+
+        let result = ConfigBuilder::<TestConfig>::new()
+            .with_str("[server]\ntick-rat = 20", ConfigSource::File("test.toml".to_string()))
+            .unwrap()
+            .build();
+        assert_eq!(result.unwrap().data.server.tick_rate, 30);
+    }
+
+    #[test]
+    fn test_validation() {
+        let result = ConfigBuilder::<TestConfig>::new()
+            .with_str("[server]\ntick-rate = 0", ConfigSource::File("test.toml".to_string()))
+            .unwrap()
+            .with_args(&["--name="])
+            .unwrap()
+            .build();
+
+        match result {
+            Err(ConfigurationError::InvalidValues(errors)) => assert_eq!(
+                errors.to_string(),
+                "name must not be empty (from command line --name=); \
+                    server.tick-rate must be greater than 0 (from file test.toml)"
+            ),
+            _ => panic!("expected invalid values"),
+        }
+    }
+
+    #[test]
+    fn test_existing_file_kept() {
+        let path = std::env::temp_dir()
+            .join(format!("er-config-test-{}.toml", std::process::id()));
+        let path = path.to_str().unwrap().to_string();
+
+        // a missing file is generated, an existing one is left alone
+        ConfigBuilder::<TestConfig>::new().with_file_or_default(&path).unwrap();
+        std::fs::write(&path, "name = ").unwrap();
+
+        let result = ConfigBuilder::<TestConfig>::new().with_file_or_default(&path);
+        assert!(matches!(result, Err(ConfigurationError::MalformedFile { .. })));
+        assert!(Config::<TestConfig>::write_default(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "name = ");
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_env_vars() {
+        use std::os::unix::ffi::OsStringExt;
+
+        let invalid = || OsString::from_vec(vec![0x66, 0x6f, 0x80]);
+
+        let vars = vec![
+            (OsString::from("ER_NAME"), OsString::from("test")),
+            (OsString::from("HOME"), invalid()),
+            (invalid(), OsString::from("test")),
+        ];
+        assert_eq!(
+            env_vars(vars).unwrap(),
+            vec![("ER_NAME".to_string(), "test".to_string())]
+        );
+
+        let vars = vec![(OsString::from("ER_NAME"), invalid())];
+        assert!(matches!(
+            env_vars(vars),
+            Err(ConfigurationError::NonUnicodeVariable { ref name }) if name == "ER_NAME"
+        ));
     }
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+use std::time::SystemTime;
+
+use super::config::{
+    Config,
+    ConfigLoader,
+    ConfigurationError,
+    Validate,
+};
+
+/**
+ * Reloads a configuration whenever its file is modified. Each change is
+ * loaded once, so a broken edit is reported once rather than on every poll.
+ */
+pub struct ConfigWatcher {
+    loader: ConfigLoader,
+    modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(loader: ConfigLoader) -> ConfigWatcher {
+        let modified = modified(loader.path());
+
+        ConfigWatcher { loader, modified }
+    }
+
+    /// The reloaded configuration if the file changed since the last poll
+    pub fn poll<T>(&mut self) -> Option<Result<Config<T>, ConfigurationError>>
+    where
+        T: serde::ser::Serialize + serde::de::DeserializeOwned + Default + Validate,
+    {
+        let modified = modified(self.loader.path());
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+
+        Some(self.loader.reload())
+    }
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::util::config::Validator;
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(default, rename_all = "kebab-case")]
+    struct TestConfig {
+        tick_rate: u64,
+    }
+
+    impl Default for TestConfig {
+        fn default() -> TestConfig {
+            TestConfig { tick_rate: 30 }
+        }
+    }
+
+    impl Validate for TestConfig {
+        fn validate(&self, validator: &mut Validator) {
+            validator.check("tick-rate", self.tick_rate > 0, "must be greater than 0");
+        }
+    }
+
+    #[test]
+    fn test_reload_on_change() {
+        let path = std::env::temp_dir()
+            .join(format!("er-watcher-test-{}.toml", std::process::id()));
+        std::fs::write(&path, "tick-rate = 60").unwrap();
+
+        let loader = ConfigLoader::new(
+            Some(path.to_str().unwrap().to_string()),
+            Vec::new()
+        ).unwrap();
+        let mut watcher = ConfigWatcher::new(loader);
+        assert!(watcher.poll::<TestConfig>().is_none());
+
+        // file times can be coarser than the test, so they are set explicitly
+        let touch = |content: &str, seconds: u64| {
+            std::fs::write(&path, content).unwrap();
+            std::fs::File::open(&path).unwrap()
+                .set_modified(SystemTime::now() + Duration::from_secs(seconds))
+                .unwrap();
+        };
+
+        touch("tick-rate = 20", 1);
+        let config = watcher.poll::<TestConfig>().unwrap().unwrap();
+        assert_eq!(config.data.tick_rate, 20);
+        assert!(watcher.poll::<TestConfig>().is_none());
+
+        touch("tick-rate = 0", 2);
+        assert!(watcher.poll::<TestConfig>().unwrap().is_err());
+        assert!(watcher.poll::<TestConfig>().is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
\ No newline at end of file
@@ -1,17 +1,29 @@
+use std::fmt::Write;
+
 use failure::Error;
+use log::kv::{
+    self,
+    VisitSource,
+};
+
+use super::rotatingfile::RotatingFile;
 
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct LoggingConfig {
     pub level: LogLevel,
+    pub format: LogFormat,
     pub file: Option<String>,
+    pub rotation: RotationConfig,
 }
 
 impl Default for LoggingConfig {
     fn default() -> LoggingConfig {
         LoggingConfig {
             level: LogLevel::Info,
+            format: LogFormat::Text,
             file: None,
+            rotation: RotationConfig::default(),
         }
     }
 }
@@ -26,6 +38,36 @@ pub enum LogLevel {
     Trace,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// `[12:00:00][target][INFO] message key=value`
+    Text,
+    /// one JSON object per line
+    Json,
+}
+
+#[derive(Clone, Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct RotationConfig {
+    /// size the log file may reach before a new one is started, 0 for no limit
+    pub max_size_kb: u64,
+    /// age at which a new log file is started, 0 for no limit
+    pub max_age_hours: u64,
+    /// number of previous log files kept next to the current one
+    pub keep: usize,
+}
+
+impl Default for RotationConfig {
+    fn default() -> RotationConfig {
+        RotationConfig {
+            max_size_kb: 0,
+            max_age_hours: 0,
+            keep: 5,
+        }
+    }
+}
+
 pub fn configure(config: &LoggingConfig, component: &'static str) -> Result<(), Error> {
     let level = match config.level {
         LogLevel::Error => log::LevelFilter::Error,
@@ -35,28 +77,158 @@ pub fn configure(config: &LoggingConfig, component: &'static str) -> Result<(),
         LogLevel::Trace => log::LevelFilter::Trace,
     };
 
+    let format = config.format;
     let mut logging = fern::Dispatch::new()
-        .format(|out, message, record| {
-            out.finish(format_args!(
-                "{}[{}][{}] {}",
+        .format(move |out, message, record| match format {
+            LogFormat::Text => out.finish(format_args!(
+                "{}[{}][{}] {}{}",
                 chrono::Local::now().format("[%H:%M:%S]"),
                 record.target(),
                 record.level(),
-                message
-            ))
+                message,
+                text_fields(record)
+            )),
+            LogFormat::Json => out.finish(format_args!(
+                "{}",
+                json_record(
+                    &chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, true),
+                    message,
+                    record
+                )
+            )),
         })
         .level(log::LevelFilter::Warn)
         .level_for("eternalreckoning_core", level)
         .level_for(component, level)
         .chain(std::io::stdout());
-    
+
     if let Some(ref path) = config.file {
-        logging = logging.chain(
-            fern::log_file(std::path::Path::new(path))?
+        let file: Box<dyn std::io::Write + Send> = Box::new(
+            RotatingFile::open(path, &config.rotation)?
         );
+        logging = logging.chain(file);
     }
 
     logging.apply()?;
 
     Ok(())
+}
+
+/// The record's key-value pairs as ` key=value`
+fn text_fields(record: &log::Record) -> String {
+    struct Visitor(String);
+
+    impl<'kvs> VisitSource<'kvs> for Visitor {
+        fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+            write!(self.0, " {}={}", key, value)?;
+            Ok(())
+        }
+    }
+
+    let mut visitor = Visitor(String::new());
+    let _ = record.key_values().visit(&mut visitor);
+    visitor.0
+}
+
+/**
+ * A record as a single line JSON object, with its key-value pairs under
+ * `fields`. Booleans and numbers are kept as such, any other value is
+ * written as a string.
+ */
+fn json_record(timestamp: &str, message: &std::fmt::Arguments, record: &log::Record) -> String {
+    struct Visitor(String);
+
+    impl<'kvs> VisitSource<'kvs> for Visitor {
+        fn visit_pair(&mut self, key: kv::Key<'kvs>, value: kv::Value<'kvs>) -> Result<(), kv::Error> {
+            if !self.0.ends_with('{') {
+                self.0.push(',');
+            }
+            push_json_string(&mut self.0, key.as_str());
+            self.0.push(':');
+
+            if let Some(value) = value.to_bool() {
+                write!(self.0, "{}", value)?;
+            } else if let Some(value) = value.to_i64() {
+                write!(self.0, "{}", value)?;
+            } else if let Some(value) = value.to_u64() {
+                write!(self.0, "{}", value)?;
+            } else if let Some(value) = value.to_f64().filter(|value| value.is_finite()) {
+                write!(self.0, "{}", value)?;
+            } else {
+                push_json_string(&mut self.0, &value.to_string());
+            }
+
+            Ok(())
+        }
+    }
+
+    let mut json = String::from("{\"timestamp\":");
+    push_json_string(&mut json, timestamp);
+    json.push_str(",\"level\":");
+    push_json_string(&mut json, record.level().as_str());
+    json.push_str(",\"target\":");
+    push_json_string(&mut json, record.target());
+    json.push_str(",\"message\":");
+    push_json_string(&mut json, &message.to_string());
+    json.push_str(",\"fields\":{");
+
+    let mut visitor = Visitor(json);
+    let _ = record.key_values().visit(&mut visitor);
+    let mut json = visitor.0;
+    json.push_str("}}");
+
+    json
+}
+
+fn push_json_string(json: &mut String, value: &str) {
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            },
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_record() {
+        let fields: [(&str, kv::Value); 4] = [
+            ("player", kv::Value::from("Bob \"the\" builder")),
+            ("tick", kv::Value::from(42u64)),
+            ("offset", kv::Value::from(-1.5f64)),
+            ("admitted", kv::Value::from(true)),
+        ];
+        let json = json_record(
+            "2019-01-01T00:00:00.000Z",
+            &format_args!("Client connected:\n\ttest\u{1}"),
+            &log::Record::builder()
+                .level(log::Level::Info)
+                .target("eternalreckoning_server")
+                .key_values(&fields)
+                .build()
+        );
+
+        assert_eq!(
+            json,
+            "{\"timestamp\":\"2019-01-01T00:00:00.000Z\",\"level\":\"INFO\",\
+            \"target\":\"eternalreckoning_server\",\
+            \"message\":\"Client connected:\\n\\ttest\\u0001\",\
+            \"fields\":{\"player\":\"Bob \\\"the\\\" builder\",\"tick\":42,\
+            \"offset\":-1.5,\"admitted\":true}}"
+        );
+        assert_eq!(text_fields(&log::Record::builder().key_values(&fields).build()),
+            " player=Bob \"the\" builder tick=42 offset=-1.5 admitted=true");
+    }
 }
\ No newline at end of file
@@ -1,2 +1,5 @@
 pub mod config;
-pub mod logging;
\ No newline at end of file
+pub mod configwatcher;
+pub mod logging;
+pub mod rotatingfile;
+pub mod shutdown;
\ No newline at end of file
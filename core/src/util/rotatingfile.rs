@@ -0,0 +1,238 @@
+use std::fs::{
+    self,
+    File,
+    OpenOptions,
+};
+use std::io::{
+    self,
+    Write,
+};
+use std::path::{
+    Path,
+    PathBuf,
+};
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
+use super::logging::RotationConfig;
+
+/**
+ * Log file that moves itself aside once it grows too large or too old,
+ * keeping a limited number of previous files as `<path>.1` (the newest)
+ * through `<path>.<keep>`.
+ *
+ * Writes are held until the next flush, which fern does after every
+ * record, so a record never ends up split across two files.
+ */
+pub struct RotatingFile {
+    path: PathBuf,
+    max_size: Option<u64>,
+    max_age: Option<Duration>,
+    keep: usize,
+    file: Option<File>,
+    size: u64,
+    opened: SystemTime,
+    pending: Vec<u8>,
+}
+
+impl RotatingFile {
+    pub fn open<P: Into<PathBuf>>(path: P, config: &RotationConfig) -> io::Result<RotatingFile> {
+        let mut file = RotatingFile {
+            path: path.into(),
+            max_size: match config.max_size_kb {
+                0 => None,
+                kb => Some(kb * 1024),
+            },
+            max_age: match config.max_age_hours {
+                0 => None,
+                hours => Some(Duration::from_secs(hours * 60 * 60)),
+            },
+            keep: config.keep,
+            file: None,
+            size: 0,
+            opened: SystemTime::now(),
+            pending: Vec::new(),
+        };
+        file.file()?;
+
+        Ok(file)
+    }
+
+    /// The open file, reopening it if a previous rotation failed halfway
+    fn file(&mut self) -> io::Result<&mut File> {
+        if self.file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)?;
+            let metadata = file.metadata()?;
+
+            self.size = metadata.len();
+            self.opened = metadata.created().unwrap_or_else(|_| SystemTime::now());
+            self.file = Some(file);
+        }
+
+        Ok(self.file.as_mut().unwrap())
+    }
+
+    fn should_rotate(&self, incoming: u64) -> bool {
+        if self.file.is_none() || self.size == 0 {
+            return false;
+        }
+
+        let too_large = self.max_size
+            .is_some_and(|max_size| self.size + incoming > max_size);
+        let too_old = self.max_age
+            .is_some_and(|max_age| self.opened.elapsed().is_ok_and(|age| age >= max_age));
+
+        too_large || too_old
+    }
+
+    fn backup_path(&self, index: usize) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+        path.into()
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        // closed first, as not every platform can rename an open file
+        self.file = None;
+
+        if self.keep == 0 {
+            return fs::remove_file(&self.path);
+        }
+
+        remove_if_exists(&self.backup_path(self.keep))?;
+        for index in (1..self.keep).rev() {
+            let from = self.backup_path(index);
+            if from.exists() {
+                fs::rename(from, self.backup_path(index + 1))?;
+            }
+        }
+
+        fs::rename(&self.path, self.backup_path(1))
+    }
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        // records stay pending until written, so a failed rotation loses none
+        if self.should_rotate(self.pending.len() as u64) {
+            self.rotate()?;
+        }
+
+        self.file()?;
+        let file = self.file.as_mut().unwrap();
+        file.write_all(&self.pending)?;
+        file.flush()?;
+        self.size += self.pending.len() as u64;
+        self.pending.clear();
+
+        Ok(())
+    }
+}
+
+impl Drop for RotatingFile {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(path: &Path) -> String {
+        fs::read_to_string(path).unwrap_or_default()
+    }
+
+    fn backup_path(path: &Path, index: usize) -> PathBuf {
+        PathBuf::from(format!("{}.{}", path.display(), index))
+    }
+
+    #[test]
+    fn test_size_rotation() {
+        let dir = std::env::temp_dir()
+            .join(format!("er-rotatingfile-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.log");
+
+        let config = RotationConfig {
+            max_size_kb: 1,
+            max_age_hours: 0,
+            keep: 2,
+        };
+        let mut file = RotatingFile::open(&path, &config).unwrap();
+
+        // 600 bytes per record, so each file only fits one
+        let record = |index: usize| format!("{:0>599}\n", index);
+        for index in 0..4 {
+            write!(file, "{}", &record(index)[..300]).unwrap();
+            write!(file, "{}", &record(index)[300..]).unwrap();
+            file.flush().unwrap();
+        }
+        drop(file);
+
+        assert_eq!(read(&path), record(3));
+        assert_eq!(read(&backup_path(&path, 1)), record(2));
+        assert_eq!(read(&backup_path(&path, 2)), record(1));
+        assert!(!backup_path(&path, 3).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_age_rotation() {
+        let dir = std::env::temp_dir()
+            .join(format!("er-rotatingfile-age-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.log");
+
+        let config = RotationConfig {
+            max_size_kb: 0,
+            max_age_hours: 1,
+            keep: 1,
+        };
+        let mut file = RotatingFile::open(&path, &config).unwrap();
+        let two_hours_ago = || SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+
+        writeln!(file, "first").unwrap();
+        file.flush().unwrap();
+        writeln!(file, "second").unwrap();
+        file.flush().unwrap();
+        assert_eq!(read(&path), "first\nsecond\n");
+
+        file.opened = two_hours_ago();
+        writeln!(file, "third").unwrap();
+        file.flush().unwrap();
+        assert_eq!(read(&path), "third\n");
+        assert_eq!(read(&backup_path(&path, 1)), "first\nsecond\n");
+
+        // records survive a rotation that fails
+        fs::remove_file(backup_path(&path, 1)).unwrap();
+        fs::create_dir(backup_path(&path, 1)).unwrap();
+        file.opened = two_hours_ago();
+        writeln!(file, "fourth").unwrap();
+        assert!(file.flush().is_err());
+
+        fs::remove_dir(backup_path(&path, 1)).unwrap();
+        file.flush().unwrap();
+        assert_eq!(read(&path), "third\nfourth\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
\ No newline at end of file
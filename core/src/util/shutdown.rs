@@ -0,0 +1,80 @@
+use std::ptr;
+use std::sync::{
+    atomic::{
+        AtomicBool,
+        AtomicPtr,
+        Ordering,
+    },
+    Arc,
+};
+
+/// Flag set by the first termination signal, see `Shutdown::on_signals`
+static SIGNAL_TARGET: AtomicPtr<AtomicBool> = AtomicPtr::new(ptr::null_mut());
+
+/**
+ * Shared request for long-running loops to wind down. Clones share the
+ * same request, so any thread can ask every other to stop.
+ */
+#[derive(Clone, Default)]
+pub struct Shutdown {
+    requested: Arc<AtomicBool>,
+}
+
+impl Shutdown {
+    pub fn new() -> Shutdown {
+        Default::default()
+    }
+
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /**
+     * Requests shutdown on SIGINT or SIGTERM. A second signal terminates
+     * the process as usual, in case shutting down hangs. Only the most
+     * recent caller is signalled.
+     */
+    pub fn on_signals(&self) {
+        // leaked, since a signal may arrive at any point after this
+        let flag = Arc::into_raw(self.requested.clone()) as *mut AtomicBool;
+        SIGNAL_TARGET.store(flag, Ordering::SeqCst);
+
+        unsafe {
+            libc::signal(libc::SIGINT, handle_signal as extern "C" fn(libc::c_int) as libc::sighandler_t);
+            libc::signal(libc::SIGTERM, handle_signal as extern "C" fn(libc::c_int) as libc::sighandler_t);
+        }
+    }
+}
+
+extern "C" fn handle_signal(signal: libc::c_int) {
+    let flag = SIGNAL_TARGET.load(Ordering::SeqCst);
+    if !flag.is_null() {
+        unsafe { (*flag).store(true, Ordering::SeqCst) };
+    }
+
+    unsafe {
+        libc::signal(signal, libc::SIG_DFL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signal_requests_shutdown() {
+        let shutdown = Shutdown::new();
+        let other = shutdown.clone();
+        shutdown.on_signals();
+        assert!(!other.is_requested());
+
+        unsafe {
+            libc::raise(libc::SIGTERM);
+        }
+        assert!(other.is_requested());
+    }
+}
\ No newline at end of file
@@ -10,6 +10,7 @@ use eternalreckoning_core::net::{
         self,
         Operation,
     },
+    PROTOCOL_VERSION,
 };
 
 fn main() {
@@ -20,11 +21,15 @@ fn main() {
 
     let stream = UdpFramed::new(
         socket,
-        EternalReckoningCodec
+        EternalReckoningCodec::new()
     );
 
     let sequence = stream
-        .send((Operation::ClConnectMessage(operation::ClConnectMessage), addr))
+        .send((Operation::ClConnectMessage(operation::ClConnectMessage {
+            protocol_version: PROTOCOL_VERSION,
+            client_build: concat!("ft-connect ", env!("CARGO_PKG_VERSION")).to_string(),
+            player_name: "ft-connect".to_string(),
+        }), addr))
         .and_then(|framed| {
             framed.into_future().map_err(|(err, _stream)| err)
         })
@@ -33,9 +38,11 @@ fn main() {
 
             let op = op.unwrap();
 
-            if let Operation::SvConnectResponse(operation::SvConnectResponse { uuid }) = op.0 {
+            if let Operation::SvConnectResponse(operation::SvConnectResponse { uuid, .. }) = op.0 {
                 println!("Connected with UUID {}", uuid);
                 eprintln!("Result: OK");
+            } else if let Operation::SvConnectRejected(data) = op.0 {
+                panic!("Connection rejected ({}): {}", data.reason, data.message);
             } else {
                 panic!("Invalid response from server");
             }
@@ -5,6 +5,4 @@ use failure_derive::Fail;
 pub enum NetworkError {
     #[fail(display = "Fatal error: {}", _0)]
     FatalError(Error),
-    #[fail(display = "Connection reset")]
-    RebuildRequired,
 }
\ No newline at end of file
@@ -1,7 +1,14 @@
 mod error;
 mod server;
 mod state;
+mod stats;
 mod reader;
 mod writer;
 
-pub use server::Server;
\ No newline at end of file
+pub use reader::Tx;
+pub use server::{
+    Server,
+    ServerFuture,
+};
+pub use stats::NetStats;
+pub use writer::Rx;
\ No newline at end of file
@@ -5,63 +5,62 @@ use failure::{
     format_err,
     Error,
 };
-use futures::stream::{
-    Stream,
-    SplitStream,
-};
-use tokio::net::UdpFramed;
 use tokio::prelude::{
     Async,
-    Future,
     Poll,
 };
 use uuid::Uuid;
 
 use eternalreckoning_core::net::{
-    codec::EternalReckoningCodec,
     operation::Operation,
+    transport::Transport,
 };
 
 use super::error::NetworkError;
-use super::state::SharedState;
+use super::state::{
+    SharedState,
+    State,
+};
 
 pub type Tx = Sender<(Uuid, Operation)>;
 
 pub struct Reader {
     shared: SharedState,
-    stream: SplitStream<UdpFramed<EternalReckoningCodec>>,
     tx: Tx,
 }
 
 impl Reader {
-    pub fn new(
-        shared: SharedState,
-        stream: SplitStream<UdpFramed<EternalReckoningCodec>>,
-        tx: Tx,
-    ) -> Reader
-    {
-        Reader { shared, stream, tx }
+    pub fn new(shared: SharedState, tx: Tx) -> Reader {
+        Reader { shared, tx }
     }
 
-    fn receive(&mut self, addr: SocketAddr, op: Operation) -> Result<(), Error> {
-        let mut shared = self.shared.lock()
-            .map_err(|err| {
-                format_err!("Failed to access shared state: {}", err)
-            })?;
+    fn forward(&self, id: Uuid, op: Operation) {
+        // the simulation only hangs up when shutting down, and the writer
+        // closes the server once it has flushed the goodbyes
+        if self.tx.send((id, op)).is_err() {
+            log::debug!("Simulation stopped, dropping operation from {}", id);
+        }
+    }
 
+    fn receive<T: Transport>(
+        &self,
+        transport: &mut T,
+        shared: &mut State,
+        addr: SocketAddr,
+        op: Operation,
+    ) -> Result<(), Error>
+    {
         if let Some(id) = shared.addr_to_id.get(&addr) {
             let id = *id;
             match op {
                 Operation::DisconnectMessage => {
                     shared.addr_to_id.remove(&addr);
                     shared.id_to_addr.remove(&id);
+                    transport.forget(&addr);
                 },
                 _ => (),
             }
-            self.tx.send((id, op))
-                .map_err(|err| {
-                    format_err!("Communication failure: {}", err)
-                })?;
+            self.forward(id, op);
         } else {
             match op {
                 Operation::ClConnectMessage(_) => {
@@ -70,39 +69,38 @@ impl Reader {
                     shared.addr_to_id.insert(addr, id);
                     shared.id_to_addr.insert(id, addr);
                     
-                    self.tx.send((id, op))
-                        .map_err(|err| {
-                            format_err!("Communication failure: {}", err)
-                        })?;
+                    self.forward(id, op);
                 },
                 _ => {
                     log::warn!("Received packet from unknown client: {}", &addr);
+                    transport.forget(&addr);
                 },
             }
         }
 
         Ok(())
     }
-}
-
-impl Future for Reader {
-    type Item = ();
-    type Error = NetworkError;
 
-    fn poll(&mut self) -> Poll<(), NetworkError> {
+    /// Forwards every operation received so far to the simulation
+    pub fn poll<T: Transport>(&mut self, transport: &mut T) -> Poll<(), NetworkError> {
         loop {
-            match self.stream.poll() {
+            match transport.poll() {
                 Ok(Async::NotReady) => {
                     return Ok(Async::NotReady);
                 },
                 Ok(Async::Ready(Some((op, addr)))) => {
-                    self.receive(addr, op)
+                    let mut shared = self.shared.lock()
+                        .map_err(|err| NetworkError::FatalError(
+                            format_err!("Failed to access shared state: {}", err)
+                        ))?;
+
+                    self.receive(transport, &mut shared, addr, op)
                         .map_err(|err| NetworkError::FatalError(
                             format_err!("Reader error: {}", err)
                         ))?;
                 },
                 Ok(Async::Ready(None)) => {
-                    return Err(NetworkError::RebuildRequired);
+                    return Ok(Async::Ready(()));
                 },
                 Err(err) => {
                     return Err(NetworkError::FatalError(
@@ -7,13 +7,24 @@ use failure::{
     format_err,
     Error,
 };
+use tokio::codec::BytesCodec;
 use tokio::net::{
     UdpSocket,
     UdpFramed,
 };
 use tokio::prelude::*;
 
-use eternalreckoning_core::net::codec::EternalReckoningCodec;
+use eternalreckoning_core::net::{
+    capture::Capture,
+    transport::{
+        ConditionedSocket,
+        DatagramTransport,
+        MergedSocket,
+        NetworkConditions,
+        Transport,
+        WebSocketListener,
+    },
+};
 
 use super::{
     error::NetworkError,
@@ -25,6 +36,7 @@ use super::{
         Reader,
         Tx,
     },
+    stats::NetStats,
     writer::{
         Writer,
         Rx,
@@ -32,28 +44,51 @@ use super::{
 };
 
 pub struct Server {
-    state: SharedState,
+    capture: Option<Capture>,
+    conditions: NetworkConditions,
+    stats: NetStats,
 }
 
 impl Server {
-    pub fn new() -> Server {
-        Server {
-            state: Arc::new(Mutex::new(State::new())),
-        }
+    pub fn new(capture: Option<Capture>, conditions: NetworkConditions, stats: NetStats)
+        -> Server
+    {
+        Server { capture, conditions, stats }
     }
 
     pub fn run(
         self,
         address: &String,
+        websocket_address: Option<String>,
         rx: Rx,
         tx: Tx,
-    )
+    ) -> Result<(), Error>
     {
-        let addr = address.parse().unwrap();
-        let socket = UdpSocket::bind(&addr).unwrap();
+        let addr = address.parse()
+            .map_err(|err| format_err!("Invalid bind address {}: {}", address, err))?;
+        let socket = UdpSocket::bind(&addr)
+            .map_err(|err| format_err!("Failed to bind {}: {}", addr, err))?;
         log::info!("Listening on: {}", &addr);
 
-        let server = ServerFuture::new(&self.state, socket, tx, rx);
+        let websocket = match websocket_address {
+            Some(address) => {
+                let addr = address.parse()
+                    .map_err(|err| format_err!("Invalid WebSocket address {}: {}", address, err))?;
+                let listener = WebSocketListener::bind(&addr)
+                    .map_err(|err| format_err!("Failed to bind {}: {}", addr, err))?;
+                log::info!("Listening for WebSocket connections on: {}", &addr);
+                Some(listener)
+            },
+            None => None,
+        };
+
+        let socket = ConditionedSocket::new(
+            MergedSocket::new(UdpFramed::new(socket, BytesCodec::new()), websocket),
+            &self.conditions
+        );
+        let transport = DatagramTransport::new(socket)
+            .with_capture(self.capture);
+        let server = ServerFuture::new(transport, tx, rx, self.stats);
 
         tokio::run(
             server
@@ -64,31 +99,52 @@ impl Server {
                     log::info!("Closing server")
                 })
         );
+
+        Ok(())
     }
 }
 
-struct ServerFuture {
+/**
+ * Relays operations between a transport and the simulation: received
+ * operations are sent to `tx` tagged with the client UUID, and operations
+ * from `rx` are sent to the client they are addressed to. The traffic
+ * counters of connected clients are copied to `stats` after every poll.
+ * Completes once the simulation hangs up and its last operations are sent.
+ */
+pub struct ServerFuture<T: Transport> {
+    transport: T,
+    state: SharedState,
+    stats: NetStats,
     reader: Reader,
     writer: Writer,
 }
 
-impl ServerFuture {
-    pub fn new(state: &SharedState, socket: UdpSocket, tx: Tx, rx: Rx)
-        -> ServerFuture
-    {
-        let framed = UdpFramed::new(socket, EternalReckoningCodec);
-        let (sink, stream) = framed.split();
+impl<T: Transport> ServerFuture<T> {
+    pub fn new(transport: T, tx: Tx, rx: Rx, stats: NetStats) -> ServerFuture<T> {
+        let state: SharedState = Arc::new(Mutex::new(State::new()));
 
         ServerFuture {
-            reader: Reader::new(state.clone(), stream, tx),
-            writer: Writer::new(state.clone(), sink, rx),
+            transport,
+            state: state.clone(),
+            stats,
+            reader: Reader::new(state.clone(), tx),
+            writer: Writer::new(state, rx),
         }
     }
 
+    fn update_stats(&self) -> Result<(), Error> {
+        let state = self.state.lock()
+            .map_err(|err| {
+                format_err!("Failed to access shared state: {}", err)
+            })?;
+
+        self.stats.update(&self.transport, &state);
+        Ok(())
+    }
+
     fn map_result(result: Poll<(), NetworkError>) -> Poll<(), Error> {
         match result {
             Ok(result) => Ok(result),
-            Err(NetworkError::RebuildRequired) => Ok(Async::Ready(())),
             Err(NetworkError::FatalError(err)) => Err(err),
         }
     }
@@ -109,14 +165,22 @@ impl ServerFuture {
     }
 }
 
-impl Future for ServerFuture {
+impl<T: Transport> Future for ServerFuture<T> {
     type Item = ();
     type Error = Error;
 
     fn poll(&mut self) -> Poll<(), Error> {
+        let reader = self.reader.poll(&mut self.transport);
+        let writer = self.writer.poll(&mut self.transport);
+        self.update_stats()?;
+
+        if let Ok(Async::Ready(())) = writer {
+            return Ok(Async::Ready(()));
+        }
+
         let result = Self::join_result(
-            Self::map_result(self.reader.poll()),
-            Self::map_result(self.writer.poll())
+            Self::map_result(reader),
+            Self::map_result(writer)
         );
 
         match result {
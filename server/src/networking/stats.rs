@@ -0,0 +1,61 @@
+use std::collections::HashMap;
+use std::sync::{
+    Arc,
+    Mutex,
+};
+
+use uuid::Uuid;
+
+use eternalreckoning_core::net::{
+    stats::ConnectionStats,
+    transport::Transport,
+};
+
+use super::state::State;
+
+/**
+ * Traffic counters of every connected client, copied from the transport by
+ * the network thread and read by the simulation.
+ */
+#[derive(Clone, Default)]
+pub struct NetStats {
+    clients: Arc<Mutex<HashMap<Uuid, ConnectionStats>>>,
+}
+
+impl NetStats {
+    pub fn new() -> NetStats {
+        NetStats::default()
+    }
+
+    pub fn get(&self, client: &Uuid) -> Option<ConnectionStats> {
+        self.clients.lock().ok()?.get(client).cloned()
+    }
+
+    /// Counters of all clients, in no particular order
+    pub fn all(&self) -> Vec<(Uuid, ConnectionStats)> {
+        match self.clients.lock() {
+            Ok(clients) => clients.iter()
+                .map(|(client, stats)| (*client, stats.clone()))
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Refreshes the counters of connected clients and drops departed ones
+    pub(super) fn update<T: Transport>(&self, transport: &T, state: &State) {
+        let mut clients = match self.clients.lock() {
+            Ok(clients) => clients,
+            Err(err) => {
+                log::error!("Failed to access network stats: {}", err);
+                return;
+            },
+        };
+
+        clients.retain(|client, _| state.id_to_addr.contains_key(client));
+        for (client, addr) in &state.id_to_addr {
+            if let Some(stats) = transport.stats(addr) {
+                clients.insert(*client, stats);
+            }
+        }
+    }
+}
\ No newline at end of file
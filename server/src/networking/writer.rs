@@ -2,22 +2,17 @@ use failure::{
     format_err,
     Error,
 };
-use futures::stream::{
-    Stream,
-    SplitSink,
-};
-use futures::sink::Sink;
-use tokio::net::UdpFramed;
+use futures::stream::Stream;
 use tokio::prelude::{
     Async,
-    Future,
+    AsyncSink,
     Poll,
 };
 use uuid::Uuid;
 
 use eternalreckoning_core::net::{
-    codec::EternalReckoningCodec,
     operation::Operation,
+    transport::Transport,
 };
 
 use super::error::NetworkError;
@@ -27,7 +22,6 @@ pub type Rx = futures::sync::mpsc::UnboundedReceiver<(Uuid, Operation)>;
 
 pub struct Writer {
     shared: SharedState,
-    sink: SplitSink<UdpFramed<EternalReckoningCodec>>,
     rx: Rx,
     state: WriterState,
 }
@@ -36,28 +30,42 @@ pub struct Writer {
 enum WriterState {
     Idle,
     Sending,
+    /// the simulation hung up, so only flushing is left
+    Closing,
 }
 
 impl Writer {
-    pub fn new(
-        shared: SharedState,
-        sink: SplitSink<UdpFramed<EternalReckoningCodec>>,
-        rx: Rx,
-    ) -> Writer
-    {
+    pub fn new(shared: SharedState, rx: Rx) -> Writer {
         let state = WriterState::Idle;
 
-        Writer { shared, sink, rx, state }
+        Writer { shared, rx, state }
     }
 
-    fn send(&mut self, client: Uuid, op: Operation) -> Result<(), Error> {
-        let shared = self.shared.lock()
+    fn send<T: Transport>(&mut self, transport: &mut T, client: Uuid, op: Operation)
+        -> Result<(), Error>
+    {
+        let mut shared = self.shared.lock()
             .map_err(|err| {
                 format_err!("Failed to access shared state: {}", err)
             })?;
 
-        if let Some(addr) = shared.id_to_addr.get(&client) {
-            self.sink.start_send((op, *addr))?;
+        if let Some(addr) = shared.id_to_addr.get(&client).cloned() {
+            let closing = matches!(
+                op,
+                Operation::SvConnectRejected(_) | Operation::DisconnectMessage
+            );
+
+            // the transport buffers outgoing operations until flushed
+            if let AsyncSink::NotReady(_) = transport.start_send((op, addr))? {
+                log::warn!("Transport busy, dropping operation to {}", client);
+            }
+
+            if closing {
+                // the peer may retry with a fresh connection request
+                shared.id_to_addr.remove(&client);
+                shared.addr_to_id.remove(&addr);
+                transport.forget(&addr);
+            }
         } else {
             log::warn!("Attempted to send to unknown client {}", client);
         }
@@ -65,36 +73,31 @@ impl Writer {
         Ok(())
     }
 
-    fn poll_sending(&mut self) -> Poll<(), Error> {
-        futures::try_ready!(self.sink.poll_complete());
-
-        Ok(Async::Ready(()))
-    }
-
-    fn poll_idle(&mut self) -> Poll<(), Error> {
+    fn poll_idle<T: Transport>(&mut self, transport: &mut T) -> Poll<(), Error> {
         match self.rx.poll().map_err(|_| format_err!("Reader disconnected"))? {
             Async::Ready(Some((client, op))) => {
-                self.send(client, op)?;
+                self.send(transport, client, op)?;
+                self.state = WriterState::Sending;
                 return Ok(Async::Ready(()));
             },
             Async::NotReady => return Ok(Async::NotReady),
             Async::Ready(None) => {
-                return Err(format_err!("Reader disconnected"));
+                self.state = WriterState::Closing;
+                return Ok(Async::Ready(()));
             },
         }
     }
-}
-
-impl Future for Writer {
-    type Item = ();
-    type Error = NetworkError;
 
-    fn poll(&mut self) -> Poll<(), NetworkError> {
+    /**
+     * Sends every operation queued by the simulation so far. Ready once
+     * the simulation has hung up and everything it queued is flushed.
+     */
+    pub fn poll<T: Transport>(&mut self, transport: &mut T) -> Poll<(), NetworkError> {
         loop {
             match self.state {
                 WriterState::Sending => {
                     futures::try_ready!(
-                        self.poll_sending()
+                        transport.poll_complete()
                             .map_err(|err| NetworkError::FatalError(
                                 format_err!("Writer error: {}", err)
                             ))
@@ -103,12 +106,20 @@ impl Future for Writer {
                 },
                 WriterState::Idle => {
                     futures::try_ready!(
-                        self.poll_idle()
+                        self.poll_idle(transport)
+                            .map_err(|err| NetworkError::FatalError(
+                                format_err!("Writer error: {}", err)
+                            ))
+                    );
+                },
+                WriterState::Closing => {
+                    futures::try_ready!(
+                        transport.poll_complete()
                             .map_err(|err| NetworkError::FatalError(
                                 format_err!("Writer error: {}", err)
                             ))
                     );
-                    self.state = WriterState::Sending;
+                    return Ok(Async::Ready(()));
                 },
             }
         }
@@ -2,6 +2,7 @@ use std::sync::mpsc::{
     channel,
     TryRecvError,
 };
+use std::net::SocketAddr;
 use std::time::Duration;
 use std::thread;
 
@@ -11,49 +12,154 @@ use failure::{
 };
 use futures::sync::mpsc::unbounded;
 
+use eternalreckoning_core::net::capture::Capture;
+use eternalreckoning_core::net::quantization::QuantizationConfig;
+use eternalreckoning_core::net::transport::NetworkConditions;
+use eternalreckoning_core::simulation::{
+    PhysicsConfig,
+    ProfilerConfig,
+    TerrainConfig,
+};
+use eternalreckoning_core::util::config::{
+    Validate,
+    Validator,
+};
+use eternalreckoning_core::util::shutdown::Shutdown;
 use crate::simulation::build_simulation;
+use crate::simulation::snapshotter;
 use crate::simulation::Event;
-use crate::networking::Server;
+use crate::networking::{
+    NetStats,
+    Server,
+};
 use crate::util::config::Config;
 
 #[derive(serde::Serialize, serde::Deserialize)]
 #[serde(default, rename_all = "kebab-case")]
 pub struct ServerConfig {
     pub tick_rate: u64,
+    /// late ticks simulated back to back before the rest are skipped
+    pub max_catch_up_ticks: u32,
     pub bind_address: String,
+    /// also accepts WebSocket connections on this address if set
+    pub websocket_address: Option<String>,
     pub client_ttl_ms: u64,
+    pub max_clients: usize,
+    /// how often per-client network stats are logged, 0 to disable
+    pub net_stats_interval_ms: u64,
+    pub banned_players: Vec<String>,
+    /// player movement speed, in units per second
+    pub movement_speed: f64,
+    /// upward velocity of a jump, in units per second
+    pub jump_force: f64,
+    pub physics: PhysicsConfig,
+    /// the ground players collide with, which must match the clients'
+    pub terrain: TerrainConfig,
+    /// records every packet sent and received to this file
+    pub capture_file: Option<String>,
+    /// saves the world to this file on a clean shutdown, for inspection and
+    /// bug reports; a crash writes nothing and the file is never loaded back
+    pub snapshot_file: Option<String>,
+    /// simulated latency, loss, duplication and reordering
+    pub network_conditions: NetworkConditions,
+    pub quantization: QuantizationConfig,
+    /// per-system tick timings
+    pub profiler: ProfilerConfig,
 }
 
 impl Default for ServerConfig {
     fn default() -> ServerConfig {
         ServerConfig {
             tick_rate: 60,
+            max_catch_up_ticks: 5,
             bind_address: "127.0.0.1:6142".to_string(),
+            websocket_address: None,
             client_ttl_ms: 500,
+            max_clients: 32,
+            net_stats_interval_ms: 10000,
+            banned_players: Vec::new(),
+            movement_speed: 8.5,
+            jump_force: 10.35,
+            physics: PhysicsConfig::default(),
+            terrain: TerrainConfig::default(),
+            capture_file: None,
+            snapshot_file: None,
+            network_conditions: NetworkConditions::default(),
+            quantization: QuantizationConfig::default(),
+            profiler: ProfilerConfig::default(),
+        }
+    }
+}
+
+impl Validate for ServerConfig {
+    fn validate(&self, validator: &mut Validator) {
+        validator.check(
+            "tick-rate",
+            self.tick_rate > 0 && self.tick_rate <= 1000,
+            "must be between 1 and 1000"
+        );
+        validator.check(
+            "bind-address",
+            self.bind_address.parse::<SocketAddr>().is_ok(),
+            "must be an IP address and port, such as 127.0.0.1:6142"
+        );
+        if let Some(ref address) = self.websocket_address {
+            validator.check(
+                "websocket-address",
+                address.parse::<SocketAddr>().is_ok(),
+                "must be an IP address and port, such as 127.0.0.1:6143"
+            );
         }
+        validator.check("client-ttl-ms", self.client_ttl_ms > 0, "must be greater than 0");
+        validator.check(
+            "movement-speed",
+            self.movement_speed.is_finite() && self.movement_speed >= 0.0,
+            "must not be negative"
+        );
+        validator.check(
+            "jump-force",
+            self.jump_force.is_finite() && self.jump_force >= 0.0,
+            "must not be negative"
+        );
+        validator.nested("physics", &self.physics);
+        validator.nested("terrain", &self.terrain);
+        validator.nested("network-conditions", &self.network_conditions);
+        validator.nested("quantization", &self.quantization);
+        validator.nested("profiler", &self.profiler);
     }
 }
 
 pub fn main(config: Config) -> Result<(), Error> {
+    let shutdown = Shutdown::new();
+    shutdown.on_signals();
+
     let (outbound_tx, outbound_rx) = unbounded();
     let (inbound_tx, inbound_rx) = channel();
 
+    let capture = match config.server.capture_file {
+        Some(ref path) => Some(Capture::create(path)?),
+        None => None,
+    };
+
     let addr = config.server.bind_address.clone();
-    thread::spawn(move || {
-        let server = Server::new();
-        server.run(&addr, outbound_rx, inbound_tx);
+    let websocket_addr = config.server.websocket_address.clone();
+    let conditions = config.server.network_conditions.clone();
+    let net_stats = NetStats::new();
+    let server_stats = net_stats.clone();
+    let network = thread::spawn(move || {
+        let server = Server::new(capture, conditions, server_stats);
+        server.run(&addr, websocket_addr, outbound_rx, inbound_tx)
     });
 
     let tick_length = Duration::from_millis(
         1000 / config.server.tick_rate
     );
 
-    let mut game = build_simulation(
-        outbound_tx,
-        config.server.client_ttl_ms
-    );
+    let mut game = build_simulation(outbound_tx, net_stats, &config.server)?
+        .with_max_catch_up(config.server.max_catch_up_ticks)
+        .with_shutdown(shutdown);
 
-    game.run(
+    let result = game.run(
         move || {
             match inbound_rx.try_recv() {
                 Ok((uuid, op)) => {
@@ -64,10 +170,22 @@ pub fn main(config: Config) -> Result<(), Error> {
             }
         },
         tick_length
-    )
-        .map_err(|_| {
-            format_err!("Network thread disconnected")
-        })?;
+    );
+
+    if let Some(ref path) = config.server.snapshot_file {
+        match snapshotter().save(game.world()).and_then(|snapshot| snapshot.write(path)) {
+            Ok(()) => log::info!("Saved world snapshot to {}", path),
+            Err(err) => log::error!("Failed to save world snapshot: {}", err),
+        }
+    }
+
+    // disconnects every client and hangs up, which lets the network thread
+    // finish once the disconnects are sent
+    game.dispose();
+    network.join()
+        .map_err(|_| format_err!("Network thread panicked"))??;
 
-    Ok(())
+    result.map_err(|_| {
+        format_err!("Network thread disconnected")
+    })
 }
\ No newline at end of file
@@ -0,0 +1,61 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use specs::prelude::*;
+
+use eternalreckoning_core::net::operation::Archetype;
+use eternalreckoning_core::simulation::worldsnapshot::{
+    EntityMap,
+    SnapshotComponent,
+    SnapshotError,
+};
+
+/// How clients should present a replicated entity
+pub struct Appearance {
+    pub archetype: Archetype,
+    pub model: String,
+    pub texture: String,
+}
+
+impl Component for Appearance {
+    type Storage = VecStorage<Self>;
+}
+
+impl Appearance {
+    pub fn player() -> Appearance {
+        Appearance {
+            archetype: Archetype::Player,
+            model: "assets/marker.erm".to_string(),
+            texture: "assets/marker.png".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct AppearanceData {
+    archetype: u8,
+    model: String,
+    texture: String,
+}
+
+impl SnapshotComponent for Appearance {
+    const KEY: &'static str = "appearance";
+    type Data = AppearanceData;
+
+    fn save(&self, _: &EntityMap) -> AppearanceData {
+        AppearanceData {
+            archetype: self.archetype.code(),
+            model: self.model.clone(),
+            texture: self.texture.clone(),
+        }
+    }
+
+    fn restore(data: AppearanceData, _: &EntityMap) -> Result<Appearance, SnapshotError> {
+        Ok(Appearance {
+            archetype: Archetype::from_code(data.archetype),
+            model: data.model,
+            texture: data.texture,
+        })
+    }
+}
\ No newline at end of file
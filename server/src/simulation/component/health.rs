@@ -1,7 +0,0 @@
-use specs::prelude::*;
-
-pub struct Health(pub u64);
-
-impl Component for Health {
-    type Storage = VecStorage<Self>;
-}
\ No newline at end of file
@@ -1,8 +1,32 @@
 use specs::prelude::*;
 use uuid::Uuid;
 
+use eternalreckoning_core::simulation::worldsnapshot::{
+    EntityMap,
+    SnapshotComponent,
+    SnapshotError,
+};
+
 pub struct Id(pub Uuid);
 
 impl Component for Id {
     type Storage = VecStorage<Self>;
+}
+
+impl SnapshotComponent for Id {
+    const KEY: &'static str = "id";
+    type Data = String;
+
+    fn save(&self, _: &EntityMap) -> String {
+        self.0.to_string()
+    }
+
+    fn restore(data: String, _: &EntityMap) -> Result<Id, SnapshotError> {
+        data.parse()
+            .map(Id)
+            .map_err(|err| SnapshotError::InvalidComponent {
+                key: Self::KEY.to_string(),
+                message: format!("{}", err),
+            })
+    }
 }
\ No newline at end of file
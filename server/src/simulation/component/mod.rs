@@ -1,11 +1,19 @@
+mod appearance;
 pub mod client;
-mod health;
 mod id;
-mod name;
-mod position;
 
+pub use appearance::Appearance;
 pub use client::Client;
-pub use health::Health;
 pub use id::Id;
-pub use name::Name;
-pub use position::Position;
\ No newline at end of file
+
+pub use eternalreckoning_core::simulation::component::{
+    collider,
+    Collider,
+    Health,
+    Jump,
+    Movement,
+    Name,
+    PlayerInput,
+    Position,
+    Velocity,
+};
\ No newline at end of file
@@ -1,9 +1,11 @@
 pub mod component;
 pub mod system;
 mod simulation;
+mod snapshot;
 mod event;
 
 pub use event::Event;
 pub use simulation::build_simulation;
+pub use snapshot::snapshotter;
 
 pub type EventQueue = Vec<Event>;
\ No newline at end of file
@@ -1,47 +1,162 @@
 use std::time::Duration;
 
+use failure::Error;
 use futures::sync::mpsc::UnboundedSender;
 use specs::{
+    world::Builder,
     DispatcherBuilder,
     World,
     WorldExt,
 };
 use uuid::Uuid;
 
-use eternalreckoning_core::net::operation::Operation;
+use eternalreckoning_core::net::operation::{
+    self,
+    Operation,
+};
+
+use crate::networking::NetStats;
+use crate::server::ServerConfig;
 
 use super::Event;
 use super::component::{
+    collider::{
+        self,
+        Collider,
+    },
+    Appearance,
     Client,
     Health,
+    Jump,
+    Movement,
     Name,
+    PlayerInput,
     Position,
+    Velocity,
 };
 use super::system::{
+    ClockSync,
+    CollisionDetection,
+    CollisionResolver,
     Connections,
+    InputReceiver,
+    NetStatsLogger,
+    Physics,
     PlayerMovement,
     UpdateSender,
 };
 
-use eternalreckoning_core::simulation::Simulation;
+use eternalreckoning_core::simulation::{
+    Profiler,
+    Simulation,
+    TickLength,
+};
 
 pub fn build_simulation<'a, 'b>(
     net_tx: UnboundedSender<(Uuid, Operation)>,
-    client_ttl_ms: u64,
-) -> Simulation<'a, 'b, Event>
+    net_stats: NetStats,
+    config: &ServerConfig,
+) -> Result<Simulation<'a, 'b, Event>, Error>
 {
     let mut world = World::new();
 
+    let tick_length = TickLength(Duration::from_secs(1) / config.tick_rate as u32);
+
+    world.register::<Appearance>();
     world.register::<Client>();
+    world.register::<Collider>();
     world.register::<Health>();
+    world.register::<Jump>();
+    world.register::<Movement>();
     world.register::<Name>();
+    world.register::<PlayerInput>();
     world.register::<Position>();
+    world.register::<Velocity>();
+
+    world.insert(net_stats);
+    world.insert(tick_length);
+    world.insert(config.physics.scaled(&tick_length));
+
+    // Terrain, the same the clients collide with
+    world.create_entity()
+        .with(Position(config.terrain.origin()))
+        .with(Collider::new(collider::ColliderType::HeightMap(config.terrain.load()?)))
+        .build();
+
+    let profiler = Profiler::new(
+        &config.profiler,
+        Duration::from_millis(1000 / config.tick_rate)
+    );
     
     let dispatcher = DispatcherBuilder::new()
-        .with(Connections::new(Duration::from_millis(client_ttl_ms)), "connections", &[])
-        .with(PlayerMovement, "player_movement", &[])
-        .with(UpdateSender::new(net_tx), "update_sender", &["player_movement"])
+        .with(
+            profiler.timed("connections", Connections::new(
+                Duration::from_millis(config.client_ttl_ms),
+                config.max_clients,
+                config.banned_players.clone(),
+                tick_length.scale_to(config.movement_speed),
+                tick_length.scale_to(config.jump_force),
+                net_tx.clone()
+            )),
+            "connections",
+            &[]
+        )
+        .with(
+            profiler.timed("clock_sync", ClockSync::new(net_tx.clone())),
+            "clock_sync",
+            &[]
+        )
+        .with(
+            profiler.timed(
+                "net_stats",
+                NetStatsLogger::new(Duration::from_millis(config.net_stats_interval_ms))
+            ),
+            "net_stats",
+            &[]
+        )
+        .with(
+            profiler.timed("input_receiver", InputReceiver),
+            "input_receiver",
+            &[]
+        )
+        .with(
+            profiler.timed("player_movement", PlayerMovement),
+            "player_movement",
+            &["connections", "input_receiver"]
+        )
+        .with(
+            profiler.timed("physics", Physics),
+            "physics",
+            &["player_movement"]
+        )
+        .with(
+            profiler.timed("collision_detection", CollisionDetection::default()),
+            "collision_detection",
+            &["physics"]
+        )
+        .with(
+            profiler.timed("collision_resolver", CollisionResolver),
+            "collision_resolver",
+            &["collision_detection"]
+        )
+        .with(
+            profiler.timed(
+                "update_sender",
+                UpdateSender::new(
+                    net_tx,
+                    config.quantization.clone(),
+                    operation::SimulationParams {
+                        movement_speed: config.movement_speed,
+                        jump_force: config.jump_force,
+                        physics: config.physics.clone(),
+                    }
+                )
+            ),
+            "update_sender",
+            &["collision_resolver"]
+        )
         .build();
 
-    Simulation::new(dispatcher, world)
+    Ok(Simulation::new(dispatcher, world)
+        .with_profiler(profiler))
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+use eternalreckoning_core::simulation::WorldSnapshotter;
+
+use super::component::{
+    Appearance,
+    Health,
+    Id,
+    Jump,
+    Movement,
+    Name,
+    Position,
+    Velocity,
+};
+
+/**
+ * Components saved in server world snapshots. Connection state and inputs
+ * are left out, since they mean nothing once the clients are gone. For the
+ * same reason the server never restores a snapshot into its own world:
+ * players would come back without a `Client`, so they would never time out
+ * and nobody could take control of them.
+ */
+pub fn snapshotter() -> WorldSnapshotter {
+    WorldSnapshotter::new()
+        .with::<Appearance>()
+        .with::<Health>()
+        .with::<Id>()
+        .with::<Jump>()
+        .with::<Movement>()
+        .with::<Name>()
+        .with::<Position>()
+        .with::<Velocity>()
+}
\ No newline at end of file
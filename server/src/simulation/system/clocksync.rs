@@ -0,0 +1,50 @@
+use futures::sync::mpsc::UnboundedSender;
+use specs::prelude::*;
+use uuid::Uuid;
+
+use eternalreckoning_core::net::{
+    clock::timestamp_now,
+    operation::{
+        self,
+        Operation,
+    },
+};
+
+use eternalreckoning_core::simulation::TickNumber;
+
+use super::super::EventQueue;
+
+/// Answers clock sync requests with the server time and tick
+pub struct ClockSync {
+    sender: UnboundedSender<(Uuid, Operation)>,
+}
+
+impl ClockSync {
+    pub fn new(sender: UnboundedSender<(Uuid, Operation)>) -> ClockSync {
+        ClockSync { sender }
+    }
+}
+
+impl<'a> System<'a> for ClockSync {
+    type SystemData = (
+        Read<'a, TickNumber>,
+        Read<'a, EventQueue>,
+    );
+
+    fn run(&mut self, (tick, events): Self::SystemData) {
+        for event in &*events {
+            if let Operation::ClSync(ref data) = event.op {
+                let op = Operation::SvSync(operation::SvSync {
+                    client_time: data.client_time,
+                    server_time: timestamp_now(),
+                    tick: tick.0,
+                });
+
+                self.sender.unbounded_send((event.uuid, op))
+                    .unwrap_or_else(|err| {
+                        log::error!("Failed to send clock sync: {}", err);
+                    });
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -1,31 +1,130 @@
-use std::time::{
-    Duration,
-    Instant,
-};
+use std::time::Duration;
 
+use futures::sync::mpsc::UnboundedSender;
 use specs::prelude::*;
+use uuid::Uuid;
 
-use eternalreckoning_core::net::operation::{
-    Operation,
+use eternalreckoning_core::net::{
+    operation::{
+        self,
+        Operation,
+        RejectReason,
+    },
+    PROTOCOL_VERSION,
 };
 use eternalreckoning_core::simulation::TickTime;
 
 use super::super::{
     component::{
+        collider::ColliderType,
+        Appearance,
         Client,
+        Collider,
         Id,
+        Jump,
+        Movement,
+        Name,
+        PlayerInput,
         Position,
+        Velocity,
     },
     EventQueue,
 };
 
+const MAX_PLAYER_NAME_LEN: usize = 32;
+
 pub struct Connections {
     ttl: Duration,
+    max_clients: usize,
+    banned_players: Vec<String>,
+    /// distance a player walks per tick
+    movement_speed: f64,
+    /// upward velocity a jump gives a player
+    jump_force: f64,
+    sender: UnboundedSender<(Uuid, Operation)>,
 }
 
 impl Connections {
-    pub fn new(ttl: Duration) -> Connections {
-        Connections { ttl }
+    pub fn new(
+        ttl: Duration,
+        max_clients: usize,
+        banned_players: Vec<String>,
+        movement_speed: f64,
+        jump_force: f64,
+        sender: UnboundedSender<(Uuid, Operation)>,
+    ) -> Connections
+    {
+        Connections {
+            ttl,
+            max_clients,
+            banned_players,
+            movement_speed,
+            jump_force,
+            sender,
+        }
+    }
+
+    /// Checks whether a connection request may join the game
+    fn admit(&self, data: &operation::ClConnectMessage, client_count: usize)
+        -> Result<(), (RejectReason, String)>
+    {
+        if data.protocol_version != PROTOCOL_VERSION {
+            return Err((
+                RejectReason::VersionMismatch,
+                format!(
+                    "server speaks protocol version {}, client {} speaks {}",
+                    PROTOCOL_VERSION,
+                    data.client_build,
+                    data.protocol_version
+                ),
+            ));
+        }
+
+        let name = data.player_name.trim();
+        if name.is_empty()
+            || name.chars().count() > MAX_PLAYER_NAME_LEN
+            || name.chars().any(char::is_control)
+        {
+            return Err((
+                RejectReason::InvalidName,
+                format!(
+                    "player names must be 1 to {} printable characters",
+                    MAX_PLAYER_NAME_LEN
+                ),
+            ));
+        }
+
+        if self.banned_players.iter().any(|banned| banned.eq_ignore_ascii_case(name)) {
+            return Err((
+                RejectReason::Banned,
+                format!("{} is banned from this server", name),
+            ));
+        }
+
+        if client_count >= self.max_clients {
+            return Err((
+                RejectReason::ServerFull,
+                format!("server is full ({}/{})", client_count, self.max_clients),
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn reject(&self, uuid: Uuid, reason: RejectReason, message: String) {
+        log::info!(
+            uuid:% = uuid, reason:% = reason;
+            "Rejected client {}: {} ({})", uuid, message, reason
+        );
+
+        let op = Operation::SvConnectRejected(
+            operation::SvConnectRejected { reason, message }
+        );
+
+        self.sender.unbounded_send((uuid, op))
+            .unwrap_or_else(|err| {
+                log::error!("Failed to send rejection: {}", err);
+            });
     }
 }
 
@@ -34,9 +133,16 @@ impl<'a> System<'a> for Connections {
         Entities<'a>,
         Read<'a, TickTime>,
         Read<'a, EventQueue>,
+        WriteStorage<'a, Appearance>,
         WriteStorage<'a, Client>,
+        WriteStorage<'a, Collider>,
         WriteStorage<'a, Id>,
+        WriteStorage<'a, Jump>,
+        WriteStorage<'a, Movement>,
+        WriteStorage<'a, Name>,
+        WriteStorage<'a, PlayerInput>,
         WriteStorage<'a, Position>,
+        WriteStorage<'a, Velocity>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -44,59 +150,55 @@ impl<'a> System<'a> for Connections {
             entities,
             tick_time,
             events,
+            mut appearances,
             mut clients,
+            mut colliders,
             mut ids,
+            mut jumps,
+            mut movements,
+            mut names,
+            mut inputs,
             mut positions,
+            mut velocities,
         ) = data;
 
         for event in &*events {
             match event.op {
-                Operation::ClConnectMessage(_) => {
-                    log::info!("Client connected: {}", event.uuid);
-
-                    let client = entities.create();
-
-                    ids.insert(client, Id(event.uuid.clone()))
-                        .unwrap_or_else(|err| {
-                            log::error!(
-                                "Failed to add id for client {}: {}",
-                                event.uuid,
-                                err
-                            );
-                            None
-                        });
-
-                    clients.insert(client, Client::new(Instant::now() + self.ttl))
-                        .unwrap_or_else(|err| {
-                            log::error!(
-                                "Failed to add state for client {}: {}",
-                                event.uuid,
-                                err
-                            );
-                            None
-                        });
-
-                    positions.insert(client, Position(
-                        nalgebra::Point3::<f64>::new(0.0, 0.0, 0.0)
-                    ))
-                        .unwrap_or_else(|err| {
-                            log::error!(
-                                "Failed to add position for client {}: {}",
-                                event.uuid,
-                                err
-                            );
-                            None
-                        });
-                },
-                Operation::ClSync(_) => {
-                    for (id, client) in (&ids, &mut clients).join() {
-                        if id.0 == event.uuid {
-                            client.lifetime = tick_time.0 + self.ttl;
-                            break;
-                        }
+                Operation::ClConnectMessage(ref data) => {
+                    if let Err((reason, message)) = self.admit(data, clients.count()) {
+                        self.reject(event.uuid, reason, message);
+                        continue;
                     }
+
+                    log::info!(
+                        uuid:% = event.uuid,
+                        player_name = data.player_name.as_str(),
+                        client_build = data.client_build.as_str();
+                        "Client connected: {} ({}, {})",
+                        event.uuid,
+                        data.player_name,
+                        data.client_build
+                    );
+
+                    entities.build_entity()
+                        .with(Id(event.uuid), &mut ids)
+                        .with(Client::new(tick_time.0 + self.ttl), &mut clients)
+                        .with(Appearance::player(), &mut appearances)
+                        .with(Name(data.player_name.trim().to_string()), &mut names)
+                        .with(PlayerInput::default(), &mut inputs)
+                        .with(Position(nalgebra::Point3::origin()), &mut positions)
+                        .with(Velocity(nalgebra::Vector3::zeros()), &mut velocities)
+                        .with(Movement {
+                            speed: self.movement_speed,
+                            on_ground: true,
+                        }, &mut movements)
+                        .with(Jump { force: self.jump_force }, &mut jumps)
+                        .with(Collider::new(ColliderType::Sphere(1.0)), &mut colliders)
+                        .build();
                 },
-                Operation::ClMoveSetPosition(_) => {
+                Operation::ClSync(_)
+                | Operation::ClSnapshotAck(_)
+                | Operation::ClInput(_) => {
                     for (id, client) in (&ids, &mut clients).join() {
                         if id.0 == event.uuid {
                             client.lifetime = tick_time.0 + self.ttl;
@@ -118,7 +220,7 @@ impl<'a> System<'a> for Connections {
 
         for (entity, id, client) in (&entities, &ids, &clients).join() {
             if client.lifetime <= tick_time.0 {
-                log::info!("Client disconnected: {}", id.0);
+                log::info!(uuid:% = id.0; "Client disconnected: {}", id.0);
                 entities.delete(entity)
                     .unwrap_or_else(|err| {
                         log::error!(
@@ -130,4 +232,17 @@ impl<'a> System<'a> for Connections {
             }
         }
     }
-}
+
+    /// Tells every connected client that the server is going away
+    fn dispose(self, world: &mut World) {
+        let ids = world.read_storage::<Id>();
+        let clients = world.read_storage::<Client>();
+
+        for (id, _) in (&ids, &clients).join() {
+            self.sender.unbounded_send((id.0, Operation::DisconnectMessage))
+                .unwrap_or_else(|err| {
+                    log::error!("Failed to send disconnect to {}: {}", id.0, err);
+                });
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,55 @@
+use specs::prelude::*;
+
+use eternalreckoning_core::net::{
+    operation::Operation,
+    quantization::dequantize_yaw,
+};
+use eternalreckoning_core::simulation::movement::MoveKeys;
+
+use super::super::{
+    component::{
+        Id,
+        PlayerInput,
+    },
+    EventQueue,
+};
+
+/**
+ * Stores the latest input received from each client. Inputs are held
+ * state, so `PlayerMovement` applies them every server tick until a newer
+ * one arrives; inputs older than the latest applied one are dropped.
+ */
+pub struct InputReceiver;
+
+impl<'a> System<'a> for InputReceiver {
+    type SystemData = (
+        Read<'a, EventQueue>,
+        ReadStorage<'a, Id>,
+        WriteStorage<'a, PlayerInput>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (events, ids, mut inputs) = data;
+
+        for event in &*events {
+            if let Operation::ClInput(ref data) = event.op {
+                for (id, input) in (&ids, &mut inputs).join() {
+                    if id.0 != event.uuid {
+                        continue;
+                    }
+
+                    if input.tick.is_some_and(|tick| data.tick <= tick) {
+                        log::trace!("Dropping stale input {} from {}", data.tick, id.0);
+                        break;
+                    }
+
+                    input.tick = Some(data.tick);
+                    input.keys = MoveKeys(data.keys);
+                    input.jump = data.jump;
+                    input.yaw = dequantize_yaw(data.yaw);
+                    break;
+                }
+            }
+        }
+    }
+}
\ No newline at end of file
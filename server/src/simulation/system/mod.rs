@@ -1,7 +1,18 @@
+mod clocksync;
 mod connections;
-mod playermovement;
+mod inputreceiver;
+mod netstats;
 mod updatesender;
 
+pub use clocksync::ClockSync;
 pub use connections::Connections;
-pub use playermovement::PlayerMovement;
-pub use updatesender::UpdateSender;
\ No newline at end of file
+pub use inputreceiver::InputReceiver;
+pub use netstats::NetStatsLogger;
+pub use updatesender::UpdateSender;
+
+pub use eternalreckoning_core::simulation::system::{
+    CollisionDetection,
+    CollisionResolver,
+    Physics,
+    PlayerMovement,
+};
\ No newline at end of file
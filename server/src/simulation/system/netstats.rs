@@ -0,0 +1,80 @@
+use std::collections::HashMap;
+use std::time::{
+    Duration,
+    Instant,
+};
+
+use specs::prelude::*;
+use uuid::Uuid;
+
+use eternalreckoning_core::net::stats::ConnectionStats;
+use eternalreckoning_core::simulation::TickTime;
+
+use crate::networking::NetStats;
+
+/// Periodically logs the bandwidth and traffic counters of every client
+pub struct NetStatsLogger {
+    interval: Duration,
+    last_log: Option<Instant>,
+    previous: HashMap<Uuid, ConnectionStats>,
+}
+
+impl NetStatsLogger {
+    pub fn new(interval: Duration) -> NetStatsLogger {
+        NetStatsLogger {
+            interval,
+            last_log: None,
+            previous: HashMap::new(),
+        }
+    }
+
+    fn log(&self, client: &Uuid, stats: &ConnectionStats, now: Instant, elapsed: Duration) {
+        let earlier = self.previous.get(client).cloned().unwrap_or_default();
+        let (bytes_in, bytes_out) = stats.bandwidth_since(&earlier, elapsed);
+        let idle = stats.last_seen
+            .map(|last_seen| now.saturating_duration_since(last_seen).as_millis())
+            .unwrap_or(0);
+
+        log::info!(
+            "Client {}: {:.0} B/s in, {:.0} B/s out, {} packets in, {} packets out, \
+            {} decode errors, last seen {} ms ago",
+            client,
+            bytes_in,
+            bytes_out,
+            stats.packets_in,
+            stats.packets_out,
+            stats.decode_errors,
+            idle
+        );
+        log::debug!(
+            "Client {} operations in: {:x?}, out: {:x?}",
+            client,
+            stats.opcodes_in,
+            stats.opcodes_out
+        );
+    }
+}
+
+impl<'a> System<'a> for NetStatsLogger {
+    type SystemData = (
+        Read<'a, TickTime>,
+        ReadExpect<'a, NetStats>,
+    );
+
+    fn run(&mut self, (tick_time, net_stats): Self::SystemData) {
+        let now = tick_time.0;
+        let last_log = *self.last_log.get_or_insert(now);
+        let elapsed = now.saturating_duration_since(last_log);
+        if self.interval == Duration::from_secs(0) || elapsed < self.interval {
+            return;
+        }
+
+        let clients = net_stats.all();
+        for (client, stats) in &clients {
+            self.log(client, stats, now, elapsed);
+        }
+
+        self.previous = clients.into_iter().collect();
+        self.last_log = Some(now);
+    }
+}
\ No newline at end of file
@@ -1,27 +1,120 @@
+use std::collections::{
+    HashMap,
+    HashSet,
+};
+
 use futures::sync::mpsc::UnboundedSender;
 use specs::prelude::*;
 use uuid::Uuid;
 
-use eternalreckoning_core::net::operation::{
-    self,
-    Operation,
+use eternalreckoning_core::net::{
+    operation::{
+        self,
+        Operation,
+    },
+    quantization::QuantizationConfig,
+    snapshot::{
+        Snapshot,
+        SnapshotId,
+        SnapshotRing,
+        SNAPSHOT_HISTORY,
+    },
 };
+use eternalreckoning_core::simulation::TickNumber;
 
-use super::super::component::{
-    client::ClientState,
-    Client,
-    Id,
-    Position,
-    Health,
+use super::super::{
+    component::{
+        client::ClientState,
+        Appearance,
+        Client,
+        Id,
+        PlayerInput,
+        Position,
+        Health,
+    },
+    EventQueue,
 };
 
+/// An entity as every client sees it this tick
+struct EntityView<'a> {
+    uuid: Uuid,
+    appearance: &'a Appearance,
+    components: Vec<operation::EntityComponent>,
+}
+
+struct ClientSnapshots {
+    history: SnapshotRing,
+    acknowledged: Option<SnapshotId>,
+    /// entities the client has been told to spawn
+    spawned: HashSet<Uuid>,
+    /// departed entities, with the first snapshot they are missing from
+    despawning: HashMap<Uuid, SnapshotId>,
+}
+
+impl ClientSnapshots {
+    fn new() -> ClientSnapshots {
+        ClientSnapshots {
+            history: SnapshotRing::new(SNAPSHOT_HISTORY),
+            acknowledged: None,
+            spawned: HashSet::new(),
+            despawning: HashMap::new(),
+        }
+    }
+
+    /// Whether the client has acknowledged a snapshot containing the entity
+    fn has_spawned(&self, uuid: &Uuid) -> bool {
+        match self.baseline() {
+            Some((_, baseline)) => baseline.get(uuid).is_some(),
+            None => false,
+        }
+    }
+
+    fn baseline(&self) -> Option<(SnapshotId, &Snapshot)> {
+        let id = self.acknowledged?;
+        self.history.get(id).map(|snapshot| (id, snapshot))
+    }
+}
+
 pub struct UpdateSender {
     sender: UnboundedSender<(Uuid, Operation)>,
+    quantization: QuantizationConfig,
+    /// sent to connecting clients, so their prediction moves players like we do
+    params: operation::SimulationParams,
+    clients: HashMap<Uuid, ClientSnapshots>,
 }
 
 impl UpdateSender {
-    pub fn new(sender: UnboundedSender<(Uuid, Operation)>) -> UpdateSender {
-        UpdateSender { sender }
+    pub fn new(
+        sender: UnboundedSender<(Uuid, Operation)>,
+        quantization: QuantizationConfig,
+        params: operation::SimulationParams,
+    ) -> UpdateSender
+    {
+        UpdateSender {
+            sender,
+            quantization,
+            params,
+            clients: HashMap::new(),
+        }
+    }
+
+    fn acknowledge_snapshots(&mut self, events: &EventQueue) {
+        for event in events {
+            if let Operation::ClSnapshotAck(ref data) = event.op {
+                if let Some(client) = self.clients.get_mut(&event.uuid) {
+                    let newer = match client.acknowledged {
+                        Some(acknowledged) => data.snapshot > acknowledged,
+                        None => true,
+                    };
+                    if newer && client.history.get(data.snapshot).is_some() {
+                        client.acknowledged = Some(data.snapshot);
+
+                        let acknowledged = data.snapshot;
+                        client.despawning.retain(|_, since| *since > acknowledged);
+                    }
+                }
+            }
+        }
     }
 
     fn send_connection_response<'a>(
@@ -42,7 +135,11 @@ impl UpdateSender {
         };
         
         let op = Operation::SvConnectResponse(
-            operation::SvConnectResponse { uuid: *uuid }
+            operation::SvConnectResponse {
+                uuid: *uuid,
+                zone_origin: self.quantization.origin(),
+                params: self.params.clone(),
+            }
         );
 
         self.sender.unbounded_send((*uuid, op))
@@ -55,76 +152,152 @@ impl UpdateSender {
         }
     }
 
-    fn send_world_update<'a>(
+    fn send(&self, uuid: Uuid, op: Operation) {
+        self.sender.unbounded_send((uuid, op))
+            .unwrap_or_else(|err| {
+                log::error!("Failed to send update: {}", err);
+            });
+    }
+
+    fn world_view<'a>(
         &self,
         entities: &Entities<'a>,
+        appearances: &'a ReadStorage<'a, Appearance>,
         ids: &ReadStorage<'a, Id>,
         pos: &ReadStorage<'a, Position>,
         health: &ReadStorage<'a, Health>,
-        _clients: &mut WriteStorage<'a, Client>,
-        entity: Entity
-    ) {
-        let uuid = match ids.get(entity) {
-            Some(uuid) => &uuid.0,
-            None => {
-                log::error!("No UUID for client!");
-                return;
-            }
-        };
-        let mut updates = Vec::new();
+    ) -> Vec<EntityView<'a>>
+    {
+        (entities, ids, appearances).join()
+            .map(|(ent, id, appearance)| {
+                let mut components = Vec::new();
 
-        for (ent, id) in (entities, ids).join() {
-            let mut data = Vec::new();
-
-            if *uuid != id.0 {
                 if let Some(pos) = pos.get(ent) {
-                    data.push(operation::EntityComponent::Position(
-                        pos.0.clone()
+                    components.push(self.quantization.position(&pos.0));
+                }
+
+                if let Some(health) = health.get(ent) {
+                    components.push(operation::EntityComponent::Health(
+                        health.0
                     ));
                 }
-            }
 
-            if let Some(health) = health.get(ent) {
-                data.push(operation::EntityComponent::Health(
-                    health.0
+                EntityView {
+                    uuid: id.0,
+                    appearance,
+                    components,
+                }
+            })
+            .collect()
+    }
+
+    /**
+     * Sends the world to a client as a delta against its acknowledged
+     * snapshot. The client's own entity is included, so its prediction can
+     * be checked against the input tick it reflects.
+     */
+    fn send_world_update(
+        &mut self,
+        world: &[EntityView],
+        uuid: Uuid,
+        input_tick: Option<u64>,
+        snapshot_id: SnapshotId,
+    ) {
+        let client = self.clients.entry(uuid)
+            .or_insert_with(ClientSnapshots::new);
+
+        let mut snapshot = Snapshot::new();
+        let mut spawns = Vec::new();
+
+        for entity in world {
+            // repeated until a snapshot containing the entity is acknowledged
+            if uuid != entity.uuid && !client.has_spawned(&entity.uuid) {
+                client.spawned.insert(entity.uuid);
+                spawns.push(Operation::SvSpawnEntity(
+                    operation::SvSpawnEntity {
+                        uuid: entity.uuid,
+                        archetype: entity.appearance.archetype,
+                        model: entity.appearance.model.clone(),
+                        texture: entity.appearance.texture.clone(),
+                        components: entity.components.clone(),
+                    }
                 ));
             }
 
-            updates.push(operation::EntityUpdate {
-                uuid: id.0,
-                data,
-            });
+            snapshot.insert(entity.uuid, entity.components.clone());
         }
 
-        let op = Operation::SvUpdateWorld(
-            operation::SvUpdateWorld { updates }
-        );
-        self.sender.unbounded_send((*uuid, op))
-            .unwrap_or_else(|err| {
-                log::error!("Failed to send update: {}", err);
-            });
-        
+        let departed: Vec<Uuid> = client.spawned.iter()
+            .filter(|spawned| snapshot.get(spawned).is_none())
+            .cloned()
+            .collect();
+        for departed in departed {
+            client.spawned.remove(&departed);
+            client.despawning.insert(departed, snapshot_id);
+        }
+
+        // repeated until a snapshot taken after the departure is acknowledged
+        let despawns: Vec<Operation> = client.despawning.keys()
+            .map(|departed| Operation::SvDespawnEntity(
+                operation::SvDespawnEntity { uuid: *departed }
+            ))
+            .collect();
+
+        let (baseline, (updates, removed)) = match client.baseline() {
+            Some((id, baseline)) => (Some(id), snapshot.delta_from(Some(baseline))),
+            None => (None, snapshot.delta_from(None)),
+        };
+
+        client.history.push(snapshot_id, snapshot);
+
+        for op in despawns.into_iter().chain(spawns) {
+            self.send(uuid, op);
+        }
+
+        self.send(uuid, Operation::SvUpdateWorld(
+            operation::SvUpdateWorld {
+                snapshot: snapshot_id,
+                baseline,
+                input_tick,
+                updates,
+                removed,
+            }
+        ));
     }
 }
 
 impl<'a> System<'a> for UpdateSender {
     type SystemData = (
         Entities<'a>,
+        Read<'a, TickNumber>,
+        Read<'a, EventQueue>,
+        ReadStorage<'a, Appearance>,
         ReadStorage<'a, Id>,
         ReadStorage<'a, Position>,
         ReadStorage<'a, Health>,
+        ReadStorage<'a, PlayerInput>,
         WriteStorage<'a, Client>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
         let (
             entities,
+            tick,
+            events,
+            appearances,
             ids,
             pos,
             health,
+            inputs,
             mut clients,
         ) = data;
 
+        self.acknowledge_snapshots(&events);
+
+        // snapshots are tagged with their tick, which starts at 1
+        let snapshot_id = (tick.0 as SnapshotId).max(1);
+        let world = self.world_view(&entities, &appearances, &ids, &pos, &health);
+
         for ent in entities.join() {
             let state = {
                 match clients.get(ent) {
@@ -145,16 +318,28 @@ impl<'a> System<'a> for UpdateSender {
                     );
                 },
                 ClientState::Connected => {
+                    let uuid = match ids.get(ent) {
+                        Some(uuid) => uuid.0,
+                        None => {
+                            log::error!("No UUID for client!");
+                            continue;
+                        }
+                    };
+
                     self.send_world_update(
-                        &entities,
-                        &ids,
-                        &pos,
-                        &health,
-                        &mut clients,
-                        ent
+                        &world,
+                        uuid,
+                        inputs.get(ent).and_then(|input| input.tick),
+                        snapshot_id
                     );
                 },
             }
         }
+
+        // forget disconnected clients
+        let connected: HashSet<Uuid> = (&ids, &clients).join()
+            .map(|(id, _)| id.0)
+            .collect();
+        self.clients.retain(|uuid, _| connected.contains(uuid));
     }
 }
\ No newline at end of file
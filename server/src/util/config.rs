@@ -1,5 +1,9 @@
 use serde::{Serialize, Deserialize};
 
+use eternalreckoning_core::util::config::{
+    Validate,
+    Validator,
+};
 use eternalreckoning_core::util::logging::LoggingConfig;
 
 use crate::server::ServerConfig;
@@ -18,4 +22,10 @@ impl Default for Config {
             server: ServerConfig::default(),
         }
     }
+}
+
+impl Validate for Config {
+    fn validate(&self, validator: &mut Validator) {
+        validator.nested("server", &self.server);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,248 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::mpsc::{
+    channel,
+    Receiver,
+};
+use std::time::Instant;
+
+use futures::sync::mpsc::unbounded;
+use futures::{
+    Async,
+    Future,
+    Sink,
+    Stream,
+};
+use uuid::Uuid;
+
+use eternalreckoning_core::net::{
+    operation::{
+        self,
+        Operation,
+    },
+    transport::{
+        LoopbackNetwork,
+        LoopbackTransport,
+    },
+    PROTOCOL_VERSION,
+};
+use eternalreckoning_core::simulation::Simulation;
+use eternalreckoning_server::networking::{
+    NetStats,
+    ServerFuture,
+};
+use eternalreckoning_server::simulation::{
+    build_simulation,
+    Event,
+};
+use eternalreckoning_server::util::config::Config;
+
+/// Default config, with the terrain found from the test's directory
+fn config() -> Config {
+    let mut config = Config::default();
+    config.server.terrain.heightmap =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/terrain.bmp").to_string();
+    config
+}
+
+/// A server and its simulation on a loopback network, stepped by hand
+struct Harness {
+    network: LoopbackNetwork,
+    server_addr: SocketAddr,
+    server: ServerFuture<LoopbackTransport>,
+    net_stats: NetStats,
+    game: Simulation<'static, 'static, Event>,
+    inbound_rx: Receiver<(Uuid, Operation)>,
+}
+
+impl Harness {
+    fn new() -> Harness {
+        let network = LoopbackNetwork::new();
+        let server_addr = "10.0.0.1:6142".parse().unwrap();
+
+        let (outbound_tx, outbound_rx) = unbounded();
+        let (inbound_tx, inbound_rx) = channel();
+        let net_stats = NetStats::new();
+
+        Harness {
+            server: ServerFuture::new(
+                network.transport(server_addr),
+                inbound_tx,
+                outbound_rx,
+                net_stats.clone()
+            ),
+            game: build_simulation(outbound_tx, net_stats.clone(), &config().server).unwrap(),
+            net_stats,
+            network,
+            server_addr,
+            inbound_rx,
+        }
+    }
+
+    fn client(&self, index: u8) -> LoopbackTransport {
+        self.network.transport(SocketAddr::from(([10, 0, 1, index], 50000)))
+    }
+
+    /// Relays pending operations to the simulation and runs one tick
+    fn step(&mut self) {
+        assert!(self.server.poll().unwrap().is_not_ready());
+
+        while let Ok((uuid, op)) = self.inbound_rx.try_recv() {
+            self.game.push_event(Event { uuid, op });
+        }
+        self.game.next_tick(Instant::now());
+
+        assert!(self.server.poll().unwrap().is_not_ready());
+    }
+}
+
+fn connect(client: &mut LoopbackTransport, server: SocketAddr, protocol_version: u16, name: &str) {
+    client.start_send((Operation::ClConnectMessage(operation::ClConnectMessage {
+        protocol_version,
+        client_build: "loopback test".to_string(),
+        player_name: name.to_string(),
+    }), server)).unwrap();
+    client.poll_complete().unwrap();
+}
+
+fn receive(client: &mut LoopbackTransport) -> Vec<Operation> {
+    let mut ops = Vec::new();
+    while let Async::Ready(Some((op, _))) = client.poll().unwrap() {
+        ops.push(op);
+    }
+    ops
+}
+
+#[test]
+fn test_clients_connect_and_see_each_other() {
+    futures::future::lazy(|| {
+        let mut harness = Harness::new();
+        let mut clients: Vec<LoopbackTransport> = (1..=3)
+            .map(|index| harness.client(index))
+            .collect();
+
+        for (index, client) in clients.iter_mut().enumerate() {
+            connect(client, harness.server_addr, PROTOCOL_VERSION, &format!("player{}", index));
+        }
+        harness.step();
+        harness.step();
+
+        let mut uuids = HashSet::new();
+        let mut received = Vec::new();
+        for client in clients.iter_mut() {
+            let ops = receive(client);
+            let uuid = match ops.first() {
+                Some(Operation::SvConnectResponse(data)) => data.uuid,
+                _ => panic!("expected a connect response"),
+            };
+            uuids.insert(uuid);
+            received.push((uuid, ops));
+        }
+        assert_eq!(uuids.len(), 3);
+
+        // every client is told about the other players, but not itself
+        for (uuid, ops) in received {
+            let mut others = uuids.clone();
+            others.remove(&uuid);
+
+            let spawned: HashSet<Uuid> = ops.iter()
+                .filter_map(|op| match op {
+                    Operation::SvSpawnEntity(data) => Some(data.uuid),
+                    _ => None,
+                })
+                .collect();
+            assert_eq!(spawned, others);
+
+            assert!(ops.iter().any(|op| match op {
+                Operation::SvUpdateWorld(data) => data.updates.len() == 3,
+                _ => false,
+            }));
+        }
+
+        Ok::<(), ()>(())
+    }).wait().unwrap();
+}
+
+#[test]
+fn test_version_mismatch_is_rejected() {
+    futures::future::lazy(|| {
+        let mut harness = Harness::new();
+        let mut client = harness.client(1);
+
+        connect(&mut client, harness.server_addr, PROTOCOL_VERSION + 1, "player");
+        harness.step();
+
+        match receive(&mut client).first() {
+            Some(Operation::SvConnectRejected(data)) => {
+                assert_eq!(data.reason, operation::RejectReason::VersionMismatch);
+            },
+            _ => panic!("expected a rejection"),
+        }
+
+        // the rejected peer is forgotten, so a new connection from the same
+        // address starts over
+        drop(client);
+        let mut client = harness.client(1);
+        connect(&mut client, harness.server_addr, PROTOCOL_VERSION, "player");
+        harness.step();
+
+        match receive(&mut client).first() {
+            Some(Operation::SvConnectResponse(_)) => (),
+            _ => panic!("expected a connect response"),
+        }
+
+        Ok::<(), ()>(())
+    }).wait().unwrap();
+}
+
+#[test]
+fn test_traffic_counters() {
+    futures::future::lazy(|| {
+        let mut harness = Harness::new();
+        let mut client = harness.client(1);
+
+        connect(&mut client, harness.server_addr, PROTOCOL_VERSION, "player");
+        harness.step();
+        harness.step();
+
+        let uuid = match receive(&mut client).first() {
+            Some(Operation::SvConnectResponse(data)) => data.uuid,
+            _ => panic!("expected a connect response"),
+        };
+
+        let stats = harness.net_stats.get(&uuid).unwrap();
+        assert_eq!(stats.packets_in, 1);
+        assert_eq!(stats.opcodes_in.get(&operation::ClConnectMessage::OPCODE), Some(&1));
+        assert_eq!(stats.opcodes_out.get(&operation::SvConnectResponse::OPCODE), Some(&1));
+        assert!(stats.packets_out >= 2);
+        assert!(stats.bytes_out > stats.bytes_in);
+        assert!(stats.last_seen.is_some());
+        assert_eq!(harness.net_stats.all().len(), 1);
+
+        Ok::<(), ()>(())
+    }).wait().unwrap();
+}
+
+#[test]
+fn test_shutdown_disconnects_clients() {
+    futures::future::lazy(|| {
+        let mut harness = Harness::new();
+        let mut client = harness.client(1);
+
+        connect(&mut client, harness.server_addr, PROTOCOL_VERSION, "player");
+        harness.step();
+        receive(&mut client);
+
+        // disposing the simulation hangs up, after which the server closes
+        let Harness { mut server, game, .. } = harness;
+        game.dispose();
+        assert!(server.poll().unwrap().is_ready());
+
+        match receive(&mut client).last() {
+            Some(Operation::DisconnectMessage) => (),
+            _ => panic!("expected a disconnect"),
+        }
+
+        Ok::<(), ()>(())
+    }).wait().unwrap();
+}
\ No newline at end of file
@@ -0,0 +1,309 @@
+use std::time::Duration;
+
+use futures::sync::mpsc::{
+    unbounded,
+    UnboundedReceiver,
+};
+use futures::{
+    Async,
+    Future,
+    Stream,
+};
+use specs::{
+    Join,
+    World,
+    WorldExt,
+};
+use uuid::Uuid;
+
+use eternalreckoning_core::net::{
+    operation::{
+        self,
+        Operation,
+    },
+    PROTOCOL_VERSION,
+};
+use eternalreckoning_core::simulation::{
+    movement::MoveKeys,
+    SimulatedClock,
+    Simulation,
+    TickLength,
+    WorldSnapshot,
+};
+use eternalreckoning_server::networking::NetStats;
+use eternalreckoning_server::simulation::{
+    build_simulation,
+    snapshotter,
+    component::{
+        Id,
+        Jump,
+        Movement,
+        Name,
+        Position,
+        Velocity,
+    },
+    Event,
+};
+use eternalreckoning_server::util::config::Config;
+
+/// Default config, with the terrain found from the test's directory
+fn config() -> Config {
+    let mut config = Config::default();
+    config.server.terrain.heightmap =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/../assets/terrain.bmp").to_string();
+    config
+}
+
+/// A server simulation stepped on a simulated clock, without networking
+struct Scenario {
+    game: Simulation<'static, 'static, Event>,
+    clock: SimulatedClock,
+    outbound_rx: UnboundedReceiver<(Uuid, Operation)>,
+}
+
+impl Scenario {
+    fn new(config: &Config) -> Scenario {
+        let config = &config.server;
+        let (outbound_tx, outbound_rx) = unbounded();
+
+        Scenario {
+            game: build_simulation(outbound_tx, NetStats::new(), config).unwrap(),
+            clock: SimulatedClock::new(Duration::from_secs(1) / config.tick_rate as u32),
+            outbound_rx,
+        }
+    }
+
+    fn step(&mut self, events: Vec<(Uuid, Operation)>) {
+        let events = events.into_iter().map(|(uuid, op)| Event { uuid, op });
+        self.game.step(&mut self.clock, events);
+    }
+
+    fn connect(&mut self, name: &str) -> Uuid {
+        let uuid = Uuid::new_v4();
+        self.step(vec![(uuid, Operation::ClConnectMessage(operation::ClConnectMessage {
+            protocol_version: PROTOCOL_VERSION,
+            client_build: "scenario test".to_string(),
+            player_name: name.to_string(),
+        }))]);
+        uuid
+    }
+
+    /// Operations sent to `uuid` since the last call
+    fn sent_to(&mut self, uuid: Uuid) -> Vec<Operation> {
+        let outbound_rx = &mut self.outbound_rx;
+        futures::future::lazy(|| {
+            let mut ops = Vec::new();
+            while let Ok(Async::Ready(Some((to, op)))) = outbound_rx.poll() {
+                if to == uuid {
+                    ops.push(op);
+                }
+            }
+            Ok::<_, ()>(ops)
+        }).wait().unwrap()
+    }
+
+    fn position(&self, uuid: Uuid) -> Option<nalgebra::Point3<f64>> {
+        let world = self.game.world();
+        let ids = world.read_storage::<Id>();
+        let positions = world.read_storage::<Position>();
+
+        (&ids, &positions).join()
+            .find(|(id, _)| id.0 == uuid)
+            .map(|(_, pos)| pos.0)
+    }
+}
+
+#[test]
+fn test_connect_creates_player() {
+    let mut scenario = Scenario::new(&config());
+    let uuid = scenario.connect(" player ");
+
+    assert_eq!(scenario.game.tick().0, 1);
+    assert_eq!(scenario.position(uuid), Some(nalgebra::Point3::origin()));
+
+    let world = scenario.game.world();
+    let names: Vec<String> = world.read_storage::<Name>()
+        .join()
+        .map(|name| name.0.clone())
+        .collect();
+    assert_eq!(names, vec!["player".to_string()]);
+
+    // the client is told how the server moves players, per second
+    let config = config();
+    let params = scenario.sent_to(uuid).into_iter()
+        .find_map(|op| match op {
+            Operation::SvConnectResponse(data) => Some(data.params),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(params.movement_speed, config.server.movement_speed);
+    assert_eq!(params.physics, config.server.physics);
+}
+
+#[test]
+fn test_connection_rejected() {
+    let mut config = config();
+    config.server.banned_players = vec!["Griefer".to_string()];
+    let mut scenario = Scenario::new(&config);
+
+    let uuid = scenario.connect("griefer");
+    match scenario.sent_to(uuid).first() {
+        Some(Operation::SvConnectRejected(data)) => {
+            assert_eq!(data.reason, operation::RejectReason::Banned);
+        },
+        _ => panic!("expected a rejection"),
+    }
+    assert_eq!(scenario.position(uuid), None);
+
+    let uuid = scenario.connect("\u{7}");
+    match scenario.sent_to(uuid).first() {
+        Some(Operation::SvConnectRejected(data)) => {
+            assert_eq!(data.reason, operation::RejectReason::InvalidName);
+        },
+        _ => panic!("expected a rejection"),
+    }
+    assert_eq!(scenario.position(uuid), None);
+}
+
+#[test]
+fn test_client_timeout() {
+    let mut config = config();
+    config.server.tick_rate = 10;
+    config.server.client_ttl_ms = 500;
+    let mut scenario = Scenario::new(&config);
+
+    let active = scenario.connect("active");
+    let idle = scenario.connect("idle");
+
+    // only the active client keeps talking; the idle one connected on tick 2
+    // and is dropped on tick 7, 500ms later
+    for _ in 0..10 {
+        let sync = Operation::ClSync(operation::ClSync { client_time: 0 });
+        scenario.step(vec![(active, sync)]);
+    }
+
+    assert!(scenario.position(active).is_some());
+    assert_eq!(scenario.position(idle), None);
+
+    // the active client last spoke on tick 12
+    scenario.game.step_ticks(&mut scenario.clock, 4);
+    assert!(scenario.position(active).is_some());
+    scenario.game.step_ticks(&mut scenario.clock, 1);
+    assert_eq!(scenario.position(active), None);
+}
+
+#[test]
+fn test_player_movement() {
+    let config = config();
+    let tick_length = TickLength(Duration::from_secs(1) / config.server.tick_rate as u32);
+    let speed = tick_length.scale_to(config.server.movement_speed);
+    let mut scenario = Scenario::new(&config);
+
+    let uuid = scenario.connect("runner");
+    scenario.step(vec![(uuid, Operation::ClInput(operation::ClInput {
+        tick: 1,
+        keys: MoveKeys::FORWARD,
+        jump: false,
+        yaw: 0,
+    }))]);
+    scenario.game.step_ticks(&mut scenario.clock, 9);
+
+    // the input is held for the tick it arrived on and the 9 after it
+    let moved = scenario.position(uuid).unwrap() - nalgebra::Point3::origin();
+    assert!((nalgebra::Vector2::new(moved.x, moved.z).norm() - speed * 10.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_own_position_reconciled() {
+    let mut scenario = Scenario::new(&config());
+    let uuid = scenario.connect("runner");
+
+    scenario.step(vec![(uuid, Operation::ClInput(operation::ClInput {
+        tick: 42,
+        keys: MoveKeys::FORWARD,
+        jump: false,
+        yaw: 0,
+    }))]);
+
+    let update = scenario.sent_to(uuid).into_iter()
+        .rev()
+        .find_map(|op| match op {
+            Operation::SvUpdateWorld(data) => Some(data),
+            _ => None,
+        })
+        .expect("expected a world update");
+    assert_eq!(update.input_tick, Some(42));
+
+    let own = update.updates.iter()
+        .find(|update| update.uuid == uuid)
+        .expect("expected the player's own entity");
+    let position = own.data.iter()
+        .find_map(|component| match component {
+            operation::EntityComponent::QuantizedPosition(data) => {
+                Some(data.dequantize(&nalgebra::Point3::origin()))
+            },
+            _ => None,
+        })
+        .expect("expected the player's own position");
+    assert!((position - scenario.position(uuid).unwrap()).norm() < 0.01);
+}
+
+#[test]
+fn test_player_jump() {
+    let mut config = config();
+    config.server.client_ttl_ms = 10000;
+    let mut scenario = Scenario::new(&config);
+
+    let uuid = scenario.connect("jumper");
+    scenario.game.step_ticks(&mut scenario.clock, 60);
+
+    // gravity pulls the player down onto the terrain, where they stay
+    let rest = scenario.position(uuid).unwrap();
+    assert!(rest.y > 1.0);
+    scenario.game.step_ticks(&mut scenario.clock, 10);
+    assert!((scenario.position(uuid).unwrap().y - rest.y).abs() < 0.1);
+
+    let jump = |tick, jump| (uuid, Operation::ClInput(operation::ClInput {
+        tick,
+        keys: 0,
+        jump,
+        yaw: 0,
+    }));
+    // resting contact with the ground only registers every few ticks
+    scenario.step(vec![jump(1, true)]);
+    scenario.game.step_ticks(&mut scenario.clock, 2);
+    scenario.step(vec![jump(2, false)]);
+    scenario.game.step_ticks(&mut scenario.clock, 5);
+    assert!(scenario.position(uuid).unwrap().y < rest.y - 0.5);
+
+    scenario.game.step_ticks(&mut scenario.clock, 120);
+    assert!((scenario.position(uuid).unwrap().y - rest.y).abs() < 0.1);
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let mut scenario = Scenario::new(&config());
+    let uuid = scenario.connect("player");
+
+    let text = snapshotter().save(scenario.game.world()).unwrap()
+        .to_string()
+        .unwrap();
+
+    let mut world = World::new();
+    let snapshot: WorldSnapshot = text.parse().unwrap();
+    snapshotter().restore(&snapshot, &mut world).unwrap();
+
+    let ids = world.read_storage::<Id>();
+    let names = world.read_storage::<Name>();
+    let positions = world.read_storage::<Position>();
+    let restored: Vec<_> = (&ids, &names, &positions).join()
+        .map(|(id, name, pos)| (id.0, name.0.clone(), pos.0))
+        .collect();
+    assert_eq!(restored, vec![(uuid, "player".to_string(), nalgebra::Point3::origin())]);
+
+    // players keep moving the same way once restored
+    let movements = world.read_storage::<Movement>();
+    let jumps = world.read_storage::<Jump>();
+    let velocities = world.read_storage::<Velocity>();
+    assert_eq!((&ids, &movements, &jumps, &velocities).join().count(), 1);
+}
\ No newline at end of file